@@ -0,0 +1,223 @@
+//! A self-describing binary encoding for storing values long term, independent of the
+//! limb size used in memory.
+//!
+//! An archive is, in order:
+//!
+//! - the magic bytes `ARCHIVE_MAGIC`,
+//! - a version byte, `ARCHIVE_VERSION`,
+//! - a sign byte, 0 for nonnegative values and 1 for negative ones,
+//! - a limb width byte, the number of bytes per limb of the payload: 1, 2, 4 or 8,
+//! - the number of limbs, as a little-endian u64,
+//! - the payload, the limbs least significant first, each one little-endian,
+//! - a CRC-32 (the IEEE polynomial, as in zlib) of all the bytes above, little-endian.
+//!
+//! The writers use the current limb width, 4 bytes, and no trailing zero limbs. The
+//! readers accept every width listed, so archives written after a change of the limb
+//! size remain readable.
+
+use crate::optimized_memory::BigInt;
+use crate::signed::BigInt as SignedBigInt;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// The first bytes of every archive.
+pub const ARCHIVE_MAGIC: [u8; 4] = *b"BIGZ";
+
+/// The version written by `to_archive`, and the most recent one `from_archive` reads.
+pub const ARCHIVE_VERSION: u8 = 1;
+
+/// Number of bytes before the payload.
+const HEADER_LEN: usize = 4 + 1 + 1 + 1 + 8;
+
+/// The error returned when bytes are not a valid archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveError {
+    reason: &'static str,
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid BigInt archive: {}", self.reason)
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl BigInt {
+    /// The archive of the value, described in the module documentation.
+    pub fn to_archive(&self) -> Vec<u8> {
+        encode(false, self.as_limbs_le())
+    }
+
+    /// Read a value written by `to_archive`, or by `signed::BigInt::to_archive` if it is
+    /// not negative.
+    pub fn from_archive(bytes: &[u8]) -> Result<BigInt, ArchiveError> {
+        match decode(bytes)? {
+            (true, magnitude) if !magnitude.is_zero() => Err(ArchiveError {
+                reason: "negative value",
+            }),
+            (_, magnitude) => Ok(magnitude),
+        }
+    }
+}
+
+impl SignedBigInt {
+    /// The archive of the value, described in the module documentation.
+    pub fn to_archive(&self) -> Vec<u8> {
+        encode(self.is_negative(), self.magnitude().as_limbs_le())
+    }
+
+    /// Read a value written by `to_archive` on either type.
+    pub fn from_archive(bytes: &[u8]) -> Result<SignedBigInt, ArchiveError> {
+        let (negative, magnitude) = decode(bytes)?;
+        Ok(SignedBigInt::new(negative, magnitude))
+    }
+}
+
+/// Helper function for `to_archive`.
+fn encode(negative: bool, limbs: &[u32]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + 4 * limbs.len() + 4);
+    bytes.extend_from_slice(&ARCHIVE_MAGIC);
+    bytes.push(ARCHIVE_VERSION);
+    bytes.push(negative as u8);
+    bytes.push(4);
+    bytes.extend_from_slice(&(limbs.len() as u64).to_le_bytes());
+    for limb in limbs {
+        bytes.extend_from_slice(&limb.to_le_bytes());
+    }
+    let checksum = crc32(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+/// Helper function for `from_archive`: the sign and the magnitude.
+fn decode(bytes: &[u8]) -> Result<(bool, BigInt), ArchiveError> {
+    let error = |reason| Err(ArchiveError { reason });
+    if bytes.len() < HEADER_LEN + 4 || bytes[..4] != ARCHIVE_MAGIC {
+        return error("missing header");
+    }
+    let (content, checksum) = bytes.split_at(bytes.len() - 4);
+    if crc32(content).to_le_bytes() != checksum {
+        return error("checksum mismatch");
+    }
+    if content[4] == 0 || content[4] > ARCHIVE_VERSION {
+        return error("unsupported version");
+    }
+    let negative = match content[5] {
+        0 => false,
+        1 => true,
+        _ => return error("invalid sign"),
+    };
+    let width = content[6] as usize;
+    if ![1, 2, 4, 8].contains(&width) {
+        return error("unsupported limb width");
+    }
+    let mut len = [0; 8];
+    len.copy_from_slice(&content[7..HEADER_LEN]);
+    let payload = &content[HEADER_LEN..];
+    let expected = usize::try_from(u64::from_le_bytes(len))
+        .ok()
+        .and_then(|len| len.checked_mul(width));
+    if expected != Some(payload.len()) {
+        return error("length mismatch");
+    }
+    // The payload is the value in little-endian bytes whatever the width.
+    let limbs: Vec<u32> = payload
+        .chunks(4)
+        .map(|chunk| chunk.iter().rev().fold(0, |limb, &b| limb << 8 | b as u32))
+        .collect();
+    Ok((negative, BigInt::from_limbs_le(&limbs)))
+}
+
+/// The CRC-32 of `bytes` with the reflected IEEE polynomial, one bit at a time.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(0, crc32(b""));
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let values = [
+            BigInt::zero(),
+            BigInt::one(),
+            BigInt::from(u64::MAX),
+            BigInt::ten().pow(100),
+        ];
+        for value in values.iter() {
+            assert_eq!(Ok(value.clone()), BigInt::from_archive(&value.to_archive()));
+            let negative = -SignedBigInt::from(value.clone());
+            assert_eq!(
+                Ok(negative.clone()),
+                SignedBigInt::from_archive(&negative.to_archive())
+            );
+        }
+        let bytes = BigInt::from(258u32).to_archive();
+        assert_eq!(
+            vec![b'B', b'I', b'G', b'Z', 1, 0, 4, 1, 0, 0, 0, 0, 0, 0, 0, 2, 1, 0, 0],
+            bytes[..bytes.len() - 4].to_vec()
+        );
+    }
+
+    #[test]
+    fn test_other_widths() {
+        let value = BigInt::from(0x0102_0304_0506_0708u64);
+        let payload = 0x0102_0304_0506_0708u64.to_le_bytes();
+        for &width in [1usize, 2, 4, 8].iter() {
+            let mut bytes = ARCHIVE_MAGIC.to_vec();
+            bytes.extend_from_slice(&[1, 0, width as u8]);
+            bytes.extend_from_slice(&((8 / width) as u64).to_le_bytes());
+            bytes.extend_from_slice(&payload);
+            let checksum = crc32(&bytes);
+            bytes.extend_from_slice(&checksum.to_le_bytes());
+            assert_eq!(Ok(value.clone()), BigInt::from_archive(&bytes), "{}", width);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let bytes = BigInt::from(12345u32).to_archive();
+        let reason = |bytes: &[u8]| BigInt::from_archive(bytes).unwrap_err().reason;
+        assert_eq!("missing header", reason(&bytes[..10]));
+        assert_eq!("missing header", reason(b"NOPE0000000000000000"));
+
+        let mut corrupted = bytes.clone();
+        corrupted[HEADER_LEN] ^= 1;
+        assert_eq!("checksum mismatch", reason(&corrupted));
+
+        let rewrite = |index: usize, b: u8| {
+            let mut bytes = bytes.clone();
+            bytes[index] = b;
+            let len = bytes.len() - 4;
+            let checksum = crc32(&bytes[..len]);
+            bytes[len..].copy_from_slice(&checksum.to_le_bytes());
+            bytes
+        };
+        assert_eq!("unsupported version", reason(&rewrite(4, 2)));
+        assert_eq!("unsupported version", reason(&rewrite(4, 0)));
+        assert_eq!("invalid sign", reason(&rewrite(5, 2)));
+        assert_eq!("negative value", reason(&rewrite(5, 1)));
+        assert_eq!("unsupported limb width", reason(&rewrite(6, 3)));
+        assert_eq!("length mismatch", reason(&rewrite(6, 8)));
+        assert_eq!("length mismatch", reason(&rewrite(14, 1)));
+
+        let error = BigInt::from_archive(&[]).unwrap_err();
+        assert_eq!("invalid BigInt archive: missing header", error.to_string());
+    }
+}
@@ -0,0 +1,171 @@
+//! Element-wise operations over whole slices of BigInts, for workloads that touch many
+//! values per step. The destination values keep their storage from one call to the next,
+//! and with the `rayon` feature long slices are split between threads.
+//!
+//! `dot` is the kernel of exact linear algebra: all the products are accumulated into a
+//! single buffer of limbs.
+
+use crate::limb_ops;
+use crate::optimized_memory::BigInt;
+
+/// Slices shorter than this are processed on the calling thread even with `rayon`.
+pub const PARALLEL_MIN_LEN: usize = 1024;
+
+/// `dst[i] = a[i] + b[i]` for every `i`.
+///
+/// Panics if the slices do not all have the same length.
+pub fn add_slices(dst: &mut [BigInt], a: &[BigInt], b: &[BigInt]) {
+    assert!(
+        dst.len() == a.len() && a.len() == b.len(),
+        "slices must have the same length"
+    );
+    let add = |(d, (x, y)): (&mut BigInt, (&BigInt, &BigInt))| {
+        d.assign(x);
+        d.add_in_place(y);
+    };
+    #[cfg(feature = "rayon")]
+    if dst.len() >= PARALLEL_MIN_LEN {
+        use rayon::prelude::*;
+
+        dst.par_iter_mut()
+            .zip(a.par_iter().zip(b.par_iter()))
+            .for_each(add);
+        return;
+    }
+    dst.iter_mut().zip(a.iter().zip(b.iter())).for_each(add);
+}
+
+/// `dst[i] = src[i] * k` for every `i`.
+///
+/// Panics if the slices do not have the same length.
+pub fn mul_scalar_slice(dst: &mut [BigInt], src: &[BigInt], k: u32) {
+    assert_eq!(dst.len(), src.len(), "slices must have the same length");
+    let mul = |(d, x): (&mut BigInt, &BigInt)| {
+        if k == 0 {
+            d.clear();
+        } else {
+            d.assign(x);
+            d.mul_add_limb(k, 0);
+        }
+    };
+    #[cfg(feature = "rayon")]
+    if dst.len() >= PARALLEL_MIN_LEN {
+        use rayon::prelude::*;
+
+        dst.par_iter_mut().zip(src.par_iter()).for_each(mul);
+        return;
+    }
+    dst.iter_mut().zip(src.iter()).for_each(mul);
+}
+
+/// The sum of `a[i] * b[i]`, or zero for empty slices.
+///
+/// Every partial product `a[i] * b[i][j]` is added straight into one accumulator, wide
+/// enough for the largest product plus the carries of any number of terms, so nothing is
+/// allocated per element.
+///
+/// Panics if the slices do not have the same length.
+pub fn dot(a: &[BigInt], b: &[BigInt]) -> BigInt {
+    assert_eq!(a.len(), b.len(), "slices must have the same length");
+    let width = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| x.as_limbs_le().len() + y.as_limbs_le().len())
+        .max()
+        .unwrap_or(0);
+    // Two more limbs hold the sum of up to 2^64 products.
+    let mut total = vec![0u32; width + 2];
+    for (x, y) in a.iter().zip(b) {
+        let (mut x, mut y) = (x.as_limbs_le(), y.as_limbs_le());
+        if x.len() < y.len() {
+            std::mem::swap(&mut x, &mut y);
+        }
+        for (j, &limb) in y.iter().enumerate() {
+            let carry = limb_ops::addmul_1(&mut total[j..j + x.len()], x, limb);
+            propagate_carry(&mut total[j + x.len()..], carry);
+        }
+    }
+    BigInt::from_limbs_le(&total)
+}
+
+/// Helper function for `dot`: add `carry` to the value in `limbs`, which has room for it.
+fn propagate_carry(limbs: &mut [u32], mut carry: u32) {
+    for limb in limbs.iter_mut() {
+        if carry == 0 {
+            return;
+        }
+        let (sum, overflow) = limb.overflowing_add(carry);
+        *limb = sum;
+        carry = overflow as u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn values(len: usize, seed: u32) -> Vec<BigInt> {
+        (0..len)
+            .map(|i| (BigInt::from(i as u32 * seed + 1) << (i % 97)).add(&BigInt::from(seed)))
+            .collect()
+    }
+
+    #[test]
+    fn test_add_slices() {
+        for &len in [0, 5, PARALLEL_MIN_LEN + 3].iter() {
+            let (a, b) = (values(len, 7), values(len, 1_000_003));
+            let mut dst = values(len, 11);
+            add_slices(&mut dst, &a, &b);
+            let expected: Vec<BigInt> = a.iter().zip(b.iter()).map(|(x, y)| x.add(y)).collect();
+            assert_eq!(expected, dst);
+
+            // Reusing the destination from a larger sum.
+            add_slices(&mut dst, &a, &vec![BigInt::zero(); len]);
+            assert_eq!(a, dst);
+        }
+    }
+
+    #[test]
+    fn test_mul_scalar_slice() {
+        for &len in [0, 5, PARALLEL_MIN_LEN + 3].iter() {
+            let src = values(len, 13);
+            let mut dst = values(len, 17);
+            for &k in [u32::MAX, 3, 1, 0].iter() {
+                let capacities: Vec<usize> = dst.iter().map(BigInt::heap_size).collect();
+                mul_scalar_slice(&mut dst, &src, k);
+                if k == 0 {
+                    let kept: Vec<usize> = dst.iter().map(BigInt::heap_size).collect();
+                    assert_eq!(capacities, kept);
+                }
+                let k = BigInt::from(k);
+                let expected: Vec<BigInt> = src.iter().map(|x| x.mul(&k)).collect();
+                assert_eq!(expected, dst);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dot() {
+        assert_eq!(BigInt::zero(), dot(&[], &[]));
+        let max = (BigInt::one() << 320).sub(&BigInt::one());
+        let (a, b) = (vec![max.clone(); 300], vec![max.clone(); 300]);
+        assert_eq!(max.mul(&max).mul(&BigInt::from(300u32)), dot(&a, &b));
+
+        let (a, b) = (values(50, 3), values(50, 1_000_003));
+        let mut b = b;
+        b[7] = BigInt::zero();
+        b[8] = BigInt::from(5u32);
+        let expected = a
+            .iter()
+            .zip(b.iter())
+            .fold(BigInt::zero(), |sum, (x, y)| sum.add(&x.mul(y)));
+        assert_eq!(expected, dot(&a, &b));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_add_slices_length_mismatch() {
+        add_slices(&mut values(3, 1), &values(3, 2), &values(2, 3));
+    }
+}
@@ -0,0 +1,295 @@
+//! Command-line calculator on signed BigInts.
+//!
+//! Evaluates expressions with `+ - * / % ^`, parentheses, decimal and `0x` hexadecimal
+//! literals, and variables assigned with `name = expression`. Division and remainder
+//! truncate towards zero. The expression is taken from the arguments if there are any,
+//! otherwise each line of the standard input is evaluated in turn.
+
+use bigint::signed::BigInt;
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::process;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(BigInt),
+    Ident(String),
+    Op(char),
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+            let literal: String = chars[start..i].iter().collect();
+            tokens.push(Token::Number(parse_number(&literal)?));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if "+-*/%^()=".contains(c) {
+            tokens.push(Token::Op(c));
+            i += 1;
+        } else {
+            return Err(format!("unexpected character '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_number(literal: &str) -> Result<BigInt, String> {
+    let (digits, radix) = match literal
+        .strip_prefix("0x")
+        .or_else(|| literal.strip_prefix("0X"))
+    {
+        Some(hex) => (hex, 16),
+        None => (literal, 10),
+    };
+    if digits.is_empty() {
+        return Err(format!("invalid number '{}'", literal));
+    }
+    let radix_value = BigInt::from(radix as i32);
+    let mut value = BigInt::zero();
+    for c in digits.chars() {
+        let digit = c
+            .to_digit(radix)
+            .ok_or_else(|| format!("invalid number '{}'", literal))?;
+        value = value.mul(&radix_value).add(&BigInt::from(digit as i32));
+    }
+    Ok(value)
+}
+
+/// Recursive descent evaluator. From lowest to highest precedence: `+ -`, `* / %`,
+/// unary minus, then `^`, which is right associative.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    variables: &'a HashMap<String, BigInt>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        self.position += 1;
+        self.tokens.get(self.position - 1)
+    }
+
+    fn expression(&mut self) -> Result<BigInt, String> {
+        let mut value = self.term()?;
+        while let Some(&Token::Op(op @ ('+' | '-'))) = self.peek() {
+            self.position += 1;
+            let rhs = self.term()?;
+            value = if op == '+' {
+                value.add(&rhs)
+            } else {
+                value.sub(&rhs)
+            };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<BigInt, String> {
+        let mut value = self.unary()?;
+        while let Some(&Token::Op(op @ ('*' | '/' | '%'))) = self.peek() {
+            self.position += 1;
+            let rhs = self.unary()?;
+            value = match op {
+                '*' => value.mul(&rhs),
+                _ if rhs.is_zero() => return Err("division by zero".to_string()),
+                '/' => value.div(&rhs),
+                _ => value.rem(&rhs),
+            };
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<BigInt, String> {
+        if let Some(Token::Op('-')) = self.peek() {
+            self.position += 1;
+            return Ok(-self.unary()?);
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<BigInt, String> {
+        let base = self.atom()?;
+        if let Some(Token::Op('^')) = self.peek() {
+            self.position += 1;
+            let exponent = self.unary()?;
+            if exponent.is_negative() {
+                return Err("negative exponent".to_string());
+            }
+            let exponent = exponent
+                .magnitude()
+                .to_u32()
+                .ok_or_else(|| "exponent too large".to_string())?;
+            let magnitude = base.magnitude().pow(exponent);
+            return Ok(BigInt::new(
+                base.is_negative() && exponent % 2 == 1,
+                magnitude,
+            ));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<BigInt, String> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(n.clone()),
+            Some(Token::Ident(name)) => self
+                .variables
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("unknown variable '{}'", name)),
+            Some(Token::Op('(')) => {
+                let value = self.expression()?;
+                match self.next() {
+                    Some(Token::Op(')')) => Ok(value),
+                    _ => Err("expected ')'".to_string()),
+                }
+            }
+            Some(token) => Err(format!("unexpected {}", describe(token))),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(n) => format!("number {}", n),
+        Token::Ident(name) => format!("name '{}'", name),
+        Token::Op(c) => format!("'{}'", c),
+    }
+}
+
+/// Evaluate one line, either an expression or an assignment `name = expression`, which
+/// also stores the value.
+fn evaluate(line: &str, variables: &mut HashMap<String, BigInt>) -> Result<BigInt, String> {
+    let tokens = tokenize(line)?;
+    let (target, expression) = match tokens.as_slice() {
+        [Token::Ident(name), Token::Op('='), rest @ ..] => (Some(name.clone()), rest),
+        _ => (None, tokens.as_slice()),
+    };
+    let mut parser = Parser {
+        tokens: expression,
+        position: 0,
+        variables,
+    };
+    let value = parser.expression()?;
+    if let Some(token) = parser.peek() {
+        return Err(format!("unexpected {}", describe(token)));
+    }
+    if let Some(name) = target {
+        variables.insert(name, value.clone());
+    }
+    Ok(value)
+}
+
+fn main() {
+    let mut variables = HashMap::new();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match evaluate(&args.join(" "), &mut variables) {
+            Ok(value) => println!("{}", value),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let mut failed = false;
+    for line in io::stdin().lock().lines() {
+        let line = line.unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        });
+        if line.trim().is_empty() {
+            continue;
+        }
+        match evaluate(&line, &mut variables) {
+            Ok(value) => println!("{}", value),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn eval(line: &str) -> Result<String, String> {
+        evaluate(line, &mut HashMap::new()).map(|v| v.to_string())
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(Ok("7".to_string()), eval("1 + 2 * 3"));
+        assert_eq!(Ok("9".to_string()), eval("(1 + 2) * 3"));
+        assert_eq!(Ok("-3".to_string()), eval("-7 / 2"));
+        assert_eq!(Ok("-1".to_string()), eval("-7 % 2"));
+        assert_eq!(Ok("2".to_string()), eval("10 - 4 - 4"));
+        assert_eq!(Ok("-4".to_string()), eval("-2^2"));
+        assert_eq!(Ok("-8".to_string()), eval("(-2)^3"));
+        assert_eq!(Ok("256".to_string()), eval("2^2^3"));
+        assert_eq!(
+            Ok("340282366920938463463374607431768211456".to_string()),
+            eval("2^128")
+        );
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(Ok("255".to_string()), eval("0xff"));
+        assert_eq!(Ok("4294967296".to_string()), eval("0XFFFFFFFF + 1"));
+        assert!(eval("0x").is_err());
+        assert!(eval("12ab").is_err());
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut variables = HashMap::new();
+        assert_eq!(
+            Ok(BigInt::from(100i32)),
+            evaluate("x = 10^2", &mut variables)
+        );
+        assert_eq!(Ok(BigInt::from(99i32)), evaluate("x - 1", &mut variables));
+        assert_eq!(
+            Ok(BigInt::from(200i32)),
+            evaluate("y_2 = x * 2", &mut variables)
+        );
+        assert!(evaluate("z + 1", &mut variables).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Err("division by zero".to_string()), eval("1 / (2 - 2)"));
+        assert_eq!(Err("division by zero".to_string()), eval("1 % 0"));
+        assert_eq!(Err("negative exponent".to_string()), eval("2 ^ -1"));
+        assert_eq!(Err("expected ')'".to_string()), eval("(1 + 2"));
+        assert_eq!(Err("unexpected ')'".to_string()), eval("1 + 2)"));
+        assert_eq!(Err("unexpected character '$'".to_string()), eval("1 $ 2"));
+        assert_eq!(Err("unexpected end of expression".to_string()), eval("1 +"));
+    }
+}
@@ -0,0 +1,183 @@
+//! Primality certificates: proofs that a number is prime which anyone can check with a
+//! few modular exponentiations, unlike the answer of a probabilistic test.
+//!
+//! The certificates follow Pocklington's theorem. If `n - 1 = F * R` with `F > sqrt(n)`,
+//! and for each prime `q` dividing `F` some witness `a` has `a^(n-1) = 1 (mod n)` and
+//! `gcd(a^((n-1)/q) - 1, n) = 1`, then `n` is prime. The primes `q` carry certificates
+//! of their own, down to values small enough for trial division. When `F = n - 1` this
+//! is a Pratt certificate.
+
+use crate::optimized_memory::BigInt;
+
+/// Values below this bound are certified by trial division alone.
+pub const TRIAL_DIVISION_BOUND: u32 = 1 << 16;
+
+/// `prove_prime` looks for the factors of `n - 1` by trial division up to this bound,
+/// plus at most one larger prime cofactor.
+pub const FACTOR_SEARCH_BOUND: u32 = 1 << 20;
+
+/// Witnesses are searched among `2..WITNESS_SEARCH_BOUND`.
+const WITNESS_SEARCH_BOUND: u32 = 1000;
+
+/// A proof that `n` is prime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimalityCertificate {
+    pub n: BigInt,
+    /// The factored part of `n - 1`, empty when `n` is below `TRIAL_DIVISION_BOUND`.
+    pub factors: Vec<CertifiedFactor>,
+}
+
+/// A prime power `q^exponent` dividing `n - 1`, with the witness for `q`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertifiedFactor {
+    pub prime: PrimalityCertificate,
+    pub exponent: u32,
+    pub witness: BigInt,
+}
+
+impl PrimalityCertificate {
+    /// Check the certificate and those of all the factors it relies on.
+    pub fn verify(&self) -> bool {
+        let n = &self.n;
+        if self.factors.is_empty() {
+            return *n < TRIAL_DIVISION_BOUND && n.is_prime_bpsw();
+        }
+        if *n < 3u32 {
+            return false;
+        }
+        let minus_one = n.sub(&BigInt::one());
+        let factored: BigInt = self
+            .factors
+            .iter()
+            .map(|f| f.prime.n.pow(f.exponent))
+            .product();
+        if factored.mul(&factored) <= *n || !minus_one.rem(&factored).is_zero() {
+            return false;
+        }
+        self.factors.iter().all(|f| {
+            let q = &f.prime.n;
+            f.exponent > 0
+                && f.prime.verify()
+                && f.witness.modpow(&minus_one, n).is_one()
+                && f.witness
+                    .modpow(&minus_one.div(q), n)
+                    .add(&minus_one)
+                    .rem(n)
+                    .gcd(n)
+                    .is_one()
+        })
+    }
+}
+
+impl BigInt {
+    /// A certificate that the value is prime, or `None` if it is not prime or if too
+    /// little of `n - 1` could be factored: more than half of its bits must come from
+    /// primes below `FACTOR_SEARCH_BOUND` and at most one prime above.
+    pub fn prove_prime(&self) -> Option<PrimalityCertificate> {
+        if !self.is_prime_bpsw() {
+            return None;
+        }
+        if *self < TRIAL_DIVISION_BOUND {
+            return Some(PrimalityCertificate {
+                n: self.clone(),
+                factors: Vec::new(),
+            });
+        }
+        let minus_one = self.sub(&BigInt::one());
+        let mut factors = Vec::new();
+        for (q, exponent) in partial_factorization(&minus_one) {
+            let witness = (2..WITNESS_SEARCH_BOUND).map(BigInt::from).find(|a| {
+                a.modpow(&minus_one.div(&q), self)
+                    .add(&minus_one)
+                    .rem(self)
+                    .gcd(self)
+                    .is_one()
+            })?;
+            factors.push(CertifiedFactor {
+                prime: q.prove_prime()?,
+                exponent,
+                witness,
+            });
+        }
+        let certificate = PrimalityCertificate {
+            n: self.clone(),
+            factors,
+        };
+        if certificate.verify() {
+            Some(certificate)
+        } else {
+            None
+        }
+    }
+}
+
+/// Helper function for `prove_prime`: the prime powers of `m` below `FACTOR_SEARCH_BOUND`,
+/// then the cofactor if it is prime.
+fn partial_factorization(m: &BigInt) -> Vec<(BigInt, u32)> {
+    let (small, rest) = m.small_factors(FACTOR_SEARCH_BOUND);
+    let mut factors: Vec<(BigInt, u32)> = small
+        .into_iter()
+        .map(|(p, exponent)| (BigInt::from(p), exponent))
+        .collect();
+    if rest > 1u32 && rest.is_prime_bpsw() {
+        factors.push((rest, 1));
+    }
+    factors
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn mersenne(p: usize) -> BigInt {
+        (BigInt::one() << p).sub(&BigInt::one())
+    }
+
+    #[test]
+    fn test_prove_prime() {
+        let primes = [
+            BigInt::from(2u32),
+            BigInt::from(65521u32),
+            BigInt::from(1000003u32),
+            BigInt::from(u64::MAX - 58),
+            mersenne(61),
+            mersenne(89),
+            mersenne(127),
+        ];
+        for p in primes.iter() {
+            let certificate = p.prove_prime().unwrap();
+            assert_eq!(*p, certificate.n);
+            assert!(certificate.verify(), "{}", p);
+        }
+        assert_eq!(None, BigInt::from(1000001u32).prove_prime());
+        assert_eq!(None, mersenne(61).mul(&mersenne(89)).prove_prime());
+    }
+
+    #[test]
+    fn test_verify_rejects_forgeries() {
+        let certificate = mersenne(89).prove_prime().unwrap();
+
+        let mut wrong_n = certificate.clone();
+        wrong_n.n = mersenne(89).add(&BigInt::two());
+        assert!(!wrong_n.verify());
+
+        let mut bad_witness = certificate.clone();
+        bad_witness.factors[0].witness = BigInt::one();
+        assert!(!bad_witness.verify());
+
+        let mut too_little = certificate.clone();
+        too_little.factors.truncate(2);
+        assert!(!too_little.verify());
+
+        let mut bad_exponent = certificate;
+        bad_exponent.factors[0].exponent += 1;
+        assert!(!bad_exponent.verify());
+
+        let composite = PrimalityCertificate {
+            n: BigInt::from(65537u32 * 3),
+            factors: Vec::new(),
+        };
+        assert!(!composite.verify());
+    }
+}
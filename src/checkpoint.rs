@@ -0,0 +1,597 @@
+//! Resumable versions of the long-running searches: Pollard rho, ECM and the next prime
+//! search. Each runs in steps, and between two steps its whole progress fits in a line of
+//! text, so a run of several hours can be saved to disk and picked up after a restart.
+//!
+//! A checkpoint is a tag followed by `key=value` fields in a fixed order, for instance
+//! `ecm n=1000000016000000063 curves=90 b1=11000 b2=1100000 done=17`. Resuming and running
+//! to the end gives the same answer as one uninterrupted run.
+//!
+//! `run` drives a search to the end, but stops as soon as its `should_stop` callback asks
+//! to, handing back the search so that it can be saved or continued.
+
+use crate::factor::{ecm_curve, proper_factor, BrentRound, EcmParams, RHO_ATTEMPTS};
+use crate::modular::ModRing;
+use crate::optimized_memory::{BigInt, Cancelled};
+use crate::prime::search_window;
+use std::fmt;
+use std::str::FromStr;
+
+/// Iterations of the polynomial in a step of `RhoSearch`, so that a step takes about as
+/// long whatever the round length.
+pub const RHO_STEP_ITERATIONS: usize = 1 << 14;
+
+/// Pollard rho with Brent's cycle detection, `RHO_STEP_ITERATIONS` iterations per step.
+#[derive(Debug, Clone)]
+pub struct RhoSearch {
+    ring: ModRing,
+    /// The polynomial is `x^2 + c`, for `c = 1..=RHO_ATTEMPTS`.
+    c: u32,
+    /// The length of the current round, which doubles every round.
+    r: usize,
+    /// Boxed to keep `Cancelled<RhoSearch>` small.
+    round: Box<BrentRound>,
+}
+
+impl RhoSearch {
+    /// The search for a factor of `n`, from the first polynomial, as in `pollard_rho`.
+    ///
+    /// Panics if `n` is below 2.
+    pub fn new(n: &BigInt) -> RhoSearch {
+        assert!(*n >= 2u32, "n must be at least 2");
+        let ring = ModRing::new(n.clone());
+        let round = Box::new(BrentRound::start(&ring, ring.element(&BigInt::two())));
+        RhoSearch {
+            ring,
+            c: 1,
+            r: 1,
+            round,
+        }
+    }
+
+    /// The value being factored.
+    pub fn n(&self) -> &BigInt {
+        self.ring.modulus()
+    }
+
+    /// Whether every polynomial has been tried.
+    pub fn is_finished(&self) -> bool {
+        self.c > RHO_ATTEMPTS
+    }
+
+    /// Run the next `RHO_STEP_ITERATIONS` iterations, and return the nontrivial factor
+    /// they found, if any. A cycle that closes without revealing a factor moves the search
+    /// to the next polynomial.
+    pub fn step(&mut self) -> Option<BigInt> {
+        self.advance(RHO_STEP_ITERATIONS)
+    }
+
+    /// Helper function for `step`, with any number of iterations.
+    fn advance(&mut self, budget: usize) -> Option<BigInt> {
+        if self.is_finished() {
+            return None;
+        }
+        let c = self.ring.element(&BigInt::from(self.c));
+        let g = self.round.advance(&self.ring, &c, self.r, budget);
+        if g.is_none() {
+            if self.round.is_done(self.r) {
+                self.r *= 2;
+                *self.round = BrentRound::start(&self.ring, self.round.y.clone());
+            }
+            return None;
+        }
+        let factor = proper_factor(g?, self.n());
+        if factor.is_none() {
+            self.c += 1;
+            self.r = 1;
+            *self.round = BrentRound::start(&self.ring, self.ring.element(&BigInt::two()));
+        }
+        factor
+    }
+
+    /// Step until a factor is found or every polynomial has been tried, checking
+    /// `should_stop` before each step.
+    pub fn run(
+        mut self,
+        should_stop: &dyn Fn() -> bool,
+    ) -> Result<Option<BigInt>, Cancelled<RhoSearch>> {
+        while !self.is_finished() {
+            if should_stop() {
+                return Err(Cancelled { partial: self });
+            }
+            if let Some(factor) = self.step() {
+                return Ok(Some(factor));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The progress so far, for `resume`: the polynomial, the round length, and where the
+    /// round stands.
+    pub fn checkpoint(&self) -> String {
+        format!(
+            "rho n={} c={} r={} k={} x={} y={} product={}",
+            self.n(),
+            self.c,
+            self.r,
+            self.round.k,
+            self.round.x.value(),
+            self.round.y.value(),
+            self.round.product.value()
+        )
+    }
+
+    /// The search saved by `checkpoint`.
+    pub fn resume(checkpoint: &str) -> Result<RhoSearch, ParseCheckpointError> {
+        let names = ["n", "c", "r", "k", "x", "y", "product"];
+        let fields = fields(checkpoint, "rho", &names)?;
+        let n = parse_modulus(fields[0])?;
+        let c: u32 = parse_number(fields[1])?;
+        let r: usize = parse_number(fields[2])?;
+        let k: usize = parse_number(fields[3])?;
+        let error = |reason| Err(ParseCheckpointError { reason });
+        if c == 0 || c > RHO_ATTEMPTS + 1 {
+            return error("invalid polynomial");
+        }
+        // Rounds start at 1 and double.
+        if !r.is_power_of_two() || r > usize::MAX / 4 {
+            return error("invalid round length");
+        }
+        if k >= 2 * r {
+            return error("invalid round position");
+        }
+        let ring = ModRing::new(n);
+        let round = Box::new(BrentRound {
+            x: ring.element(&parse_residue(fields[4], ring.modulus())?),
+            y: ring.element(&parse_residue(fields[5], ring.modulus())?),
+            k,
+            product: ring.element(&parse_residue(fields[6], ring.modulus())?),
+        });
+        Ok(RhoSearch { ring, c, r, round })
+    }
+}
+
+/// The elliptic curve method, one curve per step.
+#[derive(Debug, Clone)]
+pub struct EcmSearch {
+    ring: ModRing,
+    params: EcmParams,
+    curves_done: u32,
+}
+
+impl EcmSearch {
+    /// The search for a factor of `n` with the bounds of `params`, as in `ecm`.
+    ///
+    /// Panics if `n` is below 2.
+    pub fn new(n: &BigInt, params: &EcmParams) -> EcmSearch {
+        assert!(*n >= 2u32, "n must be at least 2");
+        EcmSearch {
+            ring: ModRing::new(n.clone()),
+            params: *params,
+            curves_done: 0,
+        }
+    }
+
+    /// The value being factored.
+    pub fn n(&self) -> &BigInt {
+        self.ring.modulus()
+    }
+
+    /// The number of curves tried so far.
+    pub fn curves_done(&self) -> u32 {
+        self.curves_done
+    }
+
+    /// Whether all `params.curves` curves have been tried.
+    pub fn is_finished(&self) -> bool {
+        self.curves_done >= self.params.curves
+    }
+
+    /// Try the next curve, and return the nontrivial factor it found, if any.
+    pub fn step(&mut self) -> Option<BigInt> {
+        if self.is_finished() {
+            return None;
+        }
+        let sigma = 6 + self.curves_done;
+        self.curves_done += 1;
+        ecm_curve(&self.ring, sigma, &self.params)
+    }
+
+    /// Step until a factor is found or every curve has been tried, checking `should_stop`
+    /// before each curve.
+    pub fn run(
+        mut self,
+        should_stop: &dyn Fn() -> bool,
+    ) -> Result<Option<BigInt>, Cancelled<EcmSearch>> {
+        while !self.is_finished() {
+            if should_stop() {
+                return Err(Cancelled { partial: self });
+            }
+            if let Some(factor) = self.step() {
+                return Ok(Some(factor));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The progress so far, for `resume`.
+    pub fn checkpoint(&self) -> String {
+        format!(
+            "ecm n={} curves={} b1={} b2={} done={}",
+            self.n(),
+            self.params.curves,
+            self.params.b1,
+            self.params.b2,
+            self.curves_done
+        )
+    }
+
+    /// The search saved by `checkpoint`.
+    pub fn resume(checkpoint: &str) -> Result<EcmSearch, ParseCheckpointError> {
+        let fields = fields(checkpoint, "ecm", &["n", "curves", "b1", "b2", "done"])?;
+        let params = EcmParams {
+            curves: parse_number(fields[1])?,
+            b1: parse_number(fields[2])?,
+            b2: parse_number(fields[3])?,
+        };
+        let mut search = EcmSearch::new(&parse_modulus(fields[0])?, &params);
+        search.curves_done = parse_number(fields[4])?;
+        Ok(search)
+    }
+}
+
+/// The search for the smallest prime above a value, one sieve window of candidates per
+/// step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimeSearch {
+    from: BigInt,
+    /// The next window to search.
+    window: usize,
+}
+
+impl PrimeSearch {
+    /// The search for `from.next_prime()`.
+    pub fn new(from: &BigInt) -> PrimeSearch {
+        PrimeSearch {
+            from: from.clone(),
+            window: 0,
+        }
+    }
+
+    /// The number of windows searched so far.
+    pub fn windows_done(&self) -> usize {
+        self.window
+    }
+
+    /// Search the next window, and return the prime if it holds one. Values below 2^16
+    /// are done in a single step.
+    pub fn step(&mut self) -> Option<BigInt> {
+        if self.from < 256u32 * 256 {
+            self.window += 1;
+            return Some(self.from.next_prime());
+        }
+        let mut start = self.from.add(&BigInt::one());
+        if start.is_even() {
+            start = start.add(&BigInt::one());
+        }
+        let prime = search_window(&start, true, self.window, &BigInt::is_prime_bpsw);
+        self.window += 1;
+        prime
+    }
+
+    /// Step until the prime is found, checking `should_stop` before each window.
+    pub fn run(mut self, should_stop: &dyn Fn() -> bool) -> Result<BigInt, Cancelled<PrimeSearch>> {
+        loop {
+            if should_stop() {
+                return Err(Cancelled { partial: self });
+            }
+            if let Some(prime) = self.step() {
+                return Ok(prime);
+            }
+        }
+    }
+
+    /// The progress so far, for `resume`.
+    pub fn checkpoint(&self) -> String {
+        format!("prime from={} window={}", self.from, self.window)
+    }
+
+    /// The search saved by `checkpoint`.
+    pub fn resume(checkpoint: &str) -> Result<PrimeSearch, ParseCheckpointError> {
+        let fields = fields(checkpoint, "prime", &["from", "window"])?;
+        Ok(PrimeSearch {
+            from: parse_bigint(fields[0])?,
+            window: parse_number(fields[1])?,
+        })
+    }
+}
+
+/// The error returned when a checkpoint cannot be resumed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCheckpointError {
+    reason: &'static str,
+}
+
+impl fmt::Display for ParseCheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid checkpoint: {}", self.reason)
+    }
+}
+
+impl std::error::Error for ParseCheckpointError {}
+
+/// Helper function for the `resume` functions: the values of the fields `names`, which
+/// must follow `tag` in this order.
+fn fields<'a>(
+    checkpoint: &'a str,
+    tag: &str,
+    names: &[&str],
+) -> Result<Vec<&'a str>, ParseCheckpointError> {
+    let mut tokens = checkpoint.split_whitespace();
+    if tokens.next() != Some(tag) {
+        return Err(ParseCheckpointError {
+            reason: "wrong kind of search",
+        });
+    }
+    let values = names
+        .iter()
+        .zip(tokens.by_ref())
+        .map(|(name, token)| match token.split_once('=') {
+            Some((key, value)) if key == *name => Ok(value),
+            _ => Err(ParseCheckpointError {
+                reason: "unexpected field",
+            }),
+        })
+        .collect::<Result<Vec<&str>, ParseCheckpointError>>()?;
+    if values.len() < names.len() || tokens.next().is_some() {
+        return Err(ParseCheckpointError {
+            reason: "wrong number of fields",
+        });
+    }
+    Ok(values)
+}
+
+/// Helper function for the `resume` functions: a field holding a BigInt.
+fn parse_bigint(value: &str) -> Result<BigInt, ParseCheckpointError> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseCheckpointError {
+            reason: "invalid number",
+        });
+    }
+    Ok(BigInt::from_string(value))
+}
+
+/// Helper function for the `resume` functions: a field holding the value being factored.
+fn parse_modulus(value: &str) -> Result<BigInt, ParseCheckpointError> {
+    let n = parse_bigint(value)?;
+    if n < 2u32 {
+        return Err(ParseCheckpointError {
+            reason: "n must be at least 2",
+        });
+    }
+    Ok(n)
+}
+
+/// Helper function for `RhoSearch::resume`: a field holding a value modulo `n`.
+fn parse_residue(value: &str, n: &BigInt) -> Result<BigInt, ParseCheckpointError> {
+    let residue = parse_bigint(value)?;
+    if residue >= *n {
+        return Err(ParseCheckpointError {
+            reason: "value not reduced modulo n",
+        });
+    }
+    Ok(residue)
+}
+
+/// Helper function for the `resume` functions: a field holding a primitive integer.
+fn parse_number<T: FromStr>(value: &str) -> Result<T, ParseCheckpointError> {
+    value.parse().map_err(|_| ParseCheckpointError {
+        reason: "invalid number",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Run `step` until it returns a value or `steps` run out, saving and resuming after
+    /// every step.
+    fn run_resumed<S>(
+        mut search: S,
+        mut step: impl FnMut(&mut S) -> Option<BigInt>,
+        save: impl Fn(&S) -> String,
+        load: impl Fn(&str) -> S,
+        steps: usize,
+    ) -> Option<BigInt> {
+        for _ in 0..steps {
+            if let Some(found) = step(&mut search) {
+                return Some(found);
+            }
+            search = load(&save(&search));
+        }
+        None
+    }
+
+    #[test]
+    fn test_rho_search() {
+        let n = BigInt::from(1000003u64 * 1000033);
+        let resumed = run_resumed(
+            RhoSearch::new(&n),
+            RhoSearch::step,
+            RhoSearch::checkpoint,
+            |text| RhoSearch::resume(text).unwrap(),
+            100,
+        );
+        assert_eq!(n.pollard_rho(), resumed);
+
+        // Saved and resumed inside the rounds, between two batches.
+        let resumed = run_resumed(
+            RhoSearch::new(&n),
+            |search| search.advance(100),
+            RhoSearch::checkpoint,
+            |text| RhoSearch::resume(text).unwrap(),
+            1000,
+        );
+        assert_eq!(n.pollard_rho(), resumed);
+        let mut search = RhoSearch::new(&n);
+        while search.r < 256 {
+            assert_eq!(None, search.advance(100));
+        }
+        assert_eq!(None, search.advance(200));
+        assert_eq!(
+            "rho n=1000036000099 c=1 r=256 k=200",
+            search.checkpoint().split(" x=").next().unwrap()
+        );
+
+        let mut search = RhoSearch::new(&BigInt::from(1000003u32));
+        while !search.is_finished() {
+            assert_eq!(None, search.step());
+        }
+        assert_eq!(
+            format!(
+                "rho n=1000003 c={} r=1 k=0 x=2 y=2 product=1",
+                RHO_ATTEMPTS + 1
+            ),
+            search.checkpoint()
+        );
+    }
+
+    #[test]
+    fn test_ecm_search() {
+        let n = BigInt::from(1000003u64 * 1000033);
+        let params = EcmParams {
+            curves: 20,
+            b1: 200,
+            b2: 20000,
+        };
+        let resumed = run_resumed(
+            EcmSearch::new(&n, &params),
+            EcmSearch::step,
+            EcmSearch::checkpoint,
+            |text| EcmSearch::resume(text).unwrap(),
+            20,
+        );
+        assert_eq!(n.ecm(&params), resumed);
+
+        let mut search = EcmSearch::new(&n, &params);
+        search.step();
+        search.step();
+        assert_eq!(
+            "ecm n=1000036000099 curves=20 b1=200 b2=20000 done=2",
+            search.checkpoint()
+        );
+        let resumed = EcmSearch::resume(&search.checkpoint()).unwrap();
+        assert_eq!(2, resumed.curves_done());
+        assert!(!resumed.is_finished());
+    }
+
+    #[test]
+    fn test_prime_search() {
+        for from in [BigInt::from(100u32), BigInt::one() << 100] {
+            let resumed = run_resumed(
+                PrimeSearch::new(&from),
+                PrimeSearch::step,
+                PrimeSearch::checkpoint,
+                |text| PrimeSearch::resume(text).unwrap(),
+                1000,
+            );
+            assert_eq!(Some(from.next_prime()), resumed);
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let n = BigInt::from(1000003u64 * 1000033);
+        assert_eq!(
+            Some(n.pollard_rho()),
+            RhoSearch::new(&n).run(&|| false).ok()
+        );
+        let params = EcmParams {
+            curves: 20,
+            b1: 200,
+            b2: 20000,
+        };
+        assert_eq!(
+            Some(n.ecm(&params)),
+            EcmSearch::new(&n, &params).run(&|| false).ok()
+        );
+
+        // Stop after every other check, and go on from the cancelled search each time.
+        let from = BigInt::one() << 200;
+        let checks = std::cell::Cell::new(0);
+        let stop = || {
+            checks.set(checks.get() + 1);
+            checks.get() % 2 == 0
+        };
+        let mut search = PrimeSearch::new(&from);
+        let prime = loop {
+            match search.run(&stop) {
+                Ok(prime) => break prime,
+                Err(cancelled) => search = cancelled.partial,
+            }
+        };
+        assert_eq!(from.next_prime(), prime);
+
+        let cancelled = EcmSearch::new(&n, &params).run(&|| true).unwrap_err();
+        assert_eq!(0, cancelled.partial.curves_done());
+        assert_eq!("operation cancelled", cancelled.to_string());
+    }
+
+    #[test]
+    fn test_resume_errors() {
+        let error = |text: &str| RhoSearch::resume(text).unwrap_err().reason;
+        let rho = |c: &str, r: &str, k: &str, y: &str| {
+            error(&format!(
+                "rho n=15 c={} r={} k={} x=2 y={} product=1",
+                c, r, k, y
+            ))
+        };
+        assert_eq!(
+            "wrong kind of search",
+            error("ecm n=15 c=1 r=1 k=0 x=2 y=2 product=1")
+        );
+        assert_eq!(
+            "unexpected field",
+            error("rho n=15 r=1 c=1 k=0 x=2 y=2 product=1")
+        );
+        assert_eq!(
+            "wrong number of fields",
+            error("rho n=15 c=1 r=1 k=0 x=2 y=2")
+        );
+        assert_eq!(
+            "wrong number of fields",
+            error("rho n=15 c=1 r=1 k=0 x=2 y=2 product=1 z=0")
+        );
+        assert_eq!("invalid number", rho("1", "1", "0", "-2"));
+        assert_eq!("invalid number", rho("one", "1", "0", "2"));
+        assert_eq!(
+            "n must be at least 2",
+            error("rho n=1 c=1 r=1 k=0 x=0 y=0 product=0")
+        );
+        assert_eq!("invalid polynomial", rho("0", "1", "0", "2"));
+        assert_eq!(
+            "invalid polynomial",
+            rho(&(RHO_ATTEMPTS + 2).to_string(), "1", "0", "2")
+        );
+        assert_eq!("invalid round length", rho("1", "0", "0", "2"));
+        assert_eq!("invalid round length", rho("1", "12", "0", "2"));
+        assert_eq!(
+            "invalid round length",
+            rho("1", &(1usize << 63).to_string(), "0", "2")
+        );
+        assert_eq!("invalid round position", rho("1", "4", "8", "2"));
+        assert_eq!("value not reduced modulo n", rho("1", "4", "0", "15"));
+        assert!(RhoSearch::resume(&format!(
+            "rho n=15 c={} r=1 k=0 x=2 y=2 product=1",
+            RHO_ATTEMPTS + 1
+        ))
+        .unwrap()
+        .is_finished());
+        assert_eq!(
+            "invalid checkpoint: invalid number",
+            PrimeSearch::resume("prime from= window=0")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,488 @@
+//! Factorials and their relatives, built from balanced products: the factors are split
+//! in halves and multiplied as a tree, so most multiplications have operands of similar
+//! size instead of one growing value taking single limbs at a time.
+//!
+//! The Fibonacci and Lucas numbers come from the fast-doubling identities instead, with
+//! about two squarings per bit of the index.
+
+use crate::factor::{primes_below, PRIME_TABLE_LIMIT};
+use crate::modular::{ModInt, ModRing};
+use crate::optimized_memory::BigInt;
+use crate::prime::primes_in_range;
+use std::sync::{Arc, Mutex};
+
+/// Ranges with at most this many factors are multiplied one by one.
+const LEAF_FACTORS: u64 = 16;
+
+/// `binomial(n, k)` with `k * BINOMIAL_RATIO < n` divides a product by `k!` instead of
+/// sieving the primes up to `n`.
+const BINOMIAL_RATIO: u64 = 64;
+
+/// The partition numbers computed so far, `p(0)` to `p(len - 1)`. The lock is only held
+/// to read or replace the table, never while computing.
+static PARTITION_TABLE: Mutex<Option<Arc<Vec<BigInt>>>> = Mutex::new(None);
+
+/// The product of `values`, as a balanced tree. The empty product is one.
+pub fn product_all(values: &[BigInt]) -> BigInt {
+    match values.len() {
+        0 => BigInt::one(),
+        1 => values[0].clone(),
+        len => product_all(&values[..len / 2]).mul(&product_all(&values[len / 2..])),
+    }
+}
+
+/// `n!`, by the split-product algorithm. The odd part of `n!` is the product of
+/// `L(n / 2^i)` over all `i`, where `L(m)` is the product of the odd numbers up to `m`;
+/// each `L` extends the previous one by a range of odd factors, and the power of two is
+/// `n` minus the number of ones in `n`.
+pub fn factorial(n: u64) -> BigInt {
+    factorial_with_progress(n, &mut |_, _| {})
+}
+
+/// `factorial`, calling `progress(done, total)` after each of the `total` range products,
+/// one per bit of `n`.
+pub fn factorial_with_progress(n: u64, progress: &mut dyn FnMut(u64, u64)) -> BigInt {
+    let levels = 64 - n.leading_zeros();
+    let mut odd_part = BigInt::one();
+    let mut partial = BigInt::one();
+    for shift in (0..levels).rev() {
+        partial = partial.mul(&odd_product(n >> (shift + 1), n >> shift));
+        odd_part = odd_part.mul(&partial);
+        progress((levels - shift) as u64, levels as u64);
+    }
+    odd_part << (n - u64::from(n.count_ones())) as usize
+}
+
+/// `n!!`, the product of the numbers up to `n` with the same parity as `n`. For even `n`
+/// this is `2^(n/2) (n/2)!`.
+pub fn double_factorial(n: u64) -> BigInt {
+    if n.is_multiple_of(2) {
+        factorial(n / 2) << (n / 2) as usize
+    } else {
+        odd_product(0, n)
+    }
+}
+
+/// The rising factorial `x (x + 1) ... (x + n - 1)`, one for `n = 0`.
+pub fn rising_factorial(x: &BigInt, n: u64) -> BigInt {
+    match x.to_u64() {
+        Some(first) if first.checked_add(n).is_some() => progression_product(first, n, 1),
+        _ => shifted_product(x, 0, n),
+    }
+}
+
+/// The falling factorial `x (x - 1) ... (x - n + 1)`, one for `n = 0` and zero when
+/// `x < n`.
+pub fn falling_factorial(x: &BigInt, n: u64) -> BigInt {
+    let count = BigInt::from(n);
+    if *x < count {
+        return BigInt::zero();
+    }
+    rising_factorial(&x.sub(&count).add(&BigInt::one()), n)
+}
+
+/// `n#`, the product of the primes up to `n`.
+pub fn primorial(n: u32) -> BigInt {
+    // u32::MAX is composite, so saturating leaves out nothing.
+    let primes: Vec<BigInt> = primes_below(n.saturating_add(1))
+        .iter()
+        .take_while(|&&p| p <= n)
+        .map(|&p| BigInt::from(p))
+        .collect();
+    product_all(&primes)
+}
+
+/// `n choose k`, zero when `k > n`. When `k` is tiny next to `n` this is the product of
+/// `(n - k, n]` divided by `k!`; otherwise it is assembled from its prime factorization,
+/// by Legendre's formula for the exponents of `n!`, `k!` and `(n - k)!`, so there is no
+/// division at all.
+///
+/// The primes up to `n` come from `primes_below` up to `PRIME_TABLE_LIMIT`, and from the
+/// segmented sieve of `primes_in_range` above, so a large `n` never builds a sieve of
+/// `n` bytes.
+pub fn binomial(n: u64, k: u64) -> BigInt {
+    if k > n {
+        return BigInt::zero();
+    }
+    let k = k.min(n - k);
+    if n > u64::from(u32::MAX) - 1 || k.saturating_mul(BINOMIAL_RATIO) < n {
+        return progression_product(n - k + 1, k, 1).div(&factorial(k));
+    }
+    if n < u64::from(PRIME_TABLE_LIMIT) {
+        let primes = primes_below(n as u32 + 1);
+        binomial_from_primes(n, k, primes.iter().map(|&p| u64::from(p)))
+    } else {
+        binomial_from_primes(n, k, segmented_primes(n))
+    }
+}
+
+/// Helper function for `binomial`: the primes up to `n` by the segmented sieve.
+fn segmented_primes(n: u64) -> impl Iterator<Item = u64> {
+    primes_in_range(&BigInt::zero(), &BigInt::from(n + 1)).map(|p| p.to_u64().unwrap())
+}
+
+/// Helper function for `binomial`: the product of the prime powers dividing `n choose k`,
+/// with the primes taken from `primes` in increasing order up to `n`.
+fn binomial_from_primes(n: u64, k: u64, primes: impl Iterator<Item = u64>) -> BigInt {
+    let powers: Vec<BigInt> = primes
+        .take_while(|&p| p <= n)
+        .filter_map(|p| {
+            let exponent =
+                legendre_exponent(n, p) - legendre_exponent(k, p) - legendre_exponent(n - k, p);
+            if exponent == 0 {
+                None
+            } else {
+                Some(BigInt::from(p).pow(exponent as u32))
+            }
+        })
+        .collect();
+    product_all(&powers)
+}
+
+/// The multinomial coefficient `(k1 + k2 + ...)! / (k1! k2! ...)`, as the product of the
+/// binomials `(k1 + ... + ki) choose ki`.
+///
+/// Panics if the sum of `ks` overflows a `u64`.
+pub fn multinomial(ks: &[u64]) -> BigInt {
+    let mut total = 0u64;
+    let binomials: Vec<BigInt> = ks
+        .iter()
+        .map(|&k| {
+            total = total
+                .checked_add(k)
+                .expect("multinomial total overflows u64");
+            binomial(total, k)
+        })
+        .collect();
+    product_all(&binomials)
+}
+
+/// The Fibonacci number `F(n)`, with `F(0) = 0` and `F(1) = 1`.
+pub fn fibonacci(n: u64) -> BigInt {
+    fibonacci_pair(n).0
+}
+
+/// The Lucas number `L(n) = F(n - 1) + F(n + 1)`, with `L(0) = 2` and `L(1) = 1`.
+pub fn lucas(n: u64) -> BigInt {
+    let (f, next) = fibonacci_pair(n);
+    next.add(&next).sub(&f)
+}
+
+/// `F(n) mod m`, without ever computing `F(n)` itself.
+///
+/// Panics if `m` is zero.
+pub fn fibonacci_mod(n: u64, m: &BigInt) -> BigInt {
+    let ring = ModRing::new(m.clone());
+    let (mut f, mut next) = (ring.zero(), ring.one());
+    for i in (0..64 - n.leading_zeros()).rev() {
+        let (double, double_next) = double_mod(&f, &next);
+        if (n >> i) & 1 == 1 {
+            f = double_next.clone();
+            next = &double + &double_next;
+        } else {
+            f = double;
+            next = double_next;
+        }
+    }
+    f.into_value()
+}
+
+/// Helper function for `fibonacci` and `lucas`: `(F(n), F(n + 1))` by fast doubling,
+/// from the top bit of `n` down, with `F(2k) = F(k) (2 F(k + 1) - F(k))` and
+/// `F(2k + 1) = F(k)^2 + F(k + 1)^2`.
+fn fibonacci_pair(n: u64) -> (BigInt, BigInt) {
+    let (mut f, mut next) = (BigInt::zero(), BigInt::one());
+    for i in (0..64 - n.leading_zeros()).rev() {
+        let double = f.mul(&next.add(&next).sub(&f));
+        let double_next = f.mul(&f).add(&next.mul(&next));
+        if (n >> i) & 1 == 1 {
+            f = double_next;
+            next = double.add(&f);
+        } else {
+            f = double;
+            next = double_next;
+        }
+    }
+    (f, next)
+}
+
+/// Helper function for `fibonacci_mod`: the doubling step of `fibonacci_pair` in a ring.
+fn double_mod(f: &ModInt, next: &ModInt) -> (ModInt, ModInt) {
+    let double = f * &(&(next + next) - f);
+    let double_next = &(f * f) + &(next * next);
+    (double, double_next)
+}
+
+/// The number of partitions `p(n)` of `n`, by Euler's pentagonal number recurrence
+/// `p(n) = sum over k >= 1 of (-1)^(k + 1) (p(n - k(3k - 1)/2) + p(n - k(3k + 1)/2))`.
+/// Each new value takes about `sqrt(n)` additions and all of `p(0)` to `p(n)` are kept
+/// for later calls, so asking for `n` in increasing order costs nothing extra.
+///
+/// Concurrent callers extend copies of the table independently, and the longest copy is
+/// kept.
+pub fn partitions(n: u64) -> BigInt {
+    let n = n as usize;
+    let shared = PARTITION_TABLE.lock().unwrap().clone();
+    if let Some(table) = &shared {
+        if n < table.len() {
+            return table[n].clone();
+        }
+    }
+    let mut table: Vec<BigInt> = shared.map_or_else(Vec::new, |table| table.to_vec());
+    while table.len() <= n {
+        let m = table.len();
+        let (mut positive, mut negative) = (BigInt::zero(), BigInt::zero());
+        for k in 1.. {
+            let pentagonal = k * (3 * k - 1) / 2;
+            if pentagonal > m {
+                break;
+            }
+            let sum = if k % 2 == 1 {
+                &mut positive
+            } else {
+                &mut negative
+            };
+            *sum = sum.add(&table[m - pentagonal]);
+            if pentagonal + k <= m {
+                *sum = sum.add(&table[m - pentagonal - k]);
+            }
+        }
+        let value = if m == 0 {
+            BigInt::one()
+        } else {
+            positive.sub(&negative)
+        };
+        table.push(value);
+    }
+    let value = table[n].clone();
+    let mut shared = PARTITION_TABLE.lock().unwrap();
+    if shared
+        .as_ref()
+        .is_none_or(|current| current.len() < table.len())
+    {
+        *shared = Some(Arc::new(table));
+    }
+    value
+}
+
+/// Helper function for `binomial`: the exponent of the prime `p` in `n!`.
+fn legendre_exponent(mut n: u64, p: u64) -> u64 {
+    let mut exponent = 0;
+    while n > 0 {
+        n /= p;
+        exponent += n;
+    }
+    exponent
+}
+
+/// Helper function for `factorial`: the product of the odd numbers in `(low, high]`.
+fn odd_product(low: u64, high: u64) -> BigInt {
+    let first = (low + 1) | 1;
+    if first > high {
+        return BigInt::one();
+    }
+    progression_product(first, (high - first) / 2 + 1, 2)
+}
+
+/// Helper function for `rising_factorial`: the product of `x + i` for `i` in `low..high`,
+/// as a balanced tree.
+fn shifted_product(x: &BigInt, low: u64, high: u64) -> BigInt {
+    if high - low <= LEAF_FACTORS {
+        return (low..high).map(|i| x.add(&BigInt::from(i))).product();
+    }
+    let middle = low + (high - low) / 2;
+    shifted_product(x, low, middle).mul(&shifted_product(x, middle, high))
+}
+
+/// The product of `first + i * step` for `i` in `0..count`, as a balanced tree.
+fn progression_product(first: u64, count: u64, step: u64) -> BigInt {
+    if count <= LEAF_FACTORS {
+        return (0..count).map(|i| BigInt::from(first + i * step)).product();
+    }
+    let half = count / 2;
+    progression_product(first, half, step).mul(&progression_product(
+        first + half * step,
+        count - half,
+        step,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn naive_factorial(n: u64) -> BigInt {
+        (1..=n).map(BigInt::from).product()
+    }
+
+    #[test]
+    fn test_product_all() {
+        assert_eq!(BigInt::one(), product_all(&[]));
+        let values: Vec<BigInt> = (1..=30u32).map(BigInt::from).collect();
+        assert_eq!(naive_factorial(30), product_all(&values));
+        assert_eq!(
+            BigInt::zero(),
+            product_all(&[BigInt::two(), BigInt::zero()])
+        );
+    }
+
+    #[test]
+    fn test_factorial() {
+        for n in 0..100 {
+            assert_eq!(naive_factorial(n), factorial(n), "{}", n);
+        }
+        assert_eq!(naive_factorial(1000), factorial(1000));
+        assert_eq!(naive_factorial(4097), factorial(4097));
+        assert_eq!(
+            BigInt::from_string(
+                "30414093201713378043612608166064768844377641568960512000000000000"
+            ),
+            factorial(50)
+        );
+    }
+
+    #[test]
+    fn test_factorial_with_progress() {
+        let mut reports = Vec::new();
+        let value = factorial_with_progress(1000, &mut |done, total| reports.push((done, total)));
+        assert_eq!(factorial(1000), value);
+        assert_eq!((1..=10).map(|done| (done, 10)).collect::<Vec<_>>(), reports);
+
+        let mut calls = 0;
+        assert_eq!(
+            BigInt::one(),
+            factorial_with_progress(0, &mut |_, _| calls += 1)
+        );
+        assert_eq!(0, calls);
+    }
+
+    #[test]
+    fn test_double_rising_falling() {
+        let naive_double =
+            |n: u64| -> BigInt { (1..=n).rev().step_by(2).map(BigInt::from).product() };
+        for n in 0..60 {
+            assert_eq!(naive_double(n), double_factorial(n), "{}", n);
+        }
+        assert_eq!(naive_double(1001), double_factorial(1001));
+        assert_eq!(
+            factorial(200),
+            double_factorial(200).mul(&double_factorial(199))
+        );
+
+        let x = BigInt::from(10u32);
+        assert_eq!(BigInt::one(), rising_factorial(&x, 0));
+        assert_eq!(BigInt::from(17160u32), rising_factorial(&x, 4));
+        assert_eq!(BigInt::from(5040u32), falling_factorial(&x, 4));
+        assert_eq!(BigInt::one(), falling_factorial(&x, 0));
+        assert_eq!(factorial(10), falling_factorial(&x, 10));
+        assert_eq!(BigInt::zero(), falling_factorial(&x, 11));
+        assert_eq!(BigInt::zero(), rising_factorial(&BigInt::zero(), 3));
+        assert_eq!(factorial(100).div(&factorial(9)), rising_factorial(&x, 91));
+
+        // Beyond u64, the factors are BigInts from the start.
+        let big = BigInt::from(u64::MAX);
+        let expected: BigInt = (0..40u32).map(|i| big.add(&BigInt::from(i))).product();
+        assert_eq!(expected, rising_factorial(&big, 40));
+        let falling: BigInt = (0..40u32).map(|i| big.sub(&BigInt::from(i))).product();
+        assert_eq!(falling, falling_factorial(&big, 40));
+    }
+
+    #[test]
+    fn test_primorial() {
+        assert_eq!(BigInt::one(), primorial(0));
+        assert_eq!(BigInt::one(), primorial(1));
+        assert_eq!(BigInt::two(), primorial(2));
+        assert_eq!(BigInt::from(30u32), primorial(6));
+        assert_eq!(BigInt::from(210u32), primorial(10));
+        assert_eq!(BigInt::from(6469693230u64), primorial(30));
+        let p = primorial(10000);
+        assert_eq!(1229, p.small_factors(10001).0.len());
+        assert!(p.small_factors(10001).1.is_one());
+        assert_eq!(p, primorial(10006));
+        assert_ne!(p, primorial(10007));
+    }
+
+    #[test]
+    fn test_fibonacci_lucas() {
+        let (mut f, mut next) = (BigInt::zero(), BigInt::one());
+        let (mut l, mut l_next) = (BigInt::two(), BigInt::one());
+        let m = BigInt::from(1_000_000_007u32);
+        for n in 0..300 {
+            assert_eq!(f, fibonacci(n), "{}", n);
+            assert_eq!(l, lucas(n), "{}", n);
+            assert_eq!(f.rem(&m), fibonacci_mod(n, &m), "{}", n);
+            let sum = f.add(&next);
+            f = std::mem::replace(&mut next, sum);
+            let sum = l.add(&l_next);
+            l = std::mem::replace(&mut l_next, sum);
+        }
+        assert_eq!(BigInt::from_string("354224848179261915075"), fibonacci(100));
+        // F(2n) = F(n) L(n).
+        assert_eq!(fibonacci(20000), fibonacci(10000).mul(&lucas(10000)));
+        // Modulo 10, the Fibonacci numbers repeat with period 60.
+        let ten = BigInt::from(10u32);
+        assert_eq!(
+            fibonacci_mod(7, &ten),
+            fibonacci_mod(60_000_000_000_007, &ten)
+        );
+        assert_eq!(BigInt::zero(), fibonacci_mod(u64::MAX, &BigInt::one()));
+    }
+
+    #[test]
+    fn test_partitions() {
+        let expected = [1u32, 1, 2, 3, 5, 7, 11, 15, 22, 30, 42, 56, 77, 101, 135];
+        for (n, &p) in expected.iter().enumerate() {
+            assert_eq!(BigInt::from(p), partitions(n as u64), "{}", n);
+        }
+        assert_eq!(BigInt::from(190569292u32), partitions(100));
+        assert_eq!(
+            BigInt::from_string("24061467864032622473692149727991"),
+            partitions(1000)
+        );
+        assert_eq!(BigInt::from(42u32), partitions(10));
+
+        let threads: Vec<_> = (0..4)
+            .map(|i| std::thread::spawn(move || partitions(1500 + i)))
+            .collect();
+        let values: Vec<BigInt> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        for (i, value) in values.into_iter().enumerate() {
+            assert_eq!(partitions(1500 + i as u64), value);
+        }
+    }
+
+    #[test]
+    fn test_binomial() {
+        let mut row = vec![BigInt::one()];
+        for n in 0..200u64 {
+            for (k, expected) in row.iter().enumerate() {
+                assert_eq!(*expected, binomial(n, k as u64), "{} {}", n, k);
+            }
+            assert_eq!(BigInt::zero(), binomial(n, n + 1));
+            let mut next = vec![BigInt::one()];
+            next.extend(row.windows(2).map(|w| w[0].add(&w[1])));
+            next.push(BigInt::one());
+            row = next;
+        }
+        let ratio = factorial(1000).div(&factorial(300).mul(&factorial(700)));
+        assert_eq!(ratio, binomial(1000, 300));
+        for &(n, k) in [(1000u64, 300u64), (5000, 2500), (70001, 1200)].iter() {
+            let primes = primes_below(n as u32 + 1);
+            let expected = binomial_from_primes(n, k, primes.iter().map(|&p| u64::from(p)));
+            assert_eq!(expected, binomial_from_primes(n, k, segmented_primes(n)));
+        }
+        assert_eq!(
+            BigInt::from_string("166666666666166666666667000000000000"),
+            binomial(1_000_000_000_000, 3)
+        );
+    }
+
+    #[test]
+    fn test_multinomial() {
+        assert_eq!(BigInt::one(), multinomial(&[]));
+        assert_eq!(BigInt::one(), multinomial(&[0, 0]));
+        assert_eq!(BigInt::from(1260u32), multinomial(&[2, 3, 4]));
+        let expected = factorial(60).div(&factorial(10).mul(&factorial(20)).mul(&factorial(30)));
+        assert_eq!(expected, multinomial(&[10, 20, 0, 30]));
+    }
+}
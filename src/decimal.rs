@@ -0,0 +1,559 @@
+//! Arbitrary precision decimal numbers, stored as a signed unscaled integer and a
+//! power of ten: the value is `unscaled * 10^exponent`.
+//!
+//! Like the primitive integers in debug builds, arithmetic panics when an exponent
+//! overflows i64, or when aligning two values needs a power of ten above 10^u32::MAX.
+//! Comparisons never panic: they align only values whose leading digits are in the same
+//! position.
+
+use crate::optimized_memory::{decimal_digit_bounds, BigInt as Magnitude};
+use crate::signed::BigInt;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::IntErrorKind;
+use std::str::FromStr;
+
+/// `Display` switches to scientific notation above this many zeros of padding.
+pub const DISPLAY_MAX_ZEROS: usize = 1000;
+
+/// How to round a result which cannot be represented exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Towards zero.
+    Down,
+    /// Away from zero.
+    Up,
+    /// Towards negative infinity.
+    Floor,
+    /// Towards positive infinity.
+    Ceiling,
+    /// To the nearest value, ties away from zero.
+    HalfUp,
+    /// To the nearest value, ties towards zero.
+    HalfDown,
+    /// To the nearest value, ties to the even neighbour.
+    HalfEven,
+}
+
+#[derive(Debug, Clone)]
+pub struct BigDecimal {
+    unscaled: BigInt,
+    exponent: i64,
+}
+
+impl BigDecimal {
+    pub fn zero() -> BigDecimal {
+        BigDecimal::new(BigInt::zero(), 0)
+    }
+
+    /// The value `unscaled * 10^exponent`.
+    pub fn new(unscaled: BigInt, exponent: i64) -> BigDecimal {
+        BigDecimal { unscaled, exponent }
+    }
+
+    pub fn unscaled(&self) -> &BigInt {
+        &self.unscaled
+    }
+
+    pub fn exponent(&self) -> i64 {
+        self.exponent
+    }
+
+    /// Convert a string such as `-12.034` or `1.5e-7` to BigDecimal. The exponent of the
+    /// result is given by the position of the last digit, so `"1.50"` keeps two
+    /// fractional digits.
+    pub fn from_string(s: &str) -> Result<BigDecimal, ParseDecimalError> {
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(i) => {
+                let exponent = s[i + 1..].parse::<i64>().map_err(|e| match e.kind() {
+                    IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                        ParseDecimalError::OUT_OF_RANGE
+                    }
+                    _ => ParseDecimalError {
+                        reason: "invalid exponent",
+                    },
+                })?;
+                (&s[..i], exponent)
+            }
+            None => (s, 0),
+        };
+        let (sign, mantissa) = match mantissa.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", mantissa.strip_prefix('+').unwrap_or(mantissa)),
+        };
+        let (integral, fractional) = match mantissa.find('.') {
+            Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+            None => (mantissa, ""),
+        };
+        if integral.is_empty() && fractional.is_empty() {
+            return Err(ParseDecimalError {
+                reason: "no digits",
+            });
+        }
+        if !integral
+            .bytes()
+            .chain(fractional.bytes())
+            .all(|c| c.is_ascii_digit())
+        {
+            return Err(ParseDecimalError {
+                reason: "invalid digit",
+            });
+        }
+        let exponent = i64::try_from(fractional.len())
+            .ok()
+            .and_then(|n| exponent.checked_sub(n))
+            .ok_or(ParseDecimalError::OUT_OF_RANGE)?;
+        let digits = format!("{}{}{}", sign, integral, fractional);
+        Ok(BigDecimal::new(BigInt::from_string(&digits), exponent))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.unscaled.is_zero()
+    }
+
+    pub fn add(&self, other: &BigDecimal) -> BigDecimal {
+        let exponent = std::cmp::min(self.exponent, other.exponent);
+        BigDecimal::new(
+            self.rescaled(exponent).add(&other.rescaled(exponent)),
+            exponent,
+        )
+    }
+
+    pub fn sub(&self, other: &BigDecimal) -> BigDecimal {
+        let exponent = std::cmp::min(self.exponent, other.exponent);
+        BigDecimal::new(
+            self.rescaled(exponent).sub(&other.rescaled(exponent)),
+            exponent,
+        )
+    }
+
+    /// Panics if the exponent of the product does not fit in an i64.
+    pub fn mul(&self, other: &BigDecimal) -> BigDecimal {
+        BigDecimal::new(
+            self.unscaled.mul(&other.unscaled),
+            checked(self.exponent.checked_add(other.exponent)),
+        )
+    }
+
+    /// Quotient rounded according to `mode` to `scale` fractional digits, that is with
+    /// exponent `-scale`.
+    ///
+    /// Panics if `other` is zero.
+    pub fn div(&self, other: &BigDecimal, scale: i64, mode: RoundingMode) -> BigDecimal {
+        assert!(!other.is_zero(), "attempt to divide by zero");
+        // self / other * 10^scale = self.unscaled / other.unscaled * 10^shift
+        let shift = checked(
+            self.exponent
+                .checked_sub(other.exponent)
+                .and_then(|e| e.checked_add(scale)),
+        );
+        let (numerator, denominator) = if shift >= 0 {
+            (
+                self.unscaled.mul(&power_of_ten(shift)),
+                other.unscaled.clone(),
+            )
+        } else {
+            (
+                self.unscaled.clone(),
+                other
+                    .unscaled
+                    .mul(&power_of_ten(checked(shift.checked_neg()))),
+            )
+        };
+        BigDecimal::new(
+            divide_rounded(&numerator, &denominator, mode),
+            checked(scale.checked_neg()),
+        )
+    }
+
+    /// The value rounded according to `mode` to `scale` fractional digits.
+    pub fn round(&self, scale: i64, mode: RoundingMode) -> BigDecimal {
+        let exponent = checked(scale.checked_neg());
+        if exponent <= self.exponent {
+            return BigDecimal::new(self.rescaled(exponent), exponent);
+        }
+        let divisor = power_of_ten(checked(exponent.checked_sub(self.exponent)));
+        BigDecimal::new(divide_rounded(&self.unscaled, &divisor, mode), exponent)
+    }
+
+    /// The unscaled value for a smaller or equal exponent.
+    fn rescaled(&self, exponent: i64) -> BigInt {
+        self.unscaled
+            .mul(&power_of_ten(checked(self.exponent.checked_sub(exponent))))
+    }
+}
+
+/// Helper function for the exponent arithmetic, which panics on overflow like the
+/// primitive integers do in debug builds.
+fn checked(exponent: Option<i64>) -> i64 {
+    exponent.expect("BigDecimal exponent overflow")
+}
+
+/// Panics if `exp` is negative or larger than `u32::MAX`.
+fn power_of_ten(exp: i64) -> BigInt {
+    let exp = u32::try_from(exp).expect("power of ten out of range");
+    BigInt::from(Magnitude::ten().pow(exp))
+}
+
+/// Helper function for `div` and `round`: `numerator / denominator` rounded to an integer.
+fn divide_rounded(numerator: &BigInt, denominator: &BigInt, mode: RoundingMode) -> BigInt {
+    let (quotient, remainder) = numerator.div_rem(denominator);
+    if remainder.is_zero() {
+        return quotient;
+    }
+    let negative = numerator.is_negative() != denominator.is_negative();
+    let half = remainder
+        .magnitude()
+        .add(remainder.magnitude())
+        .cmp(denominator.magnitude());
+    let away_from_zero = match mode {
+        RoundingMode::Down => false,
+        RoundingMode::Up => true,
+        RoundingMode::Floor => negative,
+        RoundingMode::Ceiling => !negative,
+        RoundingMode::HalfUp => half != Ordering::Less,
+        RoundingMode::HalfDown => half == Ordering::Greater,
+        RoundingMode::HalfEven => {
+            half == Ordering::Greater || (half == Ordering::Equal && quotient.magnitude().is_odd())
+        }
+    };
+    if !away_from_zero {
+        quotient
+    } else if negative {
+        quotient.sub(&BigInt::one())
+    } else {
+        quotient.add(&BigInt::one())
+    }
+}
+
+impl FromStr for BigDecimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(s: &str) -> Result<BigDecimal, ParseDecimalError> {
+        BigDecimal::from_string(s)
+    }
+}
+
+/// The error returned when a string is not a valid decimal number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDecimalError {
+    reason: &'static str,
+}
+
+impl ParseDecimalError {
+    const OUT_OF_RANGE: ParseDecimalError = ParseDecimalError {
+        reason: "exponent out of range",
+    };
+}
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot parse BigDecimal: {}", self.reason)
+    }
+}
+
+impl std::error::Error for ParseDecimalError {}
+
+/// Values are equal when they represent the same number, whatever their exponents.
+impl PartialEq for BigDecimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for BigDecimal {}
+
+/// The signs are compared first, then the magnitudes by the position of their leading
+/// digits, which the bit lengths bound. Only values whose leading digits may be in the
+/// same position are rescaled, so the power of ten is no longer than the operands.
+impl Ord for BigDecimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let sign = |x: &BigDecimal| match (x.is_zero(), x.unscaled.is_negative()) {
+            (true, _) => 0,
+            (false, true) => -1,
+            (false, false) => 1,
+        };
+        let signs = sign(self).cmp(&sign(other));
+        if signs != Ordering::Equal || self.is_zero() {
+            return signs;
+        }
+        let magnitudes = match leading_digit_range(self).cmp_disjoint(leading_digit_range(other)) {
+            Some(ordering) => ordering,
+            None => {
+                let exponent = std::cmp::min(self.exponent, other.exponent);
+                let (a, b) = (self.rescaled(exponent), other.rescaled(exponent));
+                a.magnitude().cmp(b.magnitude())
+            }
+        };
+        if self.unscaled.is_negative() {
+            magnitudes.reverse()
+        } else {
+            magnitudes
+        }
+    }
+}
+
+/// Helper function for `cmp`: the range of the position `p` of the leading digit of a
+/// nonzero value, `10^p <= |x| < 10^(p + 1)`.
+fn leading_digit_range(x: &BigDecimal) -> PositionRange {
+    let (fewest, most) = decimal_digit_bounds(x.unscaled.magnitude().bits());
+    let exponent = i128::from(x.exponent);
+    PositionRange(exponent + fewest as i128 - 1, exponent + most as i128 - 1)
+}
+
+/// Helper struct for `cmp`: the inclusive bounds of a leading digit position, in i128
+/// since the exponent and the number of digits can each be close to the limits of i64.
+#[derive(Debug, Clone, Copy)]
+struct PositionRange(i128, i128);
+
+impl PositionRange {
+    /// The order of the magnitudes when the ranges do not overlap.
+    fn cmp_disjoint(self, other: PositionRange) -> Option<Ordering> {
+        if self.1 < other.0 {
+            Some(Ordering::Less)
+        } else if other.1 < self.0 {
+            Some(Ordering::Greater)
+        } else {
+            None
+        }
+    }
+}
+
+impl PartialOrd for BigDecimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Plain notation without an exponent, with as many fractional digits as the exponent
+/// requires, unless that takes more than `DISPLAY_MAX_ZEROS` zeros of padding: then
+/// scientific notation such as `1.25e+4000000000`, which `from_string` reads back with
+/// the same exponent.
+impl fmt::Display for BigDecimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut digits = self.unscaled.magnitude().to_string();
+        let padding = if self.exponent >= 0 {
+            i128::from(self.exponent)
+        } else {
+            i128::from(self.exponent).unsigned_abs() as i128 + 1 - digits.len() as i128
+        };
+        if !self.is_zero() && padding > DISPLAY_MAX_ZEROS as i128 {
+            let adjusted = i128::from(self.exponent) + digits.len() as i128 - 1;
+            if digits.len() > 1 {
+                digits.insert(1, '.');
+            }
+            let digits = format!("{}e{:+}", digits, adjusted);
+            return f.pad_integral(!self.unscaled.is_negative(), "", &digits);
+        }
+        if self.exponent >= 0 {
+            if !self.is_zero() {
+                digits.push_str(&"0".repeat(self.exponent as usize));
+            }
+        } else {
+            let fractional = self.exponent.unsigned_abs() as usize;
+            if digits.len() <= fractional {
+                digits = format!("{}{}", "0".repeat(fractional + 1 - digits.len()), digits);
+            }
+            digits.insert(digits.len() - fractional, '.');
+        }
+        f.pad_integral(!self.unscaled.is_negative(), "", &digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn dec(s: &str) -> BigDecimal {
+        BigDecimal::from_string(s).unwrap()
+    }
+
+    #[test]
+    fn test_from_string() {
+        assert_eq!(BigInt::from(-12034i32), *dec("-12.034").unscaled());
+        assert_eq!(-3, dec("-12.034").exponent());
+        assert_eq!(-2, dec("1.50").exponent());
+        assert_eq!(BigInt::from(15i32), *dec("1.5e-7").unscaled());
+        assert_eq!(-8, dec("1.5e-7").exponent());
+        assert_eq!(3, dec("+2E3").exponent());
+        assert_eq!(-1, dec(".5").exponent());
+        assert_eq!(BigInt::from(5i32), *dec(".5").unscaled());
+    }
+
+    #[test]
+    fn test_from_string_errors() {
+        let error = |s: &str| BigDecimal::from_string(s).unwrap_err().to_string();
+        assert_eq!("cannot parse BigDecimal: no digits", error(""));
+        assert_eq!("cannot parse BigDecimal: no digits", error("-.e5"));
+        assert_eq!("cannot parse BigDecimal: invalid digit", error("1.2.3"));
+        assert_eq!("cannot parse BigDecimal: invalid digit", error("12a"));
+        assert_eq!("cannot parse BigDecimal: invalid exponent", error("1.5e"));
+        assert_eq!(
+            "cannot parse BigDecimal: exponent out of range",
+            error("1e99999999999999999999")
+        );
+        assert_eq!(
+            "cannot parse BigDecimal: exponent out of range",
+            error("0.5e-9223372036854775808")
+        );
+        assert_eq!(i64::MIN, dec("5e-9223372036854775808").exponent());
+        assert_eq!(dec("1.5"), "1.5".parse().unwrap());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("-12.034", dec("-12.034").to_string());
+        assert_eq!("1.50", dec("1.50").to_string());
+        assert_eq!("0.00000015", dec("1.5e-7").to_string());
+        assert_eq!("2000", dec("2e3").to_string());
+        assert_eq!("0", dec("0e3").to_string());
+        assert_eq!("0.5", dec(".5").to_string());
+        assert_eq!("-0.05", dec("-0.05").to_string());
+        assert_eq!("+1.5", format!("{:+}", dec("1.5")));
+        let s = "-123456789012345678901234567890123456789.000000000000000000001";
+        assert_eq!(s, dec(s).to_string());
+
+        // Scientific notation beyond DISPLAY_MAX_ZEROS zeros of padding.
+        assert_eq!("1e+4000000000", dec("1e4000000000").to_string());
+        assert_eq!("-1.25e+4000000000", dec("-125e3999999998").to_string());
+        assert_eq!("1.5e-1002", dec("15e-1003").to_string());
+        assert_eq!("0", dec("0e4000000000").to_string());
+        assert_eq!(1001, dec("1e1000").to_string().len());
+        for s in ["1e4000000000", "-1.25e+4000000000", "1.50e-1002"] {
+            let value = dec(s);
+            let shown = dec(&value.to_string());
+            assert_eq!(value.exponent(), shown.exponent());
+            assert_eq!(value.unscaled(), shown.unscaled());
+        }
+    }
+
+    #[test]
+    fn test_eq_cmp() {
+        assert_eq!(dec("1.5"), dec("1.500"));
+        assert_eq!(dec("1500"), dec("1.5e3"));
+        assert_eq!(dec("0"), dec("-0.00"));
+        assert!(dec("1.49") < dec("1.5"));
+        assert!(dec("-1.49") > dec("-1.5"));
+        assert!(dec("1e-30") > dec("0"));
+
+        // Exponents too far apart to rescale, and exponents close to the limits.
+        assert!(dec("1e-5000000000") != dec("1"));
+        assert!(dec("1e-5000000000") < dec("1"));
+        assert!(dec("-1e-5000000000") > dec("-1"));
+        assert!(dec("1e300000") > dec("2"));
+        assert!(dec("-1e300000") < dec("2"));
+        assert!(dec("1e9223372036854775807") > dec("1e-9223372036854775808"));
+        assert!(dec("-1e9223372036854775807") < dec("-5e9223372036854775806"));
+        assert_eq!(
+            dec("12e9223372036854775806"),
+            dec("120e9223372036854775805")
+        );
+
+        // Leading digits in the same position, compared by rescaling.
+        assert!(dec("9.99") < dec("10"));
+        assert!(dec("999e-2") < dec("1.0e1"));
+        assert!(dec("1.0000000000000000000000000001") > dec("1"));
+        assert_eq!(dec("1024e-3"), dec("1.024"));
+        for &(a, b) in [(1023i32, 10240i32), (1023, 10231), (9, 89), (100, 999)].iter() {
+            let x = BigDecimal::new(BigInt::from(a), 0);
+            let y = BigDecimal::new(BigInt::from(b), -1);
+            assert_eq!((10 * a).cmp(&b), x.cmp(&y), "{} {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_add_sub_mul() {
+        assert_eq!("3.75", dec("1.25").add(&dec("2.5")).to_string());
+        assert_eq!("-1.25", dec("1.25").sub(&dec("2.5")).to_string());
+        assert_eq!("3.125", dec("1.25").mul(&dec("2.5")).to_string());
+        assert_eq!(
+            "170141183460469231731687303715884105728.01",
+            dec("170141183460469231731687303715884105727.99")
+                .add(&dec("0.02"))
+                .to_string()
+        );
+        assert_eq!("1000.5", dec("1e3").add(&dec("0.5")).to_string());
+    }
+
+    #[test]
+    fn test_div() {
+        let one = dec("1");
+        let three = dec("3");
+        assert_eq!("0.33", one.div(&three, 2, RoundingMode::HalfUp).to_string());
+        assert_eq!("0.34", one.div(&three, 2, RoundingMode::Up).to_string());
+        assert_eq!(
+            "-0.33",
+            one.div(&dec("-3"), 2, RoundingMode::Ceiling).to_string()
+        );
+        assert_eq!(
+            "-0.34",
+            one.div(&dec("-3"), 2, RoundingMode::Floor).to_string()
+        );
+        assert_eq!(
+            "0.667",
+            dec("2").div(&three, 3, RoundingMode::HalfEven).to_string()
+        );
+        assert_eq!(
+            "2.5",
+            dec("10").div(&dec("4"), 1, RoundingMode::Down).to_string()
+        );
+        assert_eq!(
+            "300",
+            dec("1e3").div(&three, -2, RoundingMode::HalfUp).to_string()
+        );
+        assert_eq!(
+            "12.5",
+            dec("0.25")
+                .div(&dec("0.02"), 1, RoundingMode::Down)
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_round() {
+        let cases = [
+            ("2.5", RoundingMode::HalfUp, "3"),
+            ("2.5", RoundingMode::HalfDown, "2"),
+            ("2.5", RoundingMode::HalfEven, "2"),
+            ("3.5", RoundingMode::HalfEven, "4"),
+            ("-2.5", RoundingMode::HalfUp, "-3"),
+            ("-2.5", RoundingMode::HalfEven, "-2"),
+            ("-2.1", RoundingMode::Floor, "-3"),
+            ("-2.9", RoundingMode::Ceiling, "-2"),
+            ("2.1", RoundingMode::Up, "3"),
+            ("2.9", RoundingMode::Down, "2"),
+            ("2.51", RoundingMode::HalfDown, "3"),
+        ];
+        for (value, mode, expected) in cases.iter() {
+            assert_eq!(*expected, dec(value).round(0, *mode).to_string());
+        }
+        assert_eq!(
+            "1.2300",
+            dec("1.23").round(4, RoundingMode::Down).to_string()
+        );
+        assert_eq!(
+            "1.24",
+            dec("1.235").round(2, RoundingMode::HalfEven).to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_exponent_overflow() {
+        dec("1e9223372036854775807").mul(&dec("1e1"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_power_of_ten_out_of_range() {
+        dec("1e5000000000").add(&dec("1"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_div_by_zero() {
+        dec("1").div(&dec("0.0"), 2, RoundingMode::HalfUp);
+    }
+}
@@ -0,0 +1,79 @@
+//! Classic finite field Diffie-Hellman parameters over a safe prime.
+//!
+//! Like `rsa_math`, nothing here runs in constant time: use it to learn or to test.
+
+use crate::optimized_memory::BigInt;
+use crate::prime::gen_safe_prime;
+use rand::Rng;
+
+/// A safe prime `p = 2q + 1` and a generator `g` of the subgroup of prime order `q`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhParams {
+    pub p: BigInt,
+    pub q: BigInt,
+    pub g: BigInt,
+}
+
+impl DhParams {
+    /// Parameters with a `bits`-bit safe prime whose primality, and that of `q`, passes
+    /// `rounds` rounds of Miller-Rabin.
+    ///
+    /// `g` is the smallest quadratic residue above 1: working in the subgroup of order
+    /// `q` means every public key other than 1 has the same large order.
+    ///
+    /// Panics if `bits` is smaller than 10.
+    pub fn generate<R: Rng + ?Sized>(rng: &mut R, bits: usize, rounds: usize) -> DhParams {
+        let p = gen_safe_prime(rng, bits, rounds);
+        let q = p.sub(&BigInt::one()) >> 1;
+        // 4 is always a quadratic residue, so the search stops there at the latest.
+        let g = (2..=4u32)
+            .map(BigInt::from)
+            .find(|g| g.modpow(&q, &p).is_one())
+            .unwrap();
+        DhParams { p, q, g }
+    }
+
+    /// `g^private mod p`.
+    pub fn public_key(&self, private: &BigInt) -> BigInt {
+        self.g.modpow(private, &self.p)
+    }
+
+    /// The secret shared with the owner of `other_public`: `other_public^private mod p`.
+    pub fn shared_secret(&self, private: &BigInt, other_public: &BigInt) -> BigInt {
+        other_public.modpow(private, &self.p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_generate() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let params = DhParams::generate(&mut rng, 96, 20);
+        assert_eq!(96, params.p.bits());
+        assert!(params.q.is_probable_prime(54));
+        assert_eq!(params.p, (params.q.clone() << 1).add(&BigInt::one()));
+        assert!(params.g.modpow(&params.q, &params.p).is_one());
+        assert!(!params.g.is_one());
+    }
+
+    #[test]
+    fn test_exchange() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let params = DhParams::generate(&mut rng, 64, 20);
+        let alice = BigInt::from(123456789u32);
+        let bob = BigInt::from_string("987654321987654321");
+        let a = params.public_key(&alice);
+        let b = params.public_key(&bob);
+        assert_ne!(a, b);
+        assert_eq!(
+            params.shared_secret(&alice, &b),
+            params.shared_secret(&bob, &a)
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Decimal digit utilities. They walk the base 10^9 chunks of the decimal conversion,
+//! one digit at a time, so no string of the whole number is ever built.
+
+use crate::optimized_memory::{decimal_chunks, BigInt};
+
+impl BigInt {
+    /// The sum of the decimal digits.
+    pub fn digit_sum(&self) -> u64 {
+        let chunks = decimal_chunks(self.as_limbs_le());
+        digits_le(&chunks).map(u64::from).sum()
+    }
+
+    /// The iterated digit sum, a single digit: 0 for zero, otherwise the value modulo 9
+    /// with 9 in place of 0.
+    pub fn digital_root(&self) -> u32 {
+        match self.digit_sum() {
+            0 => 0,
+            sum => 1 + ((sum - 1) % 9) as u32,
+        }
+    }
+
+    /// The number with the decimal digits in reverse order, so trailing zeros are lost.
+    pub fn reverse_decimal(&self) -> BigInt {
+        let chunks = decimal_chunks(self.as_limbs_le());
+        // Reversed, the most significant digits come first in the least significant chunk.
+        let mut reversed = Vec::with_capacity(chunks.len());
+        let (mut chunk, mut scale) = (0, 1);
+        for d in digits_be(&chunks) {
+            chunk += u32::from(d) * scale;
+            scale *= 10;
+            if scale == 1_000_000_000 {
+                reversed.push(chunk);
+                chunk = 0;
+                scale = 1;
+            }
+        }
+        if scale > 1 {
+            reversed.push(chunk);
+        }
+        let base = BigInt::from(1_000_000_000u32);
+        reversed.iter().rev().fold(BigInt::zero(), |acc, &c| {
+            acc.mul(&base).add(&BigInt::from(c))
+        })
+    }
+
+    /// Whether the decimal digits read the same in both directions.
+    pub fn is_decimal_palindrome(&self) -> bool {
+        let chunks = decimal_chunks(self.as_limbs_le());
+        let mut pairs = digits_le(&chunks).zip(digits_be(&chunks));
+        pairs.all(|(a, b)| a == b)
+    }
+}
+
+/// Helper function for the digit utilities: the digits of the chunks, least significant
+/// first. Every chunk has nine digits except the last, which has no leading zeros.
+fn digits_le(chunks: &[u32]) -> impl Iterator<Item = u8> + '_ {
+    chunks
+        .iter()
+        .enumerate()
+        .flat_map(move |(i, &c)| chunk_digits(c, chunk_width(chunks, i)))
+}
+
+/// Helper function for the digit utilities: the digits of the chunks, most significant
+/// first.
+fn digits_be(chunks: &[u32]) -> impl Iterator<Item = u8> + '_ {
+    chunks
+        .iter()
+        .enumerate()
+        .rev()
+        .flat_map(move |(i, &c)| chunk_digits(c, chunk_width(chunks, i)).rev())
+}
+
+/// Helper function for `digits_le` and `digits_be`: the number of digits of chunk `i`.
+fn chunk_width(chunks: &[u32], i: usize) -> u32 {
+    if i + 1 < chunks.len() {
+        9
+    } else {
+        chunks[i].ilog10() + 1
+    }
+}
+
+/// Helper function for `digits_le` and `digits_be`: the lowest `width` digits of `chunk`,
+/// least significant first.
+fn chunk_digits(chunk: u32, width: u32) -> impl DoubleEndedIterator<Item = u8> {
+    (0..width).map(move |i| (chunk / 10u32.pow(i) % 10) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_digit_sum_digital_root() {
+        assert_eq!(0, BigInt::zero().digit_sum());
+        assert_eq!(0, BigInt::zero().digital_root());
+        assert_eq!(45, BigInt::from(1234567890u32).digit_sum());
+        assert_eq!(9, BigInt::from(1234567890u32).digital_root());
+        assert_eq!(1, BigInt::from(1000000000000000000u64).digit_sum());
+        // The digits of 2^1000 sum to 1366.
+        let power = BigInt::one() << 1000;
+        assert_eq!(1366, power.digit_sum());
+        assert_eq!(7, power.digital_root());
+        for n in 1..200u32 {
+            assert_eq!(1 + (n - 1) % 9, BigInt::from(n).digital_root());
+        }
+    }
+
+    #[test]
+    fn test_reverse_decimal() {
+        assert_eq!(BigInt::zero(), BigInt::zero().reverse_decimal());
+        assert_eq!(
+            BigInt::from(321u32),
+            BigInt::from(1230u32).reverse_decimal()
+        );
+        assert_eq!(
+            BigInt::from_string("1000000000000000000001"),
+            BigInt::from_string("1000000000000000000001").reverse_decimal()
+        );
+        assert_eq!(
+            BigInt::from_string("98765432109876543210987654321"),
+            BigInt::from_string("12345678901234567890123456789").reverse_decimal()
+        );
+        let value = BigInt::from(3u32).pow(500);
+        let reversed: String = value.to_string().chars().rev().collect();
+        assert_eq!(BigInt::from_string(&reversed), value.reverse_decimal());
+    }
+
+    #[test]
+    fn test_is_decimal_palindrome() {
+        assert!(BigInt::zero().is_decimal_palindrome());
+        assert!(BigInt::from(7u32).is_decimal_palindrome());
+        assert!(BigInt::from(1000000001u32).is_decimal_palindrome());
+        assert!(!BigInt::from(1000000000u32).is_decimal_palindrome());
+        assert!(!BigInt::from(10u32).is_decimal_palindrome());
+        assert!(BigInt::from_string("12345678900987654321").is_decimal_palindrome());
+        assert!(!BigInt::from_string("12345678900987654320").is_decimal_palindrome());
+        let value = BigInt::from(3u32).pow(500);
+        assert!(value
+            .mul(&BigInt::from(10u32).pow(239))
+            .add(&value.reverse_decimal())
+            .is_decimal_palindrome());
+    }
+}
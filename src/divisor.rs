@@ -0,0 +1,166 @@
+//! Division of many BigInts by the same 64-bit divisor. The divisor is normalized and its
+//! reciprocal precomputed once, as described by Möller and Granlund in "Improved division
+//! by invariant integers", so each 64-bit word of a dividend costs two multiplications
+//! and a few corrections instead of a hardware division.
+
+use crate::optimized_memory::BigInt;
+
+/// A nonzero divisor with its precomputed reciprocal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divisor {
+    d: u64,
+    /// The divisor shifted left until its top bit is set.
+    normalized: u64,
+    shift: u32,
+    /// floor((2^128 - 1) / normalized) - 2^64.
+    reciprocal: u64,
+}
+
+impl Divisor {
+    /// Panics if `d` is zero.
+    pub fn new(d: u64) -> Divisor {
+        assert!(d != 0, "division by zero");
+        let shift = d.leading_zeros();
+        let normalized = d << shift;
+        let reciprocal = (u128::MAX / u128::from(normalized)) as u64;
+        Divisor {
+            d,
+            normalized,
+            shift,
+            reciprocal,
+        }
+    }
+
+    pub fn divisor(&self) -> u64 {
+        self.d
+    }
+
+    /// The quotient and the remainder of `n` by the divisor.
+    pub fn div_rem(&self, n: &BigInt) -> (BigInt, u64) {
+        let shifted = n.clone() << self.shift as usize;
+        let mut quotient = Vec::with_capacity(shifted.as_limbs_le().len() + 1);
+        let mut remainder = 0;
+        for word in shifted.iter_u64_chunks_be() {
+            let (q, r) = self.div_2by1(remainder, word);
+            quotient.push(q);
+            remainder = r;
+        }
+        let limbs: Vec<u32> = quotient
+            .iter()
+            .rev()
+            .flat_map(|&q| [q as u32, (q >> 32) as u32])
+            .collect();
+        (BigInt::from_limbs_le(&limbs), remainder >> self.shift)
+    }
+
+    pub fn div(&self, n: &BigInt) -> BigInt {
+        self.div_rem(n).0
+    }
+
+    /// The remainder alone, without building the quotient.
+    pub fn rem(&self, n: &BigInt) -> u64 {
+        let shifted = n.clone() << self.shift as usize;
+        let remainder = shifted
+            .iter_u64_chunks_be()
+            .fold(0, |r, word| self.div_2by1(r, word).1);
+        remainder >> self.shift
+    }
+
+    /// Divide `high * 2^64 + low` by the normalized divisor, for `high` below it.
+    fn div_2by1(&self, high: u64, low: u64) -> (u64, u64) {
+        let product = u128::from(self.reciprocal) * u128::from(high)
+            + ((u128::from(high) << 64) | u128::from(low));
+        let mut q = ((product >> 64) as u64).wrapping_add(1);
+        let mut r = low.wrapping_sub(q.wrapping_mul(self.normalized));
+        // The estimate is at most one too large, or one too small.
+        if r > product as u64 {
+            q = q.wrapping_sub(1);
+            r = r.wrapping_add(self.normalized);
+        }
+        if r >= self.normalized {
+            q += 1;
+            r -= self.normalized;
+        }
+        (q, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_div_2by1() {
+        let divisors = [
+            1u64 << 63,
+            (1 << 63) + 1,
+            u64::MAX,
+            u64::MAX - 1,
+            0xdeadbeefcafebabe,
+        ];
+        for &d in divisors.iter() {
+            let divisor = Divisor::new(d);
+            for &high in [0, 1, d / 2, d - 1].iter() {
+                for &low in [0, 1, 12345, u64::MAX].iter() {
+                    let n = (u128::from(high) << 64) | u128::from(low);
+                    let expected = ((n / u128::from(d)) as u64, (n % u128::from(d)) as u64);
+                    assert_eq!(
+                        expected,
+                        divisor.div_2by1(high, low),
+                        "{} {} {}",
+                        d,
+                        high,
+                        low
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let values = [
+            BigInt::zero(),
+            BigInt::one(),
+            BigInt::from(u64::MAX),
+            BigInt::from(3u32).pow(300),
+            BigInt::from_string("123456789012345678901234567890123456789"),
+            BigInt::one() << 1000,
+        ];
+        let divisors = [
+            1u64,
+            2,
+            3,
+            10,
+            1_000_000_000,
+            10u64.pow(19),
+            1 << 63,
+            u64::MAX,
+            4294967311,
+        ];
+        for &d in divisors.iter() {
+            let divisor = Divisor::new(d);
+            assert_eq!(d, divisor.divisor());
+            let big_d = BigInt::from(d);
+            for n in values.iter() {
+                let (q, r) = n.div_rem(&big_d);
+                assert_eq!(
+                    (q.clone(), r.to_u64().unwrap()),
+                    divisor.div_rem(n),
+                    "{} {}",
+                    n,
+                    d
+                );
+                assert_eq!(q, divisor.div(n));
+                assert_eq!(r.to_u64().unwrap(), divisor.rem(n));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_divisor() {
+        Divisor::new(0);
+    }
+}
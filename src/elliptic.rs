@@ -0,0 +1,217 @@
+//! Elliptic curves `y^2 = x^3 + a x + b` in short Weierstrass form over the integers modulo
+//! a prime, with the chord-and-tangent group law in affine coordinates.
+//!
+//! Everything here is variable-time: the time taken by a scalar multiplication depends on
+//! the bits of the scalar, so it must not be used with secret scalars where timing can
+//! be observed.
+
+use crate::modular::{ModInt, ModRing};
+use crate::optimized_memory::BigInt;
+
+/// A curve over the integers modulo a prime `p`.
+///
+/// The primality of `p` is not checked: with a composite `p` an addition may need an
+/// inverse that does not exist, and panics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Curve {
+    ring: ModRing,
+    a: ModInt,
+    b: ModInt,
+}
+
+/// A point of a curve, either the point at infinity, the identity of the group, or a
+/// point with affine coordinates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Point {
+    Infinity,
+    Affine { x: ModInt, y: ModInt },
+}
+
+impl Curve {
+    /// Panics if `p` is zero or if the curve is singular, `4 a^3 + 27 b^2 = 0 (mod p)`.
+    pub fn new(a: &BigInt, b: &BigInt, p: &BigInt) -> Curve {
+        let ring = ModRing::new(p.clone());
+        let (a, b) = (ring.element(a), ring.element(b));
+        let discriminant = &(&ring.element(&BigInt::from(4u32)) * &a.pow(&BigInt::from(3u32)))
+            + &(&ring.element(&BigInt::from(27u32)) * &(&b * &b));
+        assert!(!discriminant.value().is_zero(), "the curve is singular");
+        Curve { ring, a, b }
+    }
+
+    pub fn modulus(&self) -> &BigInt {
+        self.ring.modulus()
+    }
+
+    /// The point `(x, y)`, or `None` if it is not on the curve.
+    pub fn point(&self, x: &BigInt, y: &BigInt) -> Option<Point> {
+        let point = Point::Affine {
+            x: self.ring.element(x),
+            y: self.ring.element(y),
+        };
+        Some(point).filter(|p| self.contains(p))
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        match point {
+            Point::Infinity => true,
+            Point::Affine { x, y } => {
+                let right = &(&(&(x * x) * x) + &(&self.a * x)) + &self.b;
+                right == y * y
+            }
+        }
+    }
+
+    pub fn neg(&self, point: &Point) -> Point {
+        match point {
+            Point::Infinity => Point::Infinity,
+            Point::Affine { x, y } => Point::Affine {
+                x: x.clone(),
+                y: -y,
+            },
+        }
+    }
+
+    pub fn add(&self, p: &Point, q: &Point) -> Point {
+        let (x1, y1, x2, y2) = match (p, q) {
+            (Point::Infinity, _) => return q.clone(),
+            (_, Point::Infinity) => return p.clone(),
+            (Point::Affine { x: x1, y: y1 }, Point::Affine { x: x2, y: y2 }) => (x1, y1, x2, y2),
+        };
+        if x1 == x2 {
+            return if y1 == y2 {
+                self.double(p)
+            } else {
+                Point::Infinity
+            };
+        }
+        let slope = &(y2 - y1) * &invert(&(x2 - x1));
+        self.chord(&slope, x1, y1, x2)
+    }
+
+    pub fn double(&self, p: &Point) -> Point {
+        let (x, y) = match p {
+            Point::Infinity => return Point::Infinity,
+            Point::Affine { x, y } => (x, y),
+        };
+        if y.value().is_zero() {
+            return Point::Infinity;
+        }
+        let three = self.ring.element(&BigInt::from(3u32));
+        let slope = &(&(&three * &(x * x)) + &self.a) * &invert(&(y + y));
+        self.chord(&slope, x, y, x)
+    }
+
+    /// `k P`, by double-and-add from the top bit of `k`.
+    pub fn mul(&self, k: &BigInt, p: &Point) -> Point {
+        let mut result = Point::Infinity;
+        for bit in k.bits_be() {
+            result = self.double(&result);
+            if bit {
+                result = self.add(&result, p);
+            }
+        }
+        result
+    }
+
+    /// Helper function for `add` and `double`: the third point on the line of the given
+    /// slope through `(x1, y1)` and `(x2, _)`, reflected.
+    fn chord(&self, slope: &ModInt, x1: &ModInt, y1: &ModInt, x2: &ModInt) -> Point {
+        let x3 = &(&(slope * slope) - x1) - x2;
+        let y3 = &(slope * &(x1 - &x3)) - y1;
+        Point::Affine { x: x3, y: y3 }
+    }
+}
+
+/// Helper function for the group law: the inverse of a nonzero element.
+fn invert(x: &ModInt) -> ModInt {
+    x.inv()
+        .expect("no inverse: the modulus of the curve is not prime")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn secp256k1() -> (Curve, Point) {
+        let p = (BigInt::one() << 256)
+            .sub(&(BigInt::one() << 32))
+            .sub(&BigInt::from(977u32));
+        let curve = Curve::new(&BigInt::zero(), &BigInt::from(7u32), &p);
+        let generator = curve
+            .point(
+                &BigInt::from_string(
+                    "55066263022277343669578718895168534326250603453777594175500187360389116729240",
+                ),
+                &BigInt::from_string(
+                    "32670510020758816978083085130507043184471273380659243275938904335757337482424",
+                ),
+            )
+            .unwrap();
+        (curve, generator)
+    }
+
+    #[test]
+    fn test_group_law_small() {
+        // y^2 = x^3 + 2x + 3 modulo 97 has 100 points, counting infinity.
+        let curve = Curve::new(&BigInt::two(), &BigInt::from(3u32), &BigInt::from(97u32));
+        let mut points = vec![Point::Infinity];
+        for x in 0..97u32 {
+            for y in 0..97u32 {
+                if let Some(p) = curve.point(&BigInt::from(x), &BigInt::from(y)) {
+                    points.push(p);
+                }
+            }
+        }
+        assert_eq!(100, points.len());
+        let order = BigInt::from(100u32);
+        for p in points.iter() {
+            assert_eq!(Point::Infinity, curve.mul(&order, p));
+            assert_eq!(Point::Infinity, curve.add(p, &curve.neg(p)));
+            assert_eq!(curve.double(p), curve.add(p, p));
+            assert_eq!(curve.double(p), curve.mul(&BigInt::two(), p));
+        }
+        for p in points.iter().step_by(7) {
+            for q in points.iter().step_by(11) {
+                let sum = curve.add(p, q);
+                assert!(curve.contains(&sum));
+                assert_eq!(sum, curve.add(q, p));
+                for r in points.iter().step_by(23) {
+                    assert_eq!(curve.add(&sum, r), curve.add(p, &curve.add(q, r)));
+                }
+            }
+        }
+        assert_eq!(None, curve.point(&BigInt::zero(), &BigInt::zero()));
+    }
+
+    #[test]
+    fn test_secp256k1() {
+        let (curve, g) = secp256k1();
+        let double = curve
+            .point(
+                &BigInt::from_string(
+                    "89565891926547004231252920425935692360644145829622209833684329913297188986597",
+                ),
+                &BigInt::from_string(
+                    "12158399299693830322967808612713398636155367887041628176798871954788371653930",
+                ),
+            )
+            .unwrap();
+        assert_eq!(double, curve.double(&g));
+        let n = BigInt::from_string(
+            "115792089237316195423570985008687907852837564279074904382605163141518161494337",
+        );
+        assert_eq!(Point::Infinity, curve.mul(&n, &g));
+        assert_eq!(curve.neg(&g), curve.mul(&n.sub(&BigInt::one()), &g));
+        let a = BigInt::from_string("123456789012345678901234567890");
+        let b = BigInt::from_string("987654321098765432109876543210");
+        assert_eq!(curve.mul(&a.mul(&b), &g), curve.mul(&a, &curve.mul(&b, &g)));
+        assert_eq!(Point::Infinity, curve.mul(&BigInt::zero(), &g));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_singular_curve() {
+        Curve::new(&BigInt::zero(), &BigInt::zero(), &BigInt::from(97u32));
+    }
+}
@@ -0,0 +1,1010 @@
+//! Integer factorization.
+
+use crate::limb_ops;
+use crate::modular::{ModInt, ModRing};
+use crate::optimized_memory::{BigInt, Cancelled};
+use crate::prime::primes_in_range;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Iterations of Brent's cycle search between two gcds.
+const RHO_BATCH: usize = 128;
+
+/// Polynomials `x^2 + c` that `pollard_rho` tries before giving up.
+pub(crate) const RHO_ATTEMPTS: u32 = 50;
+
+/// `factor` divides out the primes below this bound before anything else.
+pub const FACTOR_TRIAL_BOUND: u32 = 1 << 16;
+
+/// The step budget of each rho attempt in `factor`.
+pub const FACTOR_RHO_STEPS: usize = 1 << 16;
+
+/// The factor sizes, in digits, that `factor` then runs ECM for.
+pub const FACTOR_ECM_DIGITS: [u32; 3] = [15, 20, 25];
+
+/// The giant step of the ECM stage 2, a multiple of 2, 3, 5 and 7 so that few baby steps
+/// are coprime with it.
+const ECM_GIANT_STEP: u64 = 210;
+
+/// Bounds for `ecm`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EcmParams {
+    /// Number of curves to try.
+    pub curves: u32,
+    /// Stage 1 multiplies the starting point by every prime power up to `b1`.
+    pub b1: u32,
+    /// Stage 2 then looks for a single remaining prime of the group order up to `b2`.
+    pub b2: u64,
+}
+
+impl EcmParams {
+    /// Bounds that find a factor of about `digits` digits with good probability, after
+    /// the usual tables, with `b2 = 100 * b1`.
+    pub fn for_digits(digits: u32) -> EcmParams {
+        let (b1, curves) = match digits {
+            0..=15 => (2000, 25),
+            16..=20 => (11000, 90),
+            21..=25 => (50000, 300),
+            26..=30 => (250000, 700),
+            _ => (1000000, 1800),
+        };
+        EcmParams {
+            curves,
+            b1,
+            b2: 100 * b1 as u64,
+        }
+    }
+}
+
+impl Default for EcmParams {
+    /// The bounds for 20-digit factors.
+    fn default() -> EcmParams {
+        EcmParams::for_digits(20)
+    }
+}
+
+/// The largest limit whose primes `primes_below` keeps for later calls.
+pub const PRIME_TABLE_LIMIT: u32 = 1 << 24;
+
+/// The primes below the largest limit requested so far, at most `PRIME_TABLE_LIMIT`,
+/// shared by every caller.
+static PRIME_TABLE: Mutex<(u32, Option<Arc<Vec<u32>>>)> = Mutex::new((0, None));
+
+/// The primes below `limit` in increasing order, and possibly a few more.
+///
+/// Up to `PRIME_TABLE_LIMIT`, the sieve of Eratosthenes runs once for the largest limit
+/// seen, taking a byte per number, and smaller requests reuse it. Larger limits are
+/// served by the segmented sieve of `primes_in_range` and not kept, so only the returned
+/// primes take memory.
+pub fn primes_below(limit: u32) -> Arc<Vec<u32>> {
+    if limit > PRIME_TABLE_LIMIT {
+        return Arc::new(segmented_primes_below(limit));
+    }
+    let mut table = PRIME_TABLE.lock().unwrap();
+    if let (cached, Some(primes)) = &*table {
+        if *cached >= limit {
+            return primes.clone();
+        }
+    }
+    let primes = Arc::new(sieve(limit));
+    *table = (limit, Some(primes.clone()));
+    primes
+}
+
+/// Helper function for `primes_below` above `PRIME_TABLE_LIMIT`.
+fn segmented_primes_below(limit: u32) -> Vec<u32> {
+    primes_in_range(&BigInt::zero(), &BigInt::from(limit))
+        .map(|p| p.to_u32().unwrap())
+        .collect()
+}
+
+/// Helper function for `primes_below`: the sieve of Eratosthenes.
+fn sieve(limit: u32) -> Vec<u32> {
+    let limit = limit as usize;
+    let mut composite = vec![false; limit];
+    let mut primes = Vec::new();
+    for n in 2..limit {
+        if !composite[n] {
+            primes.push(n as u32);
+            for multiple in (n * n..limit).step_by(n) {
+                composite[multiple] = true;
+            }
+        }
+    }
+    primes
+}
+
+/// The prime factors of a value, possibly with a composite part that resisted every
+/// method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Factorization {
+    factors: BTreeMap<BigInt, u32>,
+    cofactor: BigInt,
+}
+
+impl Factorization {
+    /// The primes found, mapped to their exponents.
+    pub fn factors(&self) -> &BTreeMap<BigInt, u32> {
+        &self.factors
+    }
+
+    /// The product of the parts that could not be factored: 1 when the factorization is
+    /// complete, and 0 for the factorization of 0.
+    pub fn cofactor(&self) -> &BigInt {
+        &self.cofactor
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.cofactor.is_one()
+    }
+
+    /// The factored value, as the product of the prime powers and the cofactor.
+    pub fn value(&self) -> BigInt {
+        self.factors
+            .iter()
+            .map(|(p, &e)| p.pow(e))
+            .fold(self.cofactor.clone(), |acc, power| acc.mul(&power))
+    }
+
+    /// The number of divisors, the product of `e + 1` over the exponents `e`.
+    ///
+    /// Panics if the factorization is incomplete, like the other divisor functions.
+    pub fn num_divisors(&self) -> BigInt {
+        self.check_complete();
+        self.factors
+            .values()
+            .map(|&e| BigInt::from(e as u64 + 1))
+            .product()
+    }
+
+    /// The sum of the divisors, the product of `(p^(e+1) - 1) / (p - 1)` over the prime
+    /// powers `p^e`.
+    pub fn sum_of_divisors(&self) -> BigInt {
+        self.check_complete();
+        self.factors
+            .iter()
+            .map(|(p, &e)| p.pow(e + 1).sub(&BigInt::one()).div(&p.sub(&BigInt::one())))
+            .product()
+    }
+
+    /// All the divisors in increasing order, 1 and the value included.
+    pub fn divisors(&self) -> impl Iterator<Item = BigInt> {
+        self.check_complete();
+        let mut divisors = vec![BigInt::one()];
+        for (p, &e) in self.factors.iter() {
+            let mut with_p = Vec::with_capacity(divisors.len() * (e as usize + 1));
+            for d in divisors.iter() {
+                let mut multiple = d.clone();
+                with_p.push(multiple.clone());
+                for _ in 0..e {
+                    multiple = multiple.mul(p);
+                    with_p.push(multiple.clone());
+                }
+            }
+            divisors = with_p;
+        }
+        divisors.sort_unstable();
+        divisors.into_iter()
+    }
+
+    /// Euler's totient, the number of values in `[1, n]` coprime with `n`: the product of
+    /// `p^(e-1) * (p - 1)` over the prime powers.
+    pub fn euler_phi(&self) -> BigInt {
+        self.check_complete();
+        self.factors
+            .iter()
+            .map(|(p, &e)| p.pow(e - 1).mul(&p.sub(&BigInt::one())))
+            .product()
+    }
+
+    /// Carmichael's function, the exponent of the multiplicative group modulo `n`: the
+    /// least common multiple of `p^(e-1) * (p - 1)` over the prime powers, except that
+    /// `2^e` contributes only `2^(e-2)` from `e = 3` on.
+    pub fn carmichael_lambda(&self) -> BigInt {
+        self.check_complete();
+        self.factors
+            .iter()
+            .map(|(p, &e)| {
+                if *p == 2u32 && e >= 3 {
+                    BigInt::one() << (e as usize - 2)
+                } else {
+                    p.pow(e - 1).mul(&p.sub(&BigInt::one()))
+                }
+            })
+            .fold(BigInt::one(), |acc, l| acc.lcm(&l))
+    }
+
+    fn check_complete(&self) {
+        assert!(self.is_complete(), "the factorization is incomplete");
+    }
+
+    fn insert(&mut self, prime: BigInt, exponent: u32) {
+        *self.factors.entry(prime).or_insert(0) += exponent;
+    }
+}
+
+impl BigInt {
+    /// Factor with the default strategy: trial division below `FACTOR_TRIAL_BOUND`,
+    /// Pollard rho with `FACTOR_RHO_STEPS` steps per attempt, then ECM with the bounds for
+    /// each size in `FACTOR_ECM_DIGITS`.
+    ///
+    /// Prime factors that are all below about 25 digits, except maybe the largest, are
+    /// found with good probability; anything left over is reported as the cofactor.
+    pub fn factor(&self) -> Factorization {
+        self.factor_with(FACTOR_RHO_STEPS, &default_ecm())
+    }
+
+    /// Factor by trial division below `FACTOR_TRIAL_BOUND`, then splitting the composite
+    /// parts with rho limited to `rho_steps` steps per attempt, then each of the `ecm`
+    /// settings in turn. Primality is decided by `is_prime_bpsw`.
+    pub fn factor_with(&self, rho_steps: usize, ecm: &[EcmParams]) -> Factorization {
+        self.factor_with_until(rho_steps, ecm, &|| false, &mut |_, _| {})
+            .unwrap_or_else(|cancelled| cancelled.partial)
+    }
+
+    /// `factor`, checking `should_stop` before each rho polynomial and each ECM curve. A
+    /// cancelled run returns what it found so far, with the unfactored parts in the
+    /// cofactor.
+    pub fn factor_until(
+        &self,
+        should_stop: &dyn Fn() -> bool,
+    ) -> Result<Factorization, Cancelled<Factorization>> {
+        self.factor_with_until(
+            FACTOR_RHO_STEPS,
+            &default_ecm(),
+            should_stop,
+            &mut |_, _| {},
+        )
+    }
+
+    /// `factor`, calling `progress(done, total)` whenever a part is settled, as a prime
+    /// factor or as cofactor, where `total` is the bit length of the value and `done` the
+    /// bit length settled so far, approximately, up to `done == total`.
+    pub fn factor_with_progress(&self, progress: &mut dyn FnMut(u64, u64)) -> Factorization {
+        self.factor_with_until(FACTOR_RHO_STEPS, &default_ecm(), &|| false, progress)
+            .unwrap_or_else(|cancelled| cancelled.partial)
+    }
+
+    /// Helper function for `factor_with`, `factor_until` and `factor_with_progress`.
+    fn factor_with_until(
+        &self,
+        rho_steps: usize,
+        ecm: &[EcmParams],
+        should_stop: &dyn Fn() -> bool,
+        progress: &mut dyn FnMut(u64, u64),
+    ) -> Result<Factorization, Cancelled<Factorization>> {
+        let total = self.bits() as u64;
+        let (small, rest) = self.small_factors(FACTOR_TRIAL_BOUND);
+        let mut result = Factorization {
+            factors: BTreeMap::new(),
+            cofactor: BigInt::one(),
+        };
+        if rest.is_zero() {
+            result.cofactor = rest;
+            progress(total, total);
+            return Ok(result);
+        }
+        for (p, exponent) in small {
+            result.insert(BigInt::from(p), exponent);
+        }
+        // The bit lengths of the parts add up to `total` give or take one per part.
+        let mut done = total.saturating_sub(rest.bits() as u64);
+        progress(done, total);
+        let mut pending = vec![rest];
+        while let Some(m) = pending.pop() {
+            if m.is_one() {
+                continue;
+            }
+            if m.is_prime_bpsw() {
+                done = total.min(done + m.bits() as u64);
+                result.insert(m, 1);
+                progress(done, total);
+                continue;
+            }
+            match m.split_until(rho_steps, ecm, should_stop) {
+                Ok(Some(d)) => {
+                    pending.push(m.div(&d));
+                    pending.push(d);
+                }
+                Ok(None) => {
+                    done = total.min(done + m.bits() as u64);
+                    result.cofactor = result.cofactor.mul(&m);
+                    progress(done, total);
+                }
+                Err(_) => {
+                    for part in pending.iter().chain(Some(&m)) {
+                        result.cofactor = result.cofactor.mul(part);
+                    }
+                    return Err(Cancelled { partial: result });
+                }
+            }
+        }
+        if done < total {
+            progress(total, total);
+        }
+        Ok(result)
+    }
+
+    /// `self.factor().euler_phi()`.
+    ///
+    /// Panics if the factorization is incomplete, zero included.
+    pub fn euler_phi(&self) -> BigInt {
+        self.factor().euler_phi()
+    }
+
+    /// `self.factor().carmichael_lambda()`.
+    ///
+    /// Panics if the factorization is incomplete, zero included.
+    pub fn carmichael_lambda(&self) -> BigInt {
+        self.factor().carmichael_lambda()
+    }
+
+    /// Whether no square other than 1 divides the value; zero is not squarefree.
+    ///
+    /// Panics if the value cannot be factored far enough by `factor`'s strategy.
+    pub fn is_squarefree(&self) -> bool {
+        self.squarefree_prime_count().is_some()
+    }
+
+    /// The Moebius function: 0 if a square other than 1 divides the value (zero
+    /// included), otherwise 1 or -1 for an even or odd number of prime factors.
+    ///
+    /// Panics if the value cannot be factored far enough by `factor`'s strategy.
+    pub fn moebius(&self) -> i32 {
+        match self.squarefree_prime_count() {
+            None => 0,
+            Some(count) if count % 2 == 0 => 1,
+            Some(_) => -1,
+        }
+    }
+
+    /// Helper function for `is_squarefree` and `moebius`: the number of prime factors of a
+    /// squarefree value, `None` as soon as a square factor shows up.
+    fn squarefree_prime_count(&self) -> Option<usize> {
+        if self.is_zero() {
+            return None;
+        }
+        let (small, rest) = self.small_factors(FACTOR_TRIAL_BOUND);
+        if small.iter().any(|&(_, e)| e > 1) {
+            return None;
+        }
+        let ecm = default_ecm();
+        let mut count = small.len();
+        let mut pending = vec![rest];
+        while let Some(m) = pending.pop() {
+            if m.is_one() {
+                continue;
+            }
+            if m.sqrt().pow(2) == m {
+                return None;
+            }
+            if m.is_prime_bpsw() {
+                count += 1;
+                continue;
+            }
+            let d = m
+                .split(FACTOR_RHO_STEPS, &ecm)
+                .expect("could not factor the value far enough");
+            let cofactor = m.div(&d);
+            // A prime dividing both parts divides the value twice.
+            if !d.gcd(&cofactor).is_one() {
+                return None;
+            }
+            pending.push(cofactor);
+            pending.push(d);
+        }
+        Some(count)
+    }
+
+    /// Helper function for the factorization pipeline: a proper factor of a composite
+    /// by rho, then ECM.
+    fn split(&self, rho_steps: usize, ecm: &[EcmParams]) -> Option<BigInt> {
+        self.split_until(rho_steps, ecm, &|| false).ok().flatten()
+    }
+
+    /// Helper function for `split`, for an odd composite without small factors: checks
+    /// `should_stop` before each rho polynomial and each ECM curve.
+    fn split_until(
+        &self,
+        rho_steps: usize,
+        ecm: &[EcmParams],
+        should_stop: &dyn Fn() -> bool,
+    ) -> Result<Option<BigInt>, Cancelled<()>> {
+        let ring = ModRing::new(self.clone());
+        for c in 1..=RHO_ATTEMPTS {
+            if should_stop() {
+                return Err(Cancelled { partial: () });
+            }
+            match brent(&ring, &ring.element(&BigInt::from(c)), rho_steps) {
+                Some(g) if g == *self => continue,
+                Some(g) => return Ok(Some(g)),
+                None => break,
+            }
+        }
+        for params in ecm {
+            for sigma in 6..6 + params.curves {
+                if should_stop() {
+                    return Err(Cancelled { partial: () });
+                }
+                if let Some(d) = ecm_curve(&ring, sigma, params) {
+                    return Ok(Some(d));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// The prime factors below `limit` with their exponents, in increasing order, and
+    /// the cofactor left once they are divided out. Zero has no factors and is its own
+    /// cofactor.
+    ///
+    /// The primes come from `primes_below`, so repeated calls share the sieve.
+    pub fn small_factors(&self, limit: u32) -> (Vec<(u32, u32)>, BigInt) {
+        let mut factors = Vec::new();
+        let mut rest = self.clone();
+        if rest.is_zero() {
+            return (factors, rest);
+        }
+        for &p in primes_below(limit).iter().take_while(|&&p| p < limit) {
+            // The cofactor is now 1 or a prime.
+            if rest < p as u64 * p as u64 {
+                if rest > 1u32 && rest < limit {
+                    factors.push((rest.to_u32().unwrap(), 1));
+                    rest = BigInt::one();
+                }
+                break;
+            }
+            let mut exponent = 0;
+            while limb_ops::mod_1(rest.as_limbs_le(), p) == 0 {
+                rest = rest.div(&BigInt::from(p));
+                exponent += 1;
+            }
+            if exponent > 0 {
+                factors.push((p, exponent));
+            }
+        }
+        (factors, rest)
+    }
+
+    /// A nontrivial factor by Pollard's rho method with Brent's cycle detection, or `None`
+    /// if the value is below 4, prime, or resisted every attempt.
+    ///
+    /// The products of `RHO_BATCH` differences are accumulated before taking a gcd. The
+    /// expected number of steps is about the square root of the smallest prime factor,
+    /// which keeps factors of up to 20 digits or so within reach.
+    pub fn pollard_rho(&self) -> Option<BigInt> {
+        if *self < 4u32 || self.is_prime_bpsw() {
+            return None;
+        }
+        if self.is_even() {
+            return Some(BigInt::two());
+        }
+        self.pollard_rho_bounded(usize::MAX)
+    }
+
+    /// Helper function for `pollard_rho` and `factor_with`: gives up after about
+    /// `2 * max_steps` steps without a cycle. Only a cycle that reveals no proper factor
+    /// leads to another polynomial.
+    fn pollard_rho_bounded(&self, max_steps: usize) -> Option<BigInt> {
+        let ring = ModRing::new(self.clone());
+        for c in 1..=RHO_ATTEMPTS {
+            let g = brent(&ring, &ring.element(&BigInt::from(c)), max_steps)?;
+            if g != *self {
+                return Some(g);
+            }
+        }
+        None
+    }
+
+    /// A nontrivial factor by Lenstra's elliptic curve method, or `None` if the value is
+    /// below 4, prime, or no curve succeeded.
+    ///
+    /// Each curve is a Montgomery curve from Suyama's family, with `sigma = 6, 7, ...`,
+    /// and works in `x`-only projective coordinates, so no inversion is needed after the
+    /// setup. The running time depends on the size of the factor found, not on the value,
+    /// which makes ECM the method of choice for 20 to 35 digit factors of large numbers.
+    pub fn ecm(&self, params: &EcmParams) -> Option<BigInt> {
+        if *self < 4u32 || self.is_prime_bpsw() {
+            return None;
+        }
+        if self.is_even() {
+            return Some(BigInt::two());
+        }
+        let ring = ModRing::new(self.clone());
+        (6..6 + params.curves).find_map(|sigma| ecm_curve(&ring, sigma, params))
+    }
+}
+
+/// A point of a Montgomery curve in projective `(X : Z)` coordinates, with the sign of
+/// `y` forgotten.
+#[derive(Debug, Clone)]
+struct Point {
+    x: ModInt,
+    z: ModInt,
+}
+
+/// A Montgomery curve `B y^2 = x^3 + A x^2 + x`, of which only `(A + 2) / 4` is needed.
+struct Curve {
+    a24: ModInt,
+}
+
+impl Curve {
+    fn double(&self, p: &Point) -> Point {
+        let sum = &p.x + &p.z;
+        let difference = &p.x - &p.z;
+        let sum = &sum * &sum;
+        let difference = &difference * &difference;
+        let t = &sum - &difference;
+        Point {
+            x: &sum * &difference,
+            z: &t * &(&difference + &(&self.a24 * &t)),
+        }
+    }
+
+    /// `p + q`, given `p - q`.
+    fn add(p: &Point, q: &Point, difference: &Point) -> Point {
+        let u = &(&p.x - &p.z) * &(&q.x + &q.z);
+        let v = &(&p.x + &p.z) * &(&q.x - &q.z);
+        let sum = &u + &v;
+        let diff = &u - &v;
+        Point {
+            x: &difference.z * &(&sum * &sum),
+            z: &difference.x * &(&diff * &diff),
+        }
+    }
+
+    /// `k * p` for `k >= 1`, by the Montgomery ladder.
+    fn multiply(&self, k: u64, p: &Point) -> Point {
+        let mut low = p.clone();
+        let mut high = self.double(p);
+        for i in (0..63 - k.leading_zeros()).rev() {
+            if (k >> i) & 1 == 1 {
+                low = Curve::add(&high, &low, p);
+                high = self.double(&high);
+            } else {
+                high = Curve::add(&high, &low, p);
+                low = self.double(&low);
+            }
+        }
+        low
+    }
+}
+
+/// The ECM settings of `factor`, from `FACTOR_ECM_DIGITS`.
+fn default_ecm() -> Vec<EcmParams> {
+    FACTOR_ECM_DIGITS
+        .iter()
+        .map(|&digits| EcmParams::for_digits(digits))
+        .collect()
+}
+
+/// Helper function for `ecm`: both stages on the curve for `sigma`.
+pub(crate) fn ecm_curve(ring: &ModRing, sigma: u32, params: &EcmParams) -> Option<BigInt> {
+    let n = ring.modulus();
+    let small = |k: u32| ring.element(&BigInt::from(k));
+    let sigma = small(sigma);
+    let u = &(&sigma * &sigma) - &small(5);
+    let v = &sigma * &small(4);
+    let u3 = &(&u * &u) * &u;
+    let v_minus_u = &v - &u;
+    let numerator = &(&(&v_minus_u * &v_minus_u) * &v_minus_u) * &(&(&small(3) * &u) + &v);
+    let denominator = &(&small(16) * &u3) * &v;
+    let inverse = match denominator.inv() {
+        Some(inverse) => inverse,
+        // A lucky failure of the setup.
+        None => return proper_factor(denominator.value().gcd(n), n),
+    };
+    let curve = Curve {
+        a24: &numerator * &inverse,
+    };
+    let mut q = Point {
+        x: u3,
+        z: &(&v * &v) * &v,
+    };
+
+    for &p in primes_below(params.b1 + 1)
+        .iter()
+        .take_while(|&&p| p <= params.b1)
+    {
+        let mut power = p as u64;
+        while power * p as u64 <= params.b1 as u64 {
+            power *= p as u64;
+        }
+        q = curve.multiply(power, &q);
+    }
+    let g = q.z.value().gcd(n);
+    if !g.is_one() {
+        return proper_factor(g, n);
+    }
+
+    // Stage 2: any prime of the order up to b2 is k * D +- j with j < D / 2 odd and
+    // coprime with D, so x(k D q) = x(j q) modulo the factor and the products below
+    // vanish.
+    let d = ECM_GIANT_STEP;
+    let twice = curve.double(&q);
+    let mut baby = Vec::new();
+    let (mut previous, mut current) = (q.clone(), q.clone());
+    for j in (1..d / 2).step_by(2) {
+        if j % 3 != 0 && j % 5 != 0 && j % 7 != 0 {
+            baby.push(current.clone());
+        }
+        let next = Curve::add(&current, &twice, &previous);
+        previous = std::mem::replace(&mut current, next);
+    }
+    let first = (params.b1 as u64 / d).max(2);
+    let step = curve.multiply(d, &q);
+    let mut giant_previous = curve.multiply((first - 1) * d, &q);
+    let mut giant = curve.multiply(first * d, &q);
+    let mut product = ring.one();
+    for _ in first..=params.b2 / d + 1 {
+        for b in baby.iter() {
+            product = &product * &(&(&giant.x * &b.z) - &(&b.x * &giant.z));
+        }
+        let next = Curve::add(&giant, &step, &giant_previous);
+        giant_previous = std::mem::replace(&mut giant, next);
+    }
+    proper_factor(product.value().gcd(n), n)
+}
+
+/// `g` if it is a proper factor of `n`.
+pub(crate) fn proper_factor(g: BigInt, n: &BigInt) -> Option<BigInt> {
+    if g.is_one() || g == *n {
+        None
+    } else {
+        Some(g)
+    }
+}
+
+/// Helper function for `pollard_rho`: one run of Brent's variant for `x^2 + c`. Returns
+/// the gcd that ended the search, which is `n` itself if the cycle closed without
+/// revealing a proper factor, or `None` if no cycle was found within about
+/// `2 * max_steps` steps.
+fn brent(ring: &ModRing, c: &ModInt, max_steps: usize) -> Option<BigInt> {
+    let mut y = ring.element(&BigInt::two());
+    let mut r = 1;
+    loop {
+        if let Some(g) = brent_round(ring, c, &mut y, r) {
+            return Some(g);
+        }
+        if r > max_steps {
+            return None;
+        }
+        r *= 2;
+    }
+}
+
+/// Helper function for `brent`: the round of length `r`, comparing `y` with each of its
+/// next `r` iterates after skipping `r` of them, which leaves `y` where the next round
+/// starts. Returns the gcd that ended the round if it is not 1.
+fn brent_round(ring: &ModRing, c: &ModInt, y: &mut ModInt, r: usize) -> Option<BigInt> {
+    let mut round = BrentRound::start(ring, y.clone());
+    let g = round.advance(ring, c, r, usize::MAX);
+    *y = round.y;
+    g
+}
+
+/// A round of Brent's cycle search between two batches of iterations, so that a long
+/// round can be run, and saved, piece by piece.
+#[derive(Debug, Clone)]
+pub(crate) struct BrentRound {
+    /// The iterate the round compares with.
+    pub(crate) x: ModInt,
+    /// The current iterate.
+    pub(crate) y: ModInt,
+    /// The number of iterations done in the round, the `r` skipped ones included.
+    pub(crate) k: usize,
+    /// The product of the differences compared so far.
+    pub(crate) product: ModInt,
+}
+
+impl BrentRound {
+    /// The round starting from `y`.
+    pub(crate) fn start(ring: &ModRing, y: ModInt) -> BrentRound {
+        BrentRound {
+            x: y.clone(),
+            y,
+            k: 0,
+            product: ring.one(),
+        }
+    }
+
+    /// Whether all `2 * r` iterations of the round of length `r` are done.
+    pub(crate) fn is_done(&self, r: usize) -> bool {
+        self.k >= 2 * r
+    }
+
+    /// Run about `budget` more iterations of the round of length `r`, stopping only
+    /// between two batches, and return the gcd that ended the round if it is not 1.
+    pub(crate) fn advance(
+        &mut self,
+        ring: &ModRing,
+        c: &ModInt,
+        r: usize,
+        mut budget: usize,
+    ) -> Option<BigInt> {
+        let n = ring.modulus();
+        let step = |x: &ModInt| &(x * x) + c;
+        while self.k < r && budget > 0 {
+            self.y = step(&self.y);
+            self.k += 1;
+            budget -= 1;
+        }
+        while self.k < 2 * r && budget > 0 {
+            let mut saved = self.y.clone();
+            let batch = RHO_BATCH.min(2 * r - self.k);
+            for _ in 0..batch {
+                self.y = step(&self.y);
+                self.product = &self.product * &(&self.x - &self.y);
+            }
+            self.k += batch;
+            budget = budget.saturating_sub(batch);
+            let g = self.product.value().gcd(n);
+            if g == *n {
+                // The batch overshot: replay it one step at a time.
+                loop {
+                    saved = step(&saved);
+                    let g = (&self.x - &saved).value().gcd(n);
+                    if !g.is_one() {
+                        return Some(g);
+                    }
+                }
+            }
+            if !g.is_one() {
+                return Some(g);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_primes_below() {
+        assert_eq!(vec![2, 3, 5, 7], *sieve(10));
+        assert!(sieve(2).is_empty());
+        let primes = primes_below(10000);
+        assert_eq!(1229, primes.iter().take_while(|&&p| p < 10000).count());
+        assert!(primes_below(100).starts_with(&[2, 3, 5, 7, 11]));
+        for &limit in [0, 3, 4, 1000, 70001, 200_000].iter() {
+            assert_eq!(sieve(limit), segmented_primes_below(limit), "{}", limit);
+        }
+        assert!(PRIME_TABLE.lock().unwrap().0 <= PRIME_TABLE_LIMIT);
+    }
+
+    #[test]
+    fn test_small_factors() {
+        let n = BigInt::from(1024u32 * 243 * 101).mul(&BigInt::from(1000003u32));
+        assert_eq!(
+            (vec![(2, 10), (3, 5), (101, 1)], BigInt::from(1000003u32)),
+            n.small_factors(1000)
+        );
+        assert_eq!(
+            (vec![(2, 10), (3, 5), (101, 1), (1000003, 1)], BigInt::one()),
+            n.small_factors(2000000)
+        );
+        // The prime 1000003 is not below the limit.
+        assert_eq!(
+            (vec![(2, 10), (3, 5), (101, 1)], BigInt::from(1000003u32)),
+            n.small_factors(1000003)
+        );
+        assert_eq!((vec![], BigInt::one()), BigInt::one().small_factors(100));
+        assert_eq!((vec![], BigInt::zero()), BigInt::zero().small_factors(100));
+        assert_eq!(
+            (vec![(7, 2)], BigInt::one()),
+            BigInt::from(49u32).small_factors(8)
+        );
+    }
+
+    #[test]
+    fn test_pollard_rho() {
+        let composites = [
+            BigInt::from(8051u32),
+            BigInt::from(10403u32),
+            BigInt::from(1000000007u64 * 998244353),
+            (BigInt::one() << 64).add(&BigInt::one()),
+            (BigInt::one() << 67).sub(&BigInt::one()),
+            BigInt::from(65537u64 * 65537),
+        ];
+        for n in composites.iter() {
+            let factor = n.pollard_rho().unwrap();
+            assert!(factor > 1u32 && factor < *n, "{}", n);
+            assert!(n.rem(&factor).is_zero(), "{}", n);
+        }
+        assert_eq!(Some(BigInt::two()), BigInt::from(1u32 << 20).pollard_rho());
+        assert_eq!(None, BigInt::one().pollard_rho());
+        assert_eq!(None, BigInt::from(1000000007u32).pollard_rho());
+    }
+
+    #[test]
+    fn test_ecm() {
+        // Out of reach of rho in a test, found by the second curve.
+        let params = EcmParams::for_digits(15);
+        let p = BigInt::from(1000000000039u64);
+        let mersenne_89 = (BigInt::one() << 89).sub(&BigInt::one());
+        assert_eq!(Some(p.clone()), p.mul(&mersenne_89).ecm(&params));
+
+        assert_eq!(Some(BigInt::two()), BigInt::from(6u32).ecm(&params));
+        assert_eq!(None, BigInt::one().ecm(&params));
+        assert_eq!(None, mersenne_89.ecm(&params));
+    }
+
+    #[test]
+    fn test_factor() {
+        let check = |n: &BigInt, expected: &[(u64, u32)]| {
+            let factorization = n.factor();
+            assert!(factorization.is_complete(), "{}", n);
+            let factors: Vec<(BigInt, u32)> = expected
+                .iter()
+                .map(|&(p, e)| (BigInt::from(p), e))
+                .collect();
+            assert_eq!(
+                factors,
+                factorization
+                    .factors()
+                    .iter()
+                    .map(|(p, &e)| (p.clone(), e))
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(*n, factorization.value());
+        };
+        check(&BigInt::one(), &[]);
+        check(&BigInt::from(3628800u32), &[(2, 8), (3, 4), (5, 2), (7, 1)]);
+        check(
+            &(BigInt::one() << 64).add(&BigInt::one()),
+            &[(274177, 1), (67280421310721, 1)],
+        );
+        let square = BigInt::from(1000003u64 * 1000003).mul(&BigInt::from(12u32));
+        check(&square, &[(2, 2), (3, 1), (1000003, 2)]);
+        // Rho runs out of steps, ECM finds the factor.
+        let mersenne_89 = (BigInt::one() << 89).sub(&BigInt::one());
+        let n = mersenne_89.mul(&BigInt::from(1000000000039u64));
+        let factorization = n.factor_with(1000, &[EcmParams::for_digits(15)]);
+        assert!(factorization.is_complete());
+        assert_eq!(Some(&1), factorization.factors().get(&mersenne_89));
+
+        assert_eq!(BigInt::zero(), *BigInt::zero().factor().cofactor());
+        assert!(!BigInt::zero().factor().is_complete());
+    }
+
+    #[test]
+    fn test_factor_until() {
+        let n = BigInt::from(1000003u64 * 1000033).mul(&BigInt::from(1000037u32 * 12));
+        assert_eq!(Ok(n.factor()), n.factor_until(&|| false));
+
+        let cancelled = n.factor_until(&|| true).unwrap_err().partial;
+        assert_eq!(n, cancelled.value());
+        assert_eq!(2, cancelled.factors().len());
+        assert!(!cancelled.is_complete());
+
+        // Stop after a few checks: whatever was found is consistent.
+        let checks = std::cell::Cell::new(0);
+        let stop = || {
+            checks.set(checks.get() + 1);
+            checks.get() > 2
+        };
+        let partial = match n.factor_until(&stop) {
+            Ok(factorization)
+            | Err(Cancelled {
+                partial: factorization,
+            }) => factorization,
+        };
+        assert_eq!(n, partial.value());
+    }
+
+    #[test]
+    fn test_factor_with_progress() {
+        let n = BigInt::from(1000003u64 * 1000033).mul(&BigInt::from(1000037u32 * 12));
+        let mut reports = Vec::new();
+        let factorization = n.factor_with_progress(&mut |done, total| reports.push((done, total)));
+        assert_eq!(n.factor(), factorization);
+        let total = n.bits() as u64;
+        assert!(reports.windows(2).all(|w| w[0].0 <= w[1].0));
+        assert!(reports.iter().all(|&(done, t)| t == total && done <= total));
+        assert_eq!(Some(&(total, total)), reports.last());
+    }
+
+    #[test]
+    fn test_factor_incomplete() {
+        let p = BigInt::from(1000000007u64);
+        let q = BigInt::from(998244353u64);
+        let n = p.mul(&q).mul(&BigInt::from(6u32));
+        let factorization = n.factor_with(10, &[]);
+        assert!(!factorization.is_complete());
+        assert_eq!(p.mul(&q), *factorization.cofactor());
+        assert_eq!(2, factorization.factors().len());
+        assert_eq!(n, factorization.value());
+    }
+
+    #[test]
+    fn test_divisors() {
+        let factorization = BigInt::from(360u32).factor();
+        assert_eq!(BigInt::from(24u32), factorization.num_divisors());
+        assert_eq!(BigInt::from(1170u32), factorization.sum_of_divisors());
+        let divisors: Vec<u32> = factorization
+            .divisors()
+            .map(|d| d.to_u32().unwrap())
+            .collect();
+        let expected: Vec<u32> = (1..=360).filter(|d| 360 % d == 0).collect();
+        assert_eq!(expected, divisors);
+
+        let one = BigInt::one().factor();
+        assert_eq!(BigInt::one(), one.num_divisors());
+        assert_eq!(BigInt::one(), one.sum_of_divisors());
+        assert_eq!(vec![BigInt::one()], one.divisors().collect::<Vec<_>>());
+
+        // A perfect number: the divisors add up to twice the value.
+        let perfect = (BigInt::one() << 60).mul(&(BigInt::one() << 61).sub(&BigInt::one()));
+        let factorization = perfect.factor();
+        assert_eq!(perfect.add(&perfect), factorization.sum_of_divisors());
+        assert_eq!(BigInt::from(122u32), factorization.num_divisors());
+        assert_eq!(Some(perfect), factorization.divisors().last());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_divisors_incomplete() {
+        BigInt::zero().factor().num_divisors();
+    }
+
+    #[test]
+    fn test_moebius() {
+        // mu(1..=20) from the definition.
+        let expected = [
+            1, -1, -1, 0, -1, 1, -1, 0, 0, 1, -1, 0, -1, 1, 1, 0, -1, 0, -1, 0,
+        ];
+        for (n, &mu) in (1..=20u32).zip(expected.iter()) {
+            assert_eq!(mu, BigInt::from(n).moebius(), "{}", n);
+            assert_eq!(mu != 0, BigInt::from(n).is_squarefree(), "{}", n);
+        }
+        assert_eq!(0, BigInt::zero().moebius());
+
+        let p = BigInt::from(1000003u32);
+        let q = BigInt::from(1000033u32);
+        assert_eq!(1, p.mul(&q).moebius());
+        assert_eq!(-1, p.mul(&q).mul(&BigInt::from(30u32)).moebius());
+        assert!(!p.mul(&p).is_squarefree());
+        assert!(!p.mul(&p).mul(&q).is_squarefree());
+        let mersenne_61 = (BigInt::one() << 61).sub(&BigInt::one());
+        assert_eq!(-1, mersenne_61.moebius());
+    }
+
+    #[test]
+    fn test_euler_phi_carmichael_lambda() {
+        // From the definitions, by brute force.
+        let gcd = |mut a: u32, mut b: u32| {
+            while b != 0 {
+                let r = a % b;
+                a = std::mem::replace(&mut b, r);
+            }
+            a
+        };
+        for n in 1..200u32 {
+            let units: Vec<u32> = (1..=n).filter(|&a| gcd(a, n) == 1).collect();
+            let order = |a: u32| {
+                (1..)
+                    .scan(1u32, |x, k| {
+                        *x = *x * a % n;
+                        Some((k, *x))
+                    })
+                    .find(|&(_, x)| x == 1 % n)
+                    .unwrap()
+                    .0
+            };
+            let lambda: u32 = units.iter().map(|&a| order(a)).max().unwrap();
+            let n = BigInt::from(n);
+            assert_eq!(BigInt::from(units.len()), n.euler_phi(), "{}", n);
+            assert_eq!(BigInt::from(lambda), n.carmichael_lambda(), "{}", n);
+        }
+
+        let p = BigInt::from(1000003u32);
+        let q = BigInt::from(1000033u32);
+        let n = p.mul(&q);
+        let (p1, q1) = (p.sub(&BigInt::one()), q.sub(&BigInt::one()));
+        assert_eq!(p1.mul(&q1), n.euler_phi());
+        assert_eq!(p1.lcm(&q1), n.carmichael_lambda());
+    }
+}
@@ -0,0 +1,183 @@
+//! Gaussian integers, the complex numbers `re + im * i` with integer parts.
+
+use crate::optimized_memory::BigInt as Magnitude;
+use crate::signed::BigInt;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct GaussianInt {
+    pub re: BigInt,
+    pub im: BigInt,
+}
+
+impl GaussianInt {
+    pub fn new(re: BigInt, im: BigInt) -> GaussianInt {
+        GaussianInt { re, im }
+    }
+
+    pub fn zero() -> GaussianInt {
+        GaussianInt::new(BigInt::zero(), BigInt::zero())
+    }
+
+    pub fn one() -> GaussianInt {
+        GaussianInt::new(BigInt::one(), BigInt::zero())
+    }
+
+    /// The imaginary unit.
+    pub fn i() -> GaussianInt {
+        GaussianInt::new(BigInt::zero(), BigInt::one())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.re.is_zero() && self.im.is_zero()
+    }
+
+    pub fn add(&self, other: &GaussianInt) -> GaussianInt {
+        GaussianInt::new(self.re.add(&other.re), self.im.add(&other.im))
+    }
+
+    pub fn sub(&self, other: &GaussianInt) -> GaussianInt {
+        GaussianInt::new(self.re.sub(&other.re), self.im.sub(&other.im))
+    }
+
+    pub fn mul(&self, other: &GaussianInt) -> GaussianInt {
+        GaussianInt::new(
+            self.re.mul(&other.re).sub(&self.im.mul(&other.im)),
+            self.re.mul(&other.im).add(&self.im.mul(&other.re)),
+        )
+    }
+
+    pub fn conjugate(&self) -> GaussianInt {
+        GaussianInt::new(self.re.clone(), -&self.im)
+    }
+
+    /// `re^2 + im^2`, the product of the value and its conjugate.
+    pub fn norm(&self) -> Magnitude {
+        let re = self.re.magnitude();
+        let im = self.im.magnitude();
+        re.mul(re).add(&im.mul(im))
+    }
+
+    /// Division rounding each part of the exact quotient to the nearest integer, so that
+    /// the remainder has at most half the norm of `other`.
+    ///
+    /// Panics if `other` is zero.
+    pub fn div_rem(&self, other: &GaussianInt) -> (GaussianInt, GaussianInt) {
+        assert!(!other.is_zero(), "attempt to divide by zero");
+        let numerator = self.mul(&other.conjugate());
+        let norm = BigInt::from(other.norm());
+        let quotient = GaussianInt::new(
+            div_round(&numerator.re, &norm),
+            div_round(&numerator.im, &norm),
+        );
+        let remainder = self.sub(&quotient.mul(other));
+        (quotient, remainder)
+    }
+
+    /// Whether `other` is a multiple of `self`. Zero only divides zero.
+    pub fn divides(&self, other: &GaussianInt) -> bool {
+        if self.is_zero() {
+            return other.is_zero();
+        }
+        let numerator = other.mul(&self.conjugate());
+        let norm = BigInt::from(self.norm());
+        numerator.re.rem(&norm).is_zero() && numerator.im.rem(&norm).is_zero()
+    }
+
+    /// Greatest common divisor, normalized to have positive real part and nonnegative
+    /// imaginary part (zero if both values are zero).
+    pub fn gcd(&self, other: &GaussianInt) -> GaussianInt {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.is_zero() {
+            let (_, r) = a.div_rem(&b);
+            a = std::mem::replace(&mut b, r);
+        }
+        a.normalized()
+    }
+
+    /// The associate in the first quadrant, excluding the imaginary axis.
+    fn normalized(&self) -> GaussianInt {
+        let mut result = self.clone();
+        if result.is_zero() {
+            return result;
+        }
+        // Multiplying by -i rotates a quarter turn clockwise.
+        while !(result.re.is_positive() && !result.im.is_negative()) {
+            result = GaussianInt::new(result.im.clone(), -&result.re);
+        }
+        result
+    }
+}
+
+/// `n / d` rounded to the nearest integer, `d` being positive.
+fn div_round(n: &BigInt, d: &BigInt) -> BigInt {
+    let two = BigInt::from(2i32);
+    n.mul(&two).add(d).div_euclid(&d.mul(&two))
+}
+
+impl fmt::Display for GaussianInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.im.is_negative() {
+            write!(f, "{}-{}i", self.re, self.im.magnitude())
+        } else {
+            write!(f, "{}+{}i", self.re, self.im)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn g(re: i64, im: i64) -> GaussianInt {
+        GaussianInt::new(BigInt::from(re), BigInt::from(im))
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(g(4, 6), g(1, 2).add(&g(3, 4)));
+        assert_eq!(g(-2, -2), g(1, 2).sub(&g(3, 4)));
+        assert_eq!(g(-5, 10), g(1, 2).mul(&g(3, 4)));
+        assert_eq!(g(-1, 0), GaussianInt::i().mul(&GaussianInt::i()));
+        assert_eq!(g(3, -4), g(3, 4).conjugate());
+        assert_eq!(Magnitude::from(25u32), g(3, -4).norm());
+        assert_eq!("3-4i", g(3, -4).to_string());
+        assert_eq!("0+1i", GaussianInt::i().to_string());
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let a = g(27, -23);
+        let b = g(8, 1);
+        let (q, r) = a.div_rem(&b);
+        assert_eq!(a, q.mul(&b).add(&r));
+        assert!(r.norm().add(&r.norm()) <= b.norm());
+        let (q, r) = g(-5, 10).div_rem(&g(3, 4));
+        assert_eq!(g(1, 2), q);
+        assert!(r.is_zero());
+    }
+
+    #[test]
+    fn test_divides() {
+        assert!(g(1, 2).divides(&g(-5, 10)));
+        assert!(g(1, 1).divides(&g(2, 0)));
+        assert!(!g(1, 1).divides(&g(3, 0)));
+        assert!(g(0, 0).divides(&g(0, 0)));
+        assert!(!g(0, 0).divides(&g(1, 0)));
+    }
+
+    #[test]
+    fn test_gcd() {
+        // 5 = (2 + i)(2 - i) and 13 = (3 + 2i)(3 - 2i).
+        let a = g(2, 1).mul(&g(3, 2));
+        let b = g(2, 1).mul(&g(3, -2));
+        assert_eq!(g(2, 1), a.gcd(&b));
+        assert_eq!(g(1, 0), g(3, 0).gcd(&g(0, 5)));
+        assert_eq!(g(0, 0), g(0, 0).gcd(&g(0, 0)));
+        assert_eq!(g(5, 0), g(0, -5).gcd(&g(0, 0)));
+        // Sum of two squares: the gcd of 13 and 5 + i has norm 13.
+        assert_eq!(Magnitude::from(13u32), g(13, 0).gcd(&g(5, 1)).norm());
+    }
+}
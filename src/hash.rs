@@ -0,0 +1,123 @@
+//! Integers derived from hash outputs, for example challenge scalars computed from a
+//! SHA-256 digest.
+//!
+//! Reducing a digest naively modulo `m` favours the small residues whenever the number
+//! of possible digests is not a multiple of `m`. The helpers here either make that bias
+//! negligible with a digest much wider than `m`, or remove it by rejection sampling.
+
+use crate::optimized_memory::BigInt;
+
+/// Extra bits of input `hash_to_range` requires beyond the size of the modulus: the
+/// distance from the uniform distribution is then below 2^-128.
+pub const WIDE_REDUCTION_MARGIN: usize = 128;
+
+impl BigInt {
+    /// Interpret a digest as a big-endian unsigned integer, as OS2IP does.
+    pub fn from_digest(bytes: &[u8]) -> BigInt {
+        let limbs: Vec<u32> = bytes
+            .rchunks(4)
+            .map(|chunk| chunk.iter().fold(0, |limb, &b| (limb << 8) | b as u32))
+            .collect();
+        BigInt::from_limbs_le(&limbs)
+    }
+}
+
+/// A value in `[0, modulus)` by wide reduction of `bytes`, which must have at least
+/// `modulus.bits() + WIDE_REDUCTION_MARGIN` bits: concatenate several digests obtained
+/// with different prefixes if one is not enough.
+///
+/// Panics if `modulus` is zero or `bytes` is too short.
+pub fn hash_to_range(bytes: &[u8], modulus: &BigInt) -> BigInt {
+    assert!(!modulus.is_zero(), "modulus must be nonzero");
+    assert!(
+        bytes.len() * 8 >= modulus.bits() + WIDE_REDUCTION_MARGIN,
+        "not enough input bits for an unbiased reduction"
+    );
+    BigInt::from_digest(bytes).rem(modulus)
+}
+
+/// A uniform value in `[0, modulus)` by rejection sampling: `digest(counter)` is called
+/// with `counter = 0, 1, 2, ...` and truncated to the size of `modulus` until a result
+/// below `modulus` comes up, which takes fewer than two attempts on average.
+///
+/// Panics if `modulus` is zero or if a digest is shorter than `modulus`.
+pub fn hash_to_range_by_rejection<D, F>(modulus: &BigInt, mut digest: F) -> BigInt
+where
+    D: AsRef<[u8]>,
+    F: FnMut(u32) -> D,
+{
+    assert!(!modulus.is_zero(), "modulus must be nonzero");
+    let bits = modulus.bits();
+    for counter in 0.. {
+        let bytes = digest(counter);
+        let bytes = bytes.as_ref();
+        assert!(
+            bytes.len() * 8 >= bits,
+            "digest is shorter than the modulus"
+        );
+        let candidate = BigInt::from_digest(bytes).truncate_to(bits);
+        if candidate < *modulus {
+            return candidate;
+        }
+    }
+    unreachable!("every counter of a digest was rejected")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_from_digest() {
+        assert_eq!(BigInt::zero(), BigInt::from_digest(&[]));
+        assert_eq!(BigInt::from(0x0102u32), BigInt::from_digest(&[0, 1, 2]));
+        assert_eq!(
+            BigInt::from(0x0102030405u64),
+            BigInt::from_digest(&[1, 2, 3, 4, 5])
+        );
+        assert_eq!(BigInt::from(u128::MAX), BigInt::from_digest(&[0xff; 16]));
+    }
+
+    #[test]
+    fn test_hash_to_range() {
+        let modulus = BigInt::from(1000003u32);
+        let bytes = [0xab; 19];
+        assert_eq!(
+            BigInt::from_digest(&bytes).rem(&modulus),
+            hash_to_range(&bytes, &modulus)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_hash_to_range_too_short() {
+        hash_to_range(&[0xab; 18], &BigInt::from(1000003u32));
+    }
+
+    #[test]
+    fn test_hash_to_range_by_rejection() {
+        // 0xff truncated to 7 bits is 127, rejected for the modulus 100.
+        let modulus = BigInt::from(100u32);
+        let mut calls = Vec::new();
+        let value = hash_to_range_by_rejection(&modulus, |counter| {
+            calls.push(counter);
+            if counter < 3 {
+                [0xff]
+            } else {
+                [0x8a]
+            }
+        });
+        assert_eq!(BigInt::from(10u32), value);
+        assert_eq!(vec![0, 1, 2, 3], calls);
+
+        // Every residue is reachable, and the others fall back to the second digest.
+        let mut seen = [false; 100];
+        for b in 0..=255u8 {
+            let value =
+                hash_to_range_by_rejection(&modulus, |counter| [if counter == 0 { b } else { 0 }]);
+            seen[value.to_usize().unwrap()] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+}
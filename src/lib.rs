@@ -1,36 +1,862 @@
-#![feature(extern_crate_item_prelude)]
 #![feature(test)]
+#![feature(step_trait)]
 
 extern crate test;
 
+/// Build an `optimized_memory::BigInt` from an integer literal or a string literal in
+/// decimal format, for example `bigint!(123456789012345678901234567890)` or
+/// `bigint!("42")`. Underscores used as digit separators are ignored.
+///
+/// The literal is parsed at compile time into a `ConstBigInt`, so invalid digits are
+/// reported as compile errors and evaluating the macro only copies the limbs.
+#[macro_export]
+macro_rules! bigint {
+    ($n:tt) => {{
+        const LIMBS: usize = $crate::optimized_memory::literal_limbs(stringify!($n));
+        const VALUE: $crate::optimized_memory::ConstBigInt<LIMBS> =
+            $crate::optimized_memory::ConstBigInt::from_literal(stringify!($n));
+        VALUE.to_bigint()
+    }};
+}
+
+/// The unsigned BigInt of the backend selected by the cargo features: `easy` with
+/// `backend-easy`, otherwise `optimized_memory` (`backend-u32`, the default).
+#[cfg(feature = "backend-easy")]
+pub use easy::BigInt;
+#[cfg(not(feature = "backend-easy"))]
+pub use optimized_memory::BigInt;
+
+pub mod archive;
+pub mod batch;
+pub mod certificate;
+pub mod checkpoint;
+pub mod combinatorics;
+pub mod decimal;
+pub mod digits;
+pub mod divisor;
+pub mod elliptic;
+#[cfg(feature = "crypto")]
+pub mod dh;
+pub mod factor;
+pub mod gaussian;
+pub mod hash;
+pub mod limb_ops;
+pub mod matrix;
+pub mod modular;
+#[cfg(feature = "mul-backend")]
+pub mod mul_backend;
+pub mod number_theory;
+pub mod padic;
+pub mod poly;
+pub mod pool;
+pub mod prime;
+#[cfg(feature = "rand")]
+pub mod random;
+#[cfg(feature = "crypto")]
+pub mod rsa_math;
+pub mod series;
+pub mod signed;
+#[cfg(feature = "tracing")]
+pub mod stats;
+pub mod uint;
+pub mod unsigned;
+
+/// Implement an arithmetic operator trait on borrowed operands, delegating to the inherent
+/// method of the same name. The owned forms are left out on purpose: with the trait in
+/// scope, `a.add(&b)` would resolve to them before the inherent method and move `a`.
+macro_rules! forward_ref_binop {
+    ($imp:ident, $method:ident) => {
+        impl $imp<&BigInt> for &BigInt {
+            type Output = BigInt;
+
+            fn $method(self, other: &BigInt) -> BigInt {
+                BigInt::$method(self, other)
+            }
+        }
+    };
+}
+
 pub mod optimized_memory {
-    ///! Optimized implementation of BigInt using representation in base u32.
-    ///! Atomic operations use casts to u64, leveraging the fact that overflow is
-    ///! impossible.
+    //! Optimized implementation of BigInt using representation in base u32.
+    //! Atomic operations use casts to u64, leveraging the fact that overflow is
+    //! impossible.
+
+    use crate::limb_ops;
+    use std::cmp::Ordering;
+    use std::convert::TryFrom;
+    use std::fmt;
+    use std::hash::{Hash, Hasher};
+    use std::iter::{FromIterator, Product, Step, Sum};
+    use std::ops::{Add, BitAnd, BitOr, BitXor, Deref, Mul, Shl, Shr, Sub};
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    use std::sync::Arc;
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone)]
     pub struct BigInt {
         data: Vec<u32>,
     }
 
     impl BigInt {
-        const BASE: u64 = std::u32::MAX as u64 + 1;
+        const BASE: u64 = u32::MAX as u64 + 1;
         const PARSE_STEP: usize = 8;
 
-        pub fn zero() -> BigInt {
-            BigInt { data: Vec::new() }
+        /// Rows of the schoolbook product between two checks in `mul_until`.
+        pub const MUL_ROWS_PER_CHECK: usize = 64;
+
+        /// Zero is represented by an empty vector and can be used in constant contexts.
+        ///
+        /// Any other value needs a heap allocation, which the `Vec` backing cannot make in a
+        /// constant, so there is no `BigInt::ONE`: `ConstBigInt::ONE`, `ConstBigInt::TWO`
+        /// and `ConstBigInt::TEN` are the allocation-free constants.
+        pub const ZERO: BigInt = BigInt { data: Vec::new() };
+
+        pub const fn zero() -> BigInt {
+            BigInt::ZERO
+        }
+
+        pub fn one() -> BigInt {
+            ConstBigInt::ONE.to_bigint()
+        }
+
+        pub fn two() -> BigInt {
+            ConstBigInt::TWO.to_bigint()
+        }
+
+        pub fn ten() -> BigInt {
+            ConstBigInt::TEN.to_bigint()
+        }
+
+        /// Build a BigInt from limbs in base 2^32, least significant first.
+        pub fn from_limbs_le(limbs: &[u32]) -> BigInt {
+            let mut result = BigInt {
+                data: limbs.to_vec(),
+            };
+            result.normalize();
+            result
+        }
+
+        /// Build a BigInt from bytes, most significant first. Leading zeros are ignored.
+        pub fn from_bytes_be(bytes: &[u8]) -> BigInt {
+            let limbs: Vec<u32> = bytes
+                .rchunks(4)
+                .map(|chunk| chunk.iter().fold(0, |limb, &b| limb << 8 | b as u32))
+                .collect();
+            BigInt::from_limbs_le(&limbs)
+        }
+
+        /// The bytes of the value, most significant first, without leading zeros, or
+        /// `[0]` for zero.
+        pub fn to_bytes_be(&self) -> Vec<u8> {
+            let bytes: Vec<u8> = self
+                .iter_limbs_be()
+                .flat_map(u32::to_be_bytes)
+                .skip_while(|&b| b == 0)
+                .collect();
+            if bytes.is_empty() {
+                vec![0]
+            } else {
+                bytes
+            }
+        }
+
+        /// A 64-bit FNV-1a hash of the bytes of the value, least significant first and
+        /// without trailing zeros, so that it is the same on every platform and for every
+        /// limb size. Unlike `Hash`, suitable for values stored or sent elsewhere; not
+        /// resistant to deliberate collisions.
+        pub fn stable_hash64(&self) -> u64 {
+            const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+            const PRIME: u64 = 0x0100_0000_01b3;
+            let limbs = self.as_limbs_le();
+            let top_bytes = limbs.last().map_or(0, |&top| 4 - top.leading_zeros() as usize / 8);
+            let len = (4 * limbs.len()).saturating_sub(4 - top_bytes);
+            limbs
+                .iter()
+                .flat_map(|limb| limb.to_le_bytes())
+                .take(len)
+                .fold(OFFSET_BASIS, |hash, b| (hash ^ b as u64).wrapping_mul(PRIME))
+        }
+
+        /// The limbs in base 2^32, least significant first, without trailing zeros.
+        pub fn as_limbs_le(&self) -> &[u32] {
+            &self.data[..self.significant_len()]
+        }
+
+        /// Bytes allocated on the heap for the limbs, spare capacity included.
+        pub fn heap_size(&self) -> usize {
+            self.data.capacity() * std::mem::size_of::<u32>()
+        }
+
+        /// The limbs of `as_limbs_le`, one by one.
+        pub fn iter_limbs_le(&self) -> std::iter::Copied<std::slice::Iter<'_, u32>> {
+            self.as_limbs_le().iter().copied()
+        }
+
+        /// The limbs of `as_limbs_le` from the most significant down.
+        pub fn iter_limbs_be(&self) -> std::iter::Rev<std::iter::Copied<std::slice::Iter<'_, u32>>> {
+            self.iter_limbs_le().rev()
+        }
+
+        /// The value in base 2^64, most significant digit first and without leading zeros,
+        /// so the first chunk may have its upper half empty.
+        pub fn iter_u64_chunks_be(&self) -> impl ExactSizeIterator<Item = u64> + DoubleEndedIterator + '_ {
+            let limbs = self.as_limbs_le();
+            (0..limbs.len().div_ceil(2)).rev().map(move |i| {
+                let high = limbs.get(2 * i + 1).map_or(0, |&l| u64::from(l));
+                (high << 32) | u64::from(limbs[2 * i])
+            })
+        }
+
+        /// Consume the BigInt, returning its limbs as in `as_limbs_le`.
+        pub fn into_limbs(mut self) -> Vec<u32> {
+            self.normalize();
+            self.data
+        }
+
+        pub fn add(&self, other: &BigInt) -> BigInt {
+            add_limbs(&self.data, &other.data)
+        }
+
+        pub fn mul(&self, other: &BigInt) -> BigInt {
+            #[cfg(feature = "mul-backend")]
+            if let Some(product) = crate::mul_backend::offload(&self.data, &other.data) {
+                return product;
+            }
+            mul_limbs(&self.data, &other.data)
+        }
+
+        /// Panics if `other` is larger than `self`.
+        pub fn sub(&self, other: &BigInt) -> BigInt {
+            let mut result = self.clone();
+            result.sub_in_place(other);
+            result
+        }
+
+        /// Subtraction, or `None` if `other` is larger than `self`.
+        pub fn checked_sub(&self, other: &BigInt) -> Option<BigInt> {
+            if self < other {
+                None
+            } else {
+                Some(self.sub(other))
+            }
+        }
+
+        /// Quotient and remainder of the division by `other`.
+        ///
+        /// Panics if `other` is zero.
+        pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+            assert!(!other.is_zero(), "attempt to divide by zero");
+            div_rem_limbs(self.as_limbs_le(), other.as_limbs_le())
+        }
+
+        /// Panics if `other` is zero.
+        pub fn div(&self, other: &BigInt) -> BigInt {
+            self.div_rem(other).0
+        }
+
+        /// Panics if `other` is zero.
+        pub fn rem(&self, other: &BigInt) -> BigInt {
+            self.div_rem(other).1
+        }
+
+        /// Borrow the value as a `BigIntSlice`.
+        pub fn as_slice(&self) -> BigIntSlice<'_> {
+            BigIntSlice::new(&self.data)
+        }
+
+        /// Raise to the power `exp` by square-and-multiply.
+        pub fn pow(&self, mut exp: u32) -> BigInt {
+            let mut result = BigInt::one();
+            let mut base = self.clone();
+            while exp > 0 {
+                if exp & 1 == 1 {
+                    result = result.mul(&base);
+                }
+                exp >>= 1;
+                if exp > 0 {
+                    base = base.mul(&base);
+                }
+            }
+            result
+        }
+
+        /// `pow`, or an error if the result would have more than `max_bits` bits. Values
+        /// that are certainly too large are rejected before anything is computed, and no
+        /// intermediate value exceeds the budget.
+        pub fn checked_pow(
+            &self,
+            mut exp: u32,
+            max_bits: usize,
+        ) -> Result<BigInt, LimitExceededError> {
+            // x^exp has more than exp * (bits - 1) bits.
+            let lower_bound = (exp as u128) * (self.bits().saturating_sub(1) as u128);
+            if lower_bound >= max_bits as u128 && self.bits() > 1 && exp > 0 {
+                return Err(LimitExceededError { max_bits });
+            }
+            let mut result = BigInt::one();
+            let mut base = self.clone();
+            while exp > 0 {
+                if exp & 1 == 1 {
+                    result = result.checked_mul_limited(&base, max_bits)?;
+                }
+                exp >>= 1;
+                if exp > 0 {
+                    base = base.checked_mul_limited(&base, max_bits)?;
+                }
+            }
+            if result.bits() > max_bits {
+                return Err(LimitExceededError { max_bits });
+            }
+            Ok(result)
+        }
+
+        /// `mul`, or an error if the product would have more than `max_bits` bits. The
+        /// product has `self.bits() + other.bits()` bits or one less, so it is only
+        /// computed when it may fit.
+        pub fn checked_mul_limited(
+            &self,
+            other: &BigInt,
+            max_bits: usize,
+        ) -> Result<BigInt, LimitExceededError> {
+            if self.is_zero() || other.is_zero() {
+                return Ok(BigInt::zero());
+            }
+            if self.bits() + other.bits() - 1 > max_bits {
+                return Err(LimitExceededError { max_bits });
+            }
+            let product = self.mul(other);
+            if product.bits() > max_bits {
+                return Err(LimitExceededError { max_bits });
+            }
+            Ok(product)
+        }
+
+        /// `mul`, checking `should_stop` after every `MUL_ROWS_PER_CHECK` limbs of the
+        /// shorter operand.
+        pub fn mul_until(
+            &self,
+            other: &BigInt,
+            should_stop: &dyn Fn() -> bool,
+        ) -> Result<BigInt, Cancelled<()>> {
+            let (mut a, mut b) = (self.as_limbs_le(), other.as_limbs_le());
+            if a.len() < b.len() {
+                std::mem::swap(&mut a, &mut b);
+            }
+            #[cfg(feature = "tracing")]
+            crate::stats::record_mul(false, a.len(), b.len());
+            let mut product = vec![0; a.len() + b.len()];
+            for (j, &limb) in b.iter().enumerate() {
+                if j % BigInt::MUL_ROWS_PER_CHECK == 0 && should_stop() {
+                    return Err(Cancelled { partial: () });
+                }
+                product[j + a.len()] = limb_ops::addmul_1(&mut product[j..j + a.len()], a, limb);
+            }
+            Ok(BigInt::from_limbs_le(&product))
+        }
+
+        /// Greatest common divisor by Euclid's algorithm; `gcd(0, 0)` is zero.
+        pub fn gcd(&self, other: &BigInt) -> BigInt {
+            let mut a = self.clone();
+            let mut b = other.clone();
+            while !b.is_zero() {
+                let r = a.rem(&b);
+                a = std::mem::replace(&mut b, r);
+            }
+            a.normalize();
+            a
+        }
+
+        /// Least common multiple; zero if either value is zero.
+        pub fn lcm(&self, other: &BigInt) -> BigInt {
+            if self.is_zero() || other.is_zero() {
+                return BigInt::zero();
+            }
+            self.div(&self.gcd(other)).mul(other)
+        }
+
+        /// `(self + other) / 2`, rounded down. The sum has at most one more bit, so this
+        /// is an addition and a shift.
+        pub fn midpoint(&self, other: &BigInt) -> BigInt {
+            self.add(other) >> 1
+        }
+
+        /// The smaller of the two, `self` if they are equal, like `std::cmp::min`, without
+        /// cloning either.
+        pub fn min_ref<'a>(&'a self, other: &'a BigInt) -> &'a BigInt {
+            if other < self {
+                other
+            } else {
+                self
+            }
+        }
+
+        /// The larger of the two, `other` if they are equal, like `std::cmp::max`.
+        pub fn max_ref<'a>(&'a self, other: &'a BigInt) -> &'a BigInt {
+            if other < self {
+                self
+            } else {
+                other
+            }
+        }
+
+        /// The value restricted to `[min, max]`, like `Ord::clamp`.
+        ///
+        /// Panics if `min > max`.
+        pub fn clamp_ref<'a>(&'a self, min: &'a BigInt, max: &'a BigInt) -> &'a BigInt {
+            assert!(min <= max, "clamp_ref called with min > max");
+            if self < min {
+                min
+            } else if self > max {
+                max
+            } else {
+                self
+            }
+        }
+
+        /// Square root, rounded down.
+        pub fn sqrt(&self) -> BigInt {
+            self.sqrt_rem().0
+        }
+
+        /// Square root `s`, rounded down, and the remainder `self - s^2`, by Zimmermann's
+        /// divide-and-conquer algorithm: with the value split in quarters of `k` bits,
+        /// the root of the top half gives the top half of the root, and one division by
+        /// it gives the bottom half, so the work is a few multiplications and divisions
+        /// of half size instead of full-precision Newton steps.
+        pub fn sqrt_rem(&self) -> (BigInt, BigInt) {
+            if let Some(n) = self.to_u128() {
+                let s = n.isqrt();
+                return (BigInt::from(s), BigInt::from(n - s * s));
+            }
+            // The top quarter must be at least a quarter of its range, which shifting by
+            // an even amount arranges; the root is shifted back at the end.
+            let shift = if matches!(self.bits() % 4, 1 | 2) { 1 } else { 0 };
+            let n = self.clone() << (2 * shift);
+            let k = n.bits().div_ceil(4);
+            let (top_root, top_rem) = (n.clone() >> (2 * k)).sqrt_rem();
+            let (q, u) = (top_rem << k)
+                .add(&n.bit_slice(k, k))
+                .div_rem(&(top_root.clone() << 1));
+            let mut root = (top_root << k).add(&q);
+            let mut rem = (u << k).add(&n.low_bits(k));
+            let q_squared = q.mul(&q);
+            if rem >= q_squared {
+                rem = rem.sub(&q_squared);
+            } else {
+                // The root is one too large, at most.
+                rem = rem.add(&(root.clone() << 1)).sub(&BigInt::one()).sub(&q_squared);
+                root = root.sub(&BigInt::one());
+            }
+            if shift == 0 {
+                return (root, rem);
+            }
+            let root = root >> shift;
+            let rem = self.sub(&root.mul(&root));
+            (root, rem)
+        }
+
+        /// The `k`-th root, rounded down, by Newton's iteration from a power of two above it.
+        ///
+        /// Panics if `k` is zero.
+        pub fn nth_root(&self, k: u32) -> BigInt {
+            assert!(k > 0, "the zeroth root is undefined");
+            if self.is_zero() || k == 1 {
+                return self.clone();
+            }
+            let k_big = BigInt::from(k);
+            let mut x = BigInt::one() << self.bits().div_ceil(k as usize);
+            loop {
+                let next = x
+                    .mul(&BigInt::from(k - 1))
+                    .add(&self.div(&x.pow(k - 1)))
+                    .div(&k_big);
+                if next >= x {
+                    return x;
+                }
+                x = next;
+            }
+        }
+
+        /// Convert to u32, or `None` if the value does not fit.
+        pub fn to_u32(&self) -> Option<u32> {
+            self.to_u128().and_then(|n| {
+                if n <= u32::MAX as u128 {
+                    Some(n as u32)
+                } else {
+                    None
+                }
+            })
+        }
+
+        /// Convert to u64, or `None` if the value does not fit.
+        pub fn to_u64(&self) -> Option<u64> {
+            self.to_u128().and_then(|n| {
+                if n <= u64::MAX as u128 {
+                    Some(n as u64)
+                } else {
+                    None
+                }
+            })
+        }
+
+        /// Convert to u128, or `None` if the value does not fit.
+        pub fn to_u128(&self) -> Option<u128> {
+            let len = self.significant_len();
+            if len > 4 {
+                return None;
+            }
+            let mut value: u128 = 0;
+            for i in (0..len).rev() {
+                value = (value << 32) | self.data[i] as u128;
+            }
+            Some(value)
+        }
+
+        /// Convert to usize, or `None` if the value does not fit.
+        pub fn to_usize(&self) -> Option<usize> {
+            self.to_u128().and_then(|n| {
+                if n <= usize::MAX as u128 {
+                    Some(n as usize)
+                } else {
+                    None
+                }
+            })
+        }
+
+        pub fn is_zero(&self) -> bool {
+            self.significant_len() == 0
+        }
+
+        pub fn is_one(&self) -> bool {
+            self.significant_len() == 1 && self.data[0] == 1
+        }
+
+        pub fn is_even(&self) -> bool {
+            self.get(0) & 1 == 0
+        }
+
+        pub fn is_odd(&self) -> bool {
+            self.get(0) & 1 == 1
+        }
+
+        /// Convert to u64, or `u64::MAX` if the value does not fit.
+        pub fn to_u64_saturating(&self) -> u64 {
+            self.to_u64().unwrap_or(u64::MAX)
+        }
+
+        /// Convert to u128, or `u128::MAX` if the value does not fit.
+        pub fn to_u128_saturating(&self) -> u128 {
+            self.to_u128().unwrap_or(u128::MAX)
+        }
+
+        /// The value itself if it fits in `bits` bits, otherwise the largest value that does.
+        pub fn clamp_to(&self, bits: usize) -> BigInt {
+            if self.bits() <= bits {
+                let mut result = self.clone();
+                result.normalize();
+                return result;
+            }
+            let mut result = BigInt {
+                data: vec![u32::MAX; bits / 32],
+            };
+            if !bits.is_multiple_of(32) {
+                result.data.push((1 << (bits % 32)) - 1);
+            }
+            result
+        }
+
+        /// The value modulo 2^bits, that is its low `bits` bits.
+        pub fn truncate_to(&self, bits: usize) -> BigInt {
+            let mut result = BigInt {
+                data: self.data.iter().take(bits.div_ceil(32)).cloned().collect(),
+            };
+            result.keep_low_bits(bits);
+            result
+        }
+
+        /// Same as `truncate_to`.
+        pub fn low_bits(&self, bits: usize) -> BigInt {
+            self.truncate_to(bits)
+        }
+
+        /// Reduce the value modulo 2^bits in place, by dropping the limbs above and
+        /// masking the top one.
+        pub fn keep_low_bits(&mut self, bits: usize) {
+            self.data.truncate(bits.div_ceil(32));
+            if !bits.is_multiple_of(32) && self.data.len() == bits.div_ceil(32) {
+                let top = self.data.len() - 1;
+                self.data[top] &= (1 << (bits % 32)) - 1;
+            }
+            self.normalize();
+        }
+
+        /// The `len` bits starting at bit `start`, that is `(self >> start) mod 2^len`.
+        /// When `start` is a multiple of 32 the limbs are copied without shifting.
+        pub fn bit_slice(&self, start: usize, len: usize) -> BigInt {
+            let first = start / 32;
+            let offset = start % 32;
+            if first >= self.data.len() {
+                return BigInt::zero();
+            }
+            let count = len.div_ceil(32).min(self.data.len() - first);
+            let mut data: Vec<u32> = if offset == 0 {
+                self.data[first..first + count].to_vec()
+            } else {
+                (first..first + count)
+                    .map(|i| (self.data[i] >> offset) | (self.get(i + 1) << (32 - offset)))
+                    .collect()
+            };
+            if count == len.div_ceil(32) && !len.is_multiple_of(32) {
+                data[count - 1] &= (1 << (len % 32)) - 1;
+            }
+            let mut result = BigInt { data };
+            result.normalize();
+            result
+        }
+
+        /// Number of bits needed to represent the value (zero for zero).
+        pub fn bits(&self) -> usize {
+            let len = self.significant_len();
+            if len == 0 {
+                0
+            } else {
+                len * 32 - self.data[len - 1].leading_zeros() as usize
+            }
+        }
+
+        /// Number of leading zero bits in the most significant nonzero limb (32 for zero).
+        pub fn leading_zeros_in_top_limb(&self) -> u32 {
+            match self.significant_len() {
+                0 => 32,
+                len => self.data[len - 1].leading_zeros(),
+            }
+        }
+
+        /// Value of the bit at position `i`, where bit 0 is the least significant.
+        pub fn bit(&self, i: usize) -> bool {
+            (self.get(i / 32) >> (i % 32)) & 1 == 1
+        }
+
+        /// The bits of the value from the least significant up to the highest one set.
+        pub fn bits_le(&self) -> Bits<'_> {
+            Bits {
+                limbs: &self.data,
+                front: 0,
+                back: self.bits(),
+            }
+        }
+
+        /// The bits of the value from the highest one set down to the least significant,
+        /// the order of left-to-right exponentiation.
+        pub fn bits_be(&self) -> std::iter::Rev<Bits<'_>> {
+            self.bits_le().rev()
+        }
+
+        /// Set the bit at position `i` to `value`, growing the storage if needed.
+        pub fn set_bit(&mut self, i: usize, value: bool) {
+            let limb = i / 32;
+            let mask = 1 << (i % 32);
+            if value {
+                if limb >= self.data.len() {
+                    self.data.resize(limb + 1, 0);
+                }
+                self.data[limb] |= mask;
+            } else if limb < self.data.len() {
+                self.data[limb] &= !mask;
+                self.normalize();
+            }
+        }
+
+        /// Number of bits set to one.
+        pub fn count_ones(&self) -> usize {
+            self.data.iter().map(|d| d.count_ones() as usize).sum()
+        }
+
+        /// Number of bit positions where the two values differ, the popcount of their XOR.
+        /// The limbs past the end of the shorter value are counted as they are.
+        pub fn hamming_distance(&self, other: &BigInt) -> usize {
+            let (short, long) = if self.data.len() <= other.data.len() {
+                (&self.data, &other.data)
+            } else {
+                (&other.data, &self.data)
+            };
+            let common: usize = short
+                .iter()
+                .zip(long.iter())
+                .map(|(a, b)| (a ^ b).count_ones() as usize)
+                .sum();
+            common + long[short.len()..].iter().map(|d| d.count_ones() as usize).sum::<usize>()
+        }
+
+        /// Convert to the nearest f64, or `f64::INFINITY` if the value is too large.
+        pub fn to_f64(&self) -> f64 {
+            let bits = self.bits();
+            if bits <= 64 {
+                return self.to_u64().unwrap() as f64;
+            }
+            if bits > 1024 {
+                return f64::INFINITY;
+            }
+            let exponent = bits - 64;
+            // The lowest bit of the 64-bit mantissa is far below the rounding position, so
+            // setting it when lower bits are lost is enough to round to nearest correctly.
+            let mantissa = self.top_bits_with_sticky(exponent) as f64;
+            mantissa * f64::from_bits(((exponent as u64) + 1023) << 52)
+        }
+
+        /// Convert to the nearest f32, or `f32::INFINITY` if the value is too large.
+        pub fn to_f32(&self) -> f32 {
+            let bits = self.bits();
+            if bits <= 64 {
+                return self.to_u64().unwrap() as f32;
+            }
+            if bits > 128 {
+                return f32::INFINITY;
+            }
+            let exponent = bits - 64;
+            let mantissa = self.top_bits_with_sticky(exponent) as f32;
+            mantissa * f32::from_bits(((exponent as u32) + 127) << 23)
+        }
+
+        /// Convert a finite, non-negative, integral f64 to BigInt exactly.
+        pub fn try_from_f64(f: f64) -> Result<BigInt, FromFloatError> {
+            if f.is_nan() {
+                return Err(FromFloatError { reason: "NaN" });
+            }
+            if f.is_infinite() {
+                return Err(FromFloatError { reason: "infinite" });
+            }
+            if f < 0.0 {
+                return Err(FromFloatError { reason: "negative" });
+            }
+            if f == 0.0 {
+                return Ok(BigInt::zero());
+            }
+
+            let bits = f.to_bits();
+            let biased_exponent = ((bits >> 52) & 0x7ff) as i64;
+            let fraction = bits & ((1 << 52) - 1);
+            if biased_exponent == 0 {
+                // Subnormal numbers are all strictly between zero and one.
+                return Err(FromFloatError { reason: "not an integer" });
+            }
+            let mantissa = fraction | (1 << 52);
+            let exponent = biased_exponent - 1075;
+            if exponent >= 0 {
+                Ok(BigInt::from(mantissa) << exponent as usize)
+            } else if exponent <= -53 || mantissa & ((1 << -exponent) - 1) != 0 {
+                Err(FromFloatError { reason: "not an integer" })
+            } else {
+                Ok(BigInt::from(mantissa >> -exponent))
+            }
+        }
+
+        /// Helper function for `to_f64` and `to_f32`. Returns the 64 bits starting at bit
+        /// `shift`, with the lowest bit set if any bit below `shift` is set.
+        fn top_bits_with_sticky(&self, shift: usize) -> u64 {
+            let limb = shift / 32;
+            let window = self.get(limb) as u128
+                | (self.get(limb + 1) as u128) << 32
+                | (self.get(limb + 2) as u128) << 64;
+            let top = (window >> (shift % 32)) as u64;
+            let lost_mask = (1u32 << (shift % 32)) - 1;
+            let sticky = self.get(limb) & lost_mask != 0 || self.data[..limb].iter().any(|d| *d != 0);
+            if sticky {
+                top | 1
+            } else {
+                top
+            }
+        }
+
+        /// Base 2 logarithm, rounded down.
+        ///
+        /// Panics if the value is zero.
+        pub fn ilog2(&self) -> usize {
+            assert!(!self.is_zero(), "argument of integer logarithm must be positive");
+            self.bits() - 1
+        }
+
+        /// Base 10 logarithm, rounded down.
+        ///
+        /// Panics if the value is zero.
+        pub fn ilog10(&self) -> usize {
+            assert!(!self.is_zero(), "argument of integer logarithm must be positive");
+            self.checked_ilog(&BigInt::ten()).unwrap()
+        }
+
+        /// Compare with the number written in decimal in `s`, leading zeros allowed, like
+        /// `self.cmp(&BigInt::from_string(s))`, or `None` if `s` holds anything other than
+        /// ASCII digits.
+        ///
+        /// The bit length of `self` gives its number of digits, or two candidates when it
+        /// is close to a power of ten, without any arithmetic on the value. `s` is only
+        /// parsed when its number of digits is one of those.
+        pub fn cmp_decimal_str(&self, s: &str) -> Option<Ordering> {
+            if !s.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let len = s.trim_start_matches('0').len();
+            if self.is_zero() {
+                return Some(0.cmp(&len));
+            }
+            let (fewest, most) = decimal_digit_bounds(self.bits());
+            if len < fewest {
+                Some(Ordering::Greater)
+            } else if len > most {
+                Some(Ordering::Less)
+            } else {
+                Some(self.cmp(&BigInt::from_string(s)))
+            }
+        }
+
+        /// Logarithm in the given base, rounded down, or `None` if the value is zero or the
+        /// base is smaller than two.
+        ///
+        /// The result is estimated from the most significant bits and then corrected by at
+        /// most one step.
+        pub fn checked_ilog(&self, base: &BigInt) -> Option<usize> {
+            if self.is_zero() || *base < 2u32 {
+                return None;
+            }
+            if self < base {
+                return Some(0);
+            }
+            let mut estimate = (self.log2_approx() / base.log2_approx()) as usize;
+            let power = base.pow(estimate as u32);
+            if power > *self {
+                estimate -= 1;
+            } else if power.mul(base) <= *self {
+                estimate += 1;
+            }
+            Some(estimate)
         }
 
-        /// Convert a decimal string to BigInt.
+        /// Helper function for `checked_ilog`: approximate base 2 logarithm of a nonzero value.
+        fn log2_approx(&self) -> f64 {
+            let bits = self.bits();
+            if bits <= 64 {
+                (self.to_u64().unwrap() as f64).log2()
+            } else {
+                (self.top_bits_with_sticky(bits - 64) as f64).log2() + (bits - 64) as f64
+            }
+        }
+
+        /// Convert a decimal string to BigInt, with the semantics of `ParseMode::Lenient`:
+        /// the empty string is zero and leading zeros are ignored.
+        ///
+        /// Panics on any character other than an ASCII digit; `from_string_with` returns
+        /// an error instead.
         pub fn from_string(s: &str) -> BigInt {
-            let mut chunks = split_string(s, BigInt::PARSE_STEP);
+            #[cfg(feature = "tracing")]
+            crate::stats::record_from_decimal();
+            let s = s.trim_start_matches('0');
+            let mut chunks = split_string(s.as_bytes(), BigInt::PARSE_STEP);
             let mut result = BigInt::zero();
             loop {
                 let mut carry = 0;
                 for i in 0..chunks.len() {
                     let temp: u64 = if carry > 0 {
                         let original_chunk_size = if i == chunks.len() - 1 {
-                            if s.len() % BigInt::PARSE_STEP == 0 {
+                            if s.len().is_multiple_of(BigInt::PARSE_STEP) {
                                 BigInt::PARSE_STEP
                             } else {
                                 s.len() % BigInt::PARSE_STEP
@@ -53,9 +879,64 @@ pub mod optimized_memory {
                     break;
                 }
             }
+            result.normalize();
             result
         }
 
+        /// Convert a decimal string to BigInt, or an error if it holds anything other than
+        /// ASCII digits, breaks the rules of `mode` or is longer than the limit set with
+        /// `set_max_parse_digits`.
+        pub fn from_string_with(s: &str, mode: ParseMode) -> Result<BigInt, ParseBigIntError> {
+            if max_parse_digits().is_some_and(|limit| s.len() > limit) {
+                return Err(ParseBigIntError {
+                    reason: "too many digits",
+                });
+            }
+            if !s.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(ParseBigIntError {
+                    reason: "invalid decimal digit",
+                });
+            }
+            if s.is_empty() && mode != ParseMode::Lenient {
+                return Err(ParseBigIntError {
+                    reason: "empty string",
+                });
+            }
+            if s.len() > 1 && s.starts_with('0') && mode == ParseMode::Canonical {
+                return Err(ParseBigIntError {
+                    reason: "redundant leading zero",
+                });
+            }
+            Ok(BigInt::from_string(s))
+        }
+
+        /// `from_string_with` in `ParseMode::Lenient`, rejecting strings longer than
+        /// `max_digits` bytes, leading zeros included, before reading them.
+        pub fn try_from_string_bounded(
+            s: &str,
+            max_digits: usize,
+        ) -> Result<BigInt, ParseBigIntError> {
+            if s.len() > max_digits {
+                return Err(ParseBigIntError {
+                    reason: "too many digits",
+                });
+            }
+            BigInt::from_string_with(s, ParseMode::Lenient)
+        }
+
+        /// Set `self` to `self * m + a` in place.
+        pub(crate) fn mul_add_limb(&mut self, m: u32, a: u32) {
+            let mut carry = a as u64;
+            for limb in self.data.iter_mut() {
+                let t = *limb as u64 * m as u64 + carry;
+                *limb = t as u32;
+                carry = t >> 32;
+            }
+            if carry > 0 {
+                self.data.push(carry as u32);
+            }
+        }
+
         /// Helper function for `from_string`.
         fn all_zero(v: &[u64]) -> bool {
             for u in v {
@@ -64,97 +945,3557 @@ pub mod optimized_memory {
                 }
             }
 
-            return true;
+            true
         }
 
         fn get(&self, i: usize) -> u32 {
             if i < self.data.len() {
                 self.data[i]
             } else {
-                0
+                0
+            }
+        }
+
+        /// `to_string`, calling `progress(done, total)` as the conversion advances, in units
+        /// proportional to the work, until `done == total`.
+        pub fn to_string_with_progress(&self, progress: &mut dyn FnMut(u64, u64)) -> String {
+            let chunks = decimal_chunks_with_progress(self.as_limbs_le(), progress);
+            let mut s = String::with_capacity(9 * chunks.len().max(1));
+            push_decimal_chunks(&chunks, &mut s);
+            s
+        }
+
+        /// Append the decimal digits of the value to `buf`, like `to_string` but reusing
+        /// the storage of `buf`. Only the base 10^9 digits of the conversion are allocated.
+        pub fn write_decimal_into(&self, buf: &mut String) {
+            let chunks = decimal_chunks(self.as_limbs_le());
+            buf.reserve(9 * chunks.len().max(1));
+            push_decimal_chunks(&chunks, buf);
+        }
+
+        /// Append the lowercase hexadecimal digits of the value, without a prefix, to
+        /// `buf`, like `format!("{:x}", self)` but without allocating.
+        pub fn write_hex_into(&self, buf: &mut String) {
+            let limbs = self.as_limbs_le();
+            buf.reserve(8 * limbs.len().max(1));
+            push_hex(limbs, false, buf);
+        }
+
+        /// Set `self` to zero, keeping its storage.
+        pub(crate) fn clear(&mut self) {
+            self.data.clear();
+        }
+
+        /// Set `self` to `a * b`, reusing the storage of `self`, by the same algorithms as
+        /// `mul`.
+        pub(crate) fn assign_mul(&mut self, a: &BigInt, b: &BigInt) {
+            #[cfg(feature = "mul-backend")]
+            if let Some(product) = crate::mul_backend::offload(&a.data, &b.data) {
+                return self.assign(&product);
+            }
+            let (a, b) = (a.as_limbs_le(), b.as_limbs_le());
+            #[cfg(feature = "tracing")]
+            crate::stats::record_mul(false, a.len(), b.len());
+            self.data.clear();
+            if a.is_empty() || b.is_empty() {
+                return;
+            }
+            self.data.resize(a.len() + b.len(), 0);
+            mul_into(&mut self.data, a, b);
+            self.normalize();
+        }
+
+        /// Set `self` to `other`, reusing the storage of `self`.
+        pub(crate) fn assign(&mut self, other: &BigInt) {
+            self.data.clear();
+            self.data.extend_from_slice(&other.data);
+        }
+
+        /// Add `other` to `self`, reusing the storage of `self`.
+        pub(crate) fn add_in_place(&mut self, other: &BigInt) {
+            if self.data.len() < other.data.len() {
+                self.data.resize(other.data.len(), 0);
+            }
+            let mut carry = 0;
+            for i in 0..self.data.len() {
+                if i >= other.data.len() && carry == 0 {
+                    break;
+                }
+                let digit_sum = self.data[i] as u64 + other.get(i) as u64 + carry;
+                self.data[i] = digit_sum as u32;
+                carry = digit_sum >> 32;
+            }
+            if carry > 0 {
+                self.data.push(carry as u32);
+            }
+        }
+
+        /// Multiply `self` by `other`, reusing the storage of `self` when `other` fits in a
+        /// single limb.
+        fn mul_in_place(&mut self, other: &BigInt) {
+            match other.data.len() {
+                0 => self.data.clear(),
+                1 => self.mul_add_limb(other.data[0], 0),
+                _ => *self = self.mul(other),
+            }
+        }
+
+        /// Subtract `other` from `self`, reusing the storage of `self`.
+        ///
+        /// Panics if `other` is larger than `self`.
+        pub(crate) fn sub_in_place(&mut self, other: &BigInt) {
+            assert!(*self >= *other, "subtraction underflow");
+            let mut borrow = 0;
+            for i in 0..self.data.len() {
+                if i >= other.data.len() && borrow == 0 {
+                    break;
+                }
+                let minuend = self.data[i] as u64;
+                let subtrahend = other.get(i) as u64 + borrow;
+                if minuend >= subtrahend {
+                    self.data[i] = (minuend - subtrahend) as u32;
+                    borrow = 0;
+                } else {
+                    self.data[i] = (minuend + BigInt::BASE - subtrahend) as u32;
+                    borrow = 1;
+                }
+            }
+            self.normalize();
+        }
+
+        /// Number of limbs, ignoring the most significant limbs which are zero.
+        fn significant_len(&self) -> usize {
+            let mut len = self.data.len();
+            while len > 0 && self.data[len - 1] == 0 {
+                len -= 1;
+            }
+            len
+        }
+
+        /// Compare with a primitive value without building a temporary BigInt.
+        fn cmp_u128(&self, other: u128) -> Ordering {
+            match self.to_u128() {
+                Some(value) => value.cmp(&other),
+                None => Ordering::Greater,
+            }
+        }
+
+        /// Compare exactly with a float, or `None` if it is NaN.
+        fn cmp_f64(&self, other: f64) -> Option<Ordering> {
+            if other.is_nan() {
+                return None;
+            }
+            if other <= 0.0 {
+                return if other == 0.0 && self.is_zero() {
+                    Some(Ordering::Equal)
+                } else {
+                    Some(Ordering::Greater)
+                };
+            }
+            if other < 1.0 {
+                return if self.is_zero() {
+                    Some(Ordering::Less)
+                } else {
+                    Some(Ordering::Greater)
+                };
+            }
+            if other.is_infinite() {
+                return Some(Ordering::Less);
+            }
+
+            let exponent = ((other.to_bits() >> 52) & 0x7ff) as usize - 1023;
+            match self.bits().cmp(&(exponent + 1)) {
+                Ordering::Equal => {}
+                ordering => return Some(ordering),
+            }
+            let integral_part = other.trunc();
+            match self.cmp(&BigInt::try_from_f64(integral_part).unwrap()) {
+                Ordering::Equal if integral_part < other => Some(Ordering::Less),
+                ordering => Some(ordering),
+            }
+        }
+
+        /// Remove the most significant limbs which are zero.
+        fn normalize(&mut self) {
+            let len = self.significant_len();
+            self.data.truncate(len);
+        }
+
+        /// Apply `op` limb by limb, padding the shorter operand with zeros.
+        fn bitwise<F: Fn(u32, u32) -> u32>(&self, other: &BigInt, op: F) -> BigInt {
+            let largest = std::cmp::max(self.data.len(), other.data.len());
+            let mut result = BigInt {
+                data: Vec::with_capacity(largest),
+            };
+            for i in 0..largest {
+                result.data.push(op(self.get(i), other.get(i)));
+            }
+            result.normalize();
+            result
+        }
+    }
+
+    impl Shl<usize> for &BigInt {
+        type Output = BigInt;
+
+        fn shl(self, n: usize) -> BigInt {
+            let limb_shift = n / 32;
+            let bit_shift = (n % 32) as u32;
+            let mut result = BigInt {
+                data: Vec::with_capacity(limb_shift + self.data.len() + 1),
+            };
+            result.data.resize(limb_shift, 0);
+            if bit_shift == 0 {
+                result.data.extend_from_slice(&self.data);
+            } else {
+                let mut carry = 0;
+                for d in &self.data {
+                    result.data.push((*d << bit_shift) | carry);
+                    carry = *d >> (32 - bit_shift);
+                }
+                result.data.push(carry);
+            }
+            result.normalize();
+            result
+        }
+    }
+
+    impl Shr<usize> for &BigInt {
+        type Output = BigInt;
+
+        fn shr(self, n: usize) -> BigInt {
+            let limb_shift = n / 32;
+            let bit_shift = (n % 32) as u32;
+            if limb_shift >= self.data.len() {
+                return BigInt::zero();
+            }
+            let mut result = BigInt {
+                data: Vec::with_capacity(self.data.len() - limb_shift),
+            };
+            for i in limb_shift..self.data.len() {
+                if bit_shift == 0 {
+                    result.data.push(self.data[i]);
+                } else {
+                    let high = self.get(i + 1) << (32 - bit_shift);
+                    result.data.push((self.data[i] >> bit_shift) | high);
+                }
+            }
+            result.normalize();
+            result
+        }
+    }
+
+    impl Shl<usize> for BigInt {
+        type Output = BigInt;
+
+        fn shl(self, n: usize) -> BigInt {
+            &self << n
+        }
+    }
+
+    impl Shr<usize> for BigInt {
+        type Output = BigInt;
+
+        fn shr(self, n: usize) -> BigInt {
+            &self >> n
+        }
+    }
+
+    /// Implement a binary operator trait for all combinations of owned and
+    /// borrowed operands, delegating to a function taking two references.
+    macro_rules! forward_binop {
+        ($imp:ident, $method:ident, $f:expr) => {
+            impl $imp<&BigInt> for &BigInt {
+                type Output = BigInt;
+
+                fn $method(self, other: &BigInt) -> BigInt {
+                    $f(self, other)
+                }
+            }
+
+            impl $imp<&BigInt> for BigInt {
+                type Output = BigInt;
+
+                fn $method(self, other: &BigInt) -> BigInt {
+                    $f(&self, other)
+                }
+            }
+
+            impl $imp<BigInt> for &BigInt {
+                type Output = BigInt;
+
+                fn $method(self, other: BigInt) -> BigInt {
+                    $f(self, &other)
+                }
+            }
+
+            impl $imp<BigInt> for BigInt {
+                type Output = BigInt;
+
+                fn $method(self, other: BigInt) -> BigInt {
+                    $f(&self, &other)
+                }
+            }
+        };
+    }
+
+    forward_binop!(BitAnd, bitand, |a: &BigInt, b: &BigInt| a.bitwise(b, |x, y| x & y));
+    forward_binop!(BitOr, bitor, |a: &BigInt, b: &BigInt| a.bitwise(b, |x, y| x | y));
+    forward_binop!(BitXor, bitxor, |a: &BigInt, b: &BigInt| a.bitwise(b, |x, y| x ^ y));
+    forward_ref_binop!(Add, add);
+    // Panics if the right operand is larger, like the subtraction of primitive integers.
+    forward_ref_binop!(Sub, sub);
+    forward_ref_binop!(Mul, mul);
+
+    impl PartialEq for BigInt {
+        fn eq(&self, other: &Self) -> bool {
+            let largest = std::cmp::max(self.data.len(), other.data.len());
+            for i in 0..largest {
+                if self.get(i) != other.get(i) {
+                    return false;
+                }
+            }
+            true
+        }
+    }
+
+    impl Eq for BigInt {}
+
+    impl Default for BigInt {
+        fn default() -> BigInt {
+            BigInt::zero()
+        }
+    }
+
+    impl Hash for BigInt {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.data[..self.significant_len()].hash(state);
+        }
+    }
+
+    impl Ord for BigInt {
+        fn cmp(&self, other: &Self) -> Ordering {
+            cmp_limbs(self.as_limbs_le(), other.as_limbs_le())
+        }
+    }
+
+    impl PartialOrd for BigInt {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    /// Implement comparisons in both directions between BigInt and unsigned primitives.
+    macro_rules! impl_primitive_cmp {
+        ($($t:ty),*) => {
+            $(
+                impl PartialEq<$t> for BigInt {
+                    fn eq(&self, other: &$t) -> bool {
+                        self.cmp_u128(*other as u128) == Ordering::Equal
+                    }
+                }
+
+                impl PartialEq<BigInt> for $t {
+                    fn eq(&self, other: &BigInt) -> bool {
+                        other == self
+                    }
+                }
+
+                impl PartialOrd<$t> for BigInt {
+                    fn partial_cmp(&self, other: &$t) -> Option<Ordering> {
+                        Some(self.cmp_u128(*other as u128))
+                    }
+                }
+
+                impl PartialOrd<BigInt> for $t {
+                    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+                        Some(other.cmp_u128(*self as u128).reverse())
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_primitive_cmp!(u8, u16, u32, u64, u128, usize);
+
+    impl PartialEq<f64> for BigInt {
+        fn eq(&self, other: &f64) -> bool {
+            self.cmp_f64(*other) == Some(Ordering::Equal)
+        }
+    }
+
+    impl PartialEq<BigInt> for f64 {
+        fn eq(&self, other: &BigInt) -> bool {
+            other == self
+        }
+    }
+
+    impl PartialOrd<f64> for BigInt {
+        fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+            self.cmp_f64(*other)
+        }
+    }
+
+    impl PartialOrd<BigInt> for f64 {
+        fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+            other.cmp_f64(*self).map(Ordering::reverse)
+        }
+    }
+
+    impl From<u128> for BigInt {
+        fn from(mut n: u128) -> BigInt {
+            let mut result = BigInt {
+                data: Vec::with_capacity(4),
+            };
+            while n > 0 {
+                result.data.push(n as u32);
+                n >>= 32;
+            }
+            result
+        }
+    }
+
+    /// Implement `From` for unsigned primitives narrower than u128.
+    macro_rules! impl_from_primitive {
+        ($($t:ty),*) => {
+            $(
+                impl From<$t> for BigInt {
+                    fn from(n: $t) -> BigInt {
+                        BigInt::from(n as u128)
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_from_primitive!(u8, u16, u32, u64, usize);
+
+    /// Collect limbs in base 2^32, least significant first.
+    impl FromIterator<u32> for BigInt {
+        fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> BigInt {
+            let mut result = BigInt {
+                data: iter.into_iter().collect(),
+            };
+            result.normalize();
+            result
+        }
+    }
+
+    impl Sum<BigInt> for BigInt {
+        fn sum<I: Iterator<Item = BigInt>>(iter: I) -> BigInt {
+            let mut result = BigInt::zero();
+            for b in iter {
+                result.add_in_place(&b);
+            }
+            result.normalize();
+            result
+        }
+    }
+
+    impl<'a> Sum<&'a BigInt> for BigInt {
+        fn sum<I: Iterator<Item = &'a BigInt>>(iter: I) -> BigInt {
+            let mut result = BigInt::zero();
+            for b in iter {
+                result.add_in_place(b);
+            }
+            result.normalize();
+            result
+        }
+    }
+
+    impl Product<BigInt> for BigInt {
+        fn product<I: Iterator<Item = BigInt>>(iter: I) -> BigInt {
+            let mut result = BigInt::one();
+            for b in iter {
+                result.mul_in_place(&b);
+            }
+            result
+        }
+    }
+
+    impl<'a> Product<&'a BigInt> for BigInt {
+        fn product<I: Iterator<Item = &'a BigInt>>(iter: I) -> BigInt {
+            let mut result = BigInt::one();
+            for b in iter {
+                result.mul_in_place(b);
+            }
+            result
+        }
+    }
+
+    impl Step for BigInt {
+        fn steps_between(start: &BigInt, end: &BigInt) -> (usize, Option<usize>) {
+            if start > end {
+                return (0, None);
+            }
+            let mut difference = end.clone();
+            difference.sub_in_place(start);
+            match difference.to_usize() {
+                Some(n) => (n, Some(n)),
+                None => (usize::MAX, None),
+            }
+        }
+
+        fn forward_checked(start: BigInt, count: usize) -> Option<BigInt> {
+            Some(start.add(&BigInt::from(count)))
+        }
+
+        fn backward_checked(mut start: BigInt, count: usize) -> Option<BigInt> {
+            let count = BigInt::from(count);
+            if start < count {
+                return None;
+            }
+            start.sub_in_place(&count);
+            Some(start)
+        }
+    }
+
+    /// An iterator over a range of BigInts, created by `range` or `range_inclusive`.
+    #[derive(Debug, Clone)]
+    pub struct Range {
+        next: BigInt,
+        end: BigInt,
+        stride: BigInt,
+        inclusive: bool,
+    }
+
+    impl Range {
+        /// Advance by `stride` instead of one at each step.
+        ///
+        /// Panics if `stride` is zero.
+        pub fn with_stride(mut self, stride: BigInt) -> Range {
+            assert!(!stride.is_zero(), "range stride must be positive");
+            self.stride = stride;
+            self
+        }
+    }
+
+    impl Iterator for Range {
+        type Item = BigInt;
+
+        fn next(&mut self) -> Option<BigInt> {
+            let in_range = if self.inclusive {
+                self.next <= self.end
+            } else {
+                self.next < self.end
+            };
+            if !in_range {
+                return None;
+            }
+            let result = self.next.clone();
+            self.next.add_in_place(&self.stride);
+            Some(result)
+        }
+    }
+
+    /// An iterator over the bits of a BigInt, created by `bits_le` or `bits_be`.
+    #[derive(Debug, Clone)]
+    pub struct Bits<'a> {
+        limbs: &'a [u32],
+        /// The positions `front..back` remain.
+        front: usize,
+        back: usize,
+    }
+
+    impl Bits<'_> {
+        fn at(&self, i: usize) -> bool {
+            (self.limbs[i / 32] >> (i % 32)) & 1 == 1
+        }
+    }
+
+    impl Iterator for Bits<'_> {
+        type Item = bool;
+
+        fn next(&mut self) -> Option<bool> {
+            if self.front == self.back {
+                return None;
+            }
+            self.front += 1;
+            Some(self.at(self.front - 1))
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let len = self.back - self.front;
+            (len, Some(len))
+        }
+    }
+
+    impl DoubleEndedIterator for Bits<'_> {
+        fn next_back(&mut self) -> Option<bool> {
+            if self.front == self.back {
+                return None;
+            }
+            self.back -= 1;
+            Some(self.at(self.back))
+        }
+    }
+
+    impl ExactSizeIterator for Bits<'_> {}
+
+    /// Iterate over the values from `start` (included) to `end` (excluded).
+    pub fn range(start: &BigInt, end: &BigInt) -> Range {
+        Range {
+            next: start.clone(),
+            end: end.clone(),
+            stride: BigInt::one(),
+            inclusive: false,
+        }
+    }
+
+    /// Iterate over the values from `start` to `end`, both included.
+    pub fn range_inclusive(start: &BigInt, end: &BigInt) -> Range {
+        Range {
+            inclusive: true,
+            ..range(start, end)
+        }
+    }
+
+    /// Allocation-free storage of `N` limbs in base 2^32, least significant first.
+    ///
+    /// Unlike `BigInt` it can be built in constant contexts, so tables of constants can
+    /// live in statics and be converted to `BigInt` when needed.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ConstBigInt<const N: usize> {
+        limbs: [u32; N],
+    }
+
+    impl ConstBigInt<1> {
+        pub const ONE: ConstBigInt<1> = ConstBigInt::from_limbs_le([1]);
+        pub const TWO: ConstBigInt<1> = ConstBigInt::from_limbs_le([2]);
+        pub const TEN: ConstBigInt<1> = ConstBigInt::from_limbs_le([10]);
+    }
+
+    impl<const N: usize> ConstBigInt<N> {
+        pub const fn from_limbs_le(limbs: [u32; N]) -> ConstBigInt<N> {
+            ConstBigInt { limbs }
+        }
+
+        /// Helper function for the `bigint!` macro: parse a stringified integer or string
+        /// literal, ignoring quotes and digit separators.
+        ///
+        /// Panics if the literal contains other non-digit characters or does not fit in `N`
+        /// limbs, which is a compile error when evaluated in a constant.
+        #[doc(hidden)]
+        pub const fn from_literal(literal: &str) -> ConstBigInt<N> {
+            let bytes = literal.as_bytes();
+            let mut limbs = [0; N];
+            let mut i = 0;
+            while i < bytes.len() {
+                let c = bytes[i];
+                i += 1;
+                if c == b'"' || c == b'_' {
+                    continue;
+                }
+                if c < b'0' || c > b'9' {
+                    panic!("invalid digit in BigInt literal");
+                }
+                let mut carry = (c - b'0') as u64;
+                let mut j = 0;
+                while j < N {
+                    let digit_product = limbs[j] as u64 * 10 + carry;
+                    limbs[j] = digit_product as u32;
+                    carry = digit_product >> 32;
+                    j += 1;
+                }
+                if carry > 0 {
+                    panic!("BigInt literal does not fit in the available limbs");
+                }
+            }
+            ConstBigInt { limbs }
+        }
+
+        /// The limbs, least significant first, without trailing zeros.
+        pub fn as_limbs_le(&self) -> &[u32] {
+            let mut len = N;
+            while len > 0 && self.limbs[len - 1] == 0 {
+                len -= 1;
+            }
+            &self.limbs[..len]
+        }
+
+        pub fn to_bigint(&self) -> BigInt {
+            BigInt::from_limbs_le(&self.limbs)
+        }
+    }
+
+    impl<const N: usize> From<&ConstBigInt<N>> for BigInt {
+        fn from(c: &ConstBigInt<N>) -> BigInt {
+            c.to_bigint()
+        }
+    }
+
+    impl<const N: usize> From<ConstBigInt<N>> for BigInt {
+        fn from(c: ConstBigInt<N>) -> BigInt {
+            c.to_bigint()
+        }
+    }
+
+    /// Helper function for the `bigint!` macro: an upper bound on the number of limbs
+    /// needed by a literal, from its length (each decimal digit takes less than 3.33 bits).
+    #[doc(hidden)]
+    pub const fn literal_limbs(literal: &str) -> usize {
+        (literal.len() * 3322).div_ceil(1000) / 32 + 1
+    }
+
+    /// A BigInt whose limbs are shared between clones and copied only on mutation.
+    ///
+    /// Cloning is a reference count increment, which makes it cheap to store the same
+    /// large value in many places. Read-only access goes through `Deref`.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+    pub struct SharedBigInt {
+        inner: Arc<BigInt>,
+    }
+
+    impl SharedBigInt {
+        pub fn new(b: BigInt) -> SharedBigInt {
+            SharedBigInt { inner: Arc::new(b) }
+        }
+
+        /// Mutable access to the value, copying the limbs first if they are shared.
+        pub fn make_mut(&mut self) -> &mut BigInt {
+            Arc::make_mut(&mut self.inner)
+        }
+
+        /// Extract the value, copying the limbs only if they are shared.
+        pub fn into_inner(self) -> BigInt {
+            Arc::try_unwrap(self.inner).unwrap_or_else(|shared| (*shared).clone())
+        }
+
+        /// Whether `self` and `other` share the same limb storage.
+        pub fn ptr_eq(&self, other: &SharedBigInt) -> bool {
+            Arc::ptr_eq(&self.inner, &other.inner)
+        }
+    }
+
+    impl Deref for SharedBigInt {
+        type Target = BigInt;
+
+        fn deref(&self) -> &BigInt {
+            &self.inner
+        }
+    }
+
+    impl From<BigInt> for SharedBigInt {
+        fn from(b: BigInt) -> SharedBigInt {
+            SharedBigInt::new(b)
+        }
+    }
+
+    /// Builds a BigInt from decimal digits received in pieces, without collecting them
+    /// into a string first. Digits are grouped nine at a time, which fit in a limb.
+    #[derive(Debug, Clone, Default)]
+    pub struct DecimalBuilder {
+        value: BigInt,
+        /// The digits not yet folded into `value`, and how many there are.
+        pending: u32,
+        pending_digits: u32,
+    }
+
+    impl DecimalBuilder {
+        pub fn new() -> DecimalBuilder {
+            DecimalBuilder::default()
+        }
+
+        /// Append one digit, given as its value. Panics if `digit` is not below ten.
+        pub fn push_digit(&mut self, digit: u8) {
+            assert!(digit < 10, "invalid decimal digit");
+            self.pending = self.pending * 10 + digit as u32;
+            self.pending_digits += 1;
+            if self.pending_digits == 9 {
+                self.flush();
+            }
+        }
+
+        /// Append a chunk of ASCII digits. Panics on any other character.
+        pub fn push_str(&mut self, digits: &str) {
+            for c in digits.bytes() {
+                assert!(c.is_ascii_digit(), "invalid decimal digit");
+                self.push_digit(c - b'0');
+            }
+        }
+
+        /// The value of all the digits pushed so far, zero if there are none.
+        pub fn finish(mut self) -> BigInt {
+            self.flush();
+            self.value.normalize();
+            self.value
+        }
+
+        fn flush(&mut self) {
+            if self.pending_digits > 0 {
+                self.value.mul_add_limb(10u32.pow(self.pending_digits), self.pending);
+                self.pending = 0;
+                self.pending_digits = 0;
+            }
+        }
+    }
+
+    /// A running total which defers carry propagation: each limb of every added value is
+    /// summed into a u64 column, and carries are only propagated when a column could
+    /// overflow or when the total is requested.
+    #[derive(Debug, Clone, Default)]
+    pub struct BigIntAccumulator {
+        columns: Vec<u64>,
+        /// Number of additions since the last carry propagation. Each one adds less than
+        /// 2^32 to every column, so u32::MAX of them fit in the upper halves.
+        pending: u32,
+    }
+
+    impl BigIntAccumulator {
+        pub fn new() -> BigIntAccumulator {
+            BigIntAccumulator::default()
+        }
+
+        pub fn add(&mut self, b: &BigInt) {
+            self.reserve_addition();
+            let limbs = b.as_limbs_le();
+            if self.columns.len() < limbs.len() {
+                self.columns.resize(limbs.len(), 0);
+            }
+            for (column, &limb) in self.columns.iter_mut().zip(limbs) {
+                *column += limb as u64;
+            }
+        }
+
+        pub fn add_u64(&mut self, n: u64) {
+            self.reserve_addition();
+            if self.columns.len() < 2 {
+                self.columns.resize(2, 0);
+            }
+            self.columns[0] += n & 0xffff_ffff;
+            self.columns[1] += n >> 32;
+        }
+
+        /// The total so far.
+        pub fn finish(mut self) -> BigInt {
+            self.propagate_carries();
+            let mut result = BigInt {
+                data: self.columns.iter().map(|&c| c as u32).collect(),
+            };
+            result.normalize();
+            result
+        }
+
+        fn reserve_addition(&mut self) {
+            if self.pending == u32::MAX {
+                self.propagate_carries();
+            }
+            self.pending += 1;
+        }
+
+        /// Leave a value below 2^32 in every column.
+        fn propagate_carries(&mut self) {
+            let mut carry = 0;
+            for column in self.columns.iter_mut() {
+                let t = *column + carry;
+                *column = t & 0xffff_ffff;
+                carry = t >> 32;
+            }
+            while carry > 0 {
+                self.columns.push(carry & 0xffff_ffff);
+                carry >>= 32;
+            }
+            self.pending = 0;
+        }
+    }
+
+    impl fmt::Display for BigInt {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write_decimal(self.as_limbs_le(), f)
+        }
+    }
+
+    impl crate::unsigned::BigUnsigned for BigInt {
+        fn zero() -> BigInt {
+            BigInt::zero()
+        }
+
+        fn from_decimal(s: &str) -> BigInt {
+            BigInt::from_string(s)
+        }
+
+        fn add(&self, other: &BigInt) -> BigInt {
+            BigInt::add(self, other)
+        }
+
+        fn mul(&self, other: &BigInt) -> BigInt {
+            BigInt::mul(self, other)
+        }
+
+        fn cmp(&self, other: &BigInt) -> Ordering {
+            Ord::cmp(self, other)
+        }
+
+        fn to_string(&self) -> String {
+            ToString::to_string(self)
+        }
+    }
+
+    impl fmt::LowerHex for BigInt {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.pad_integral(true, "0x", &hex_string(self.as_limbs_le(), false))
+        }
+    }
+
+    impl fmt::UpperHex for BigInt {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.pad_integral(true, "0x", &hex_string(self.as_limbs_le(), true))
+        }
+    }
+
+    /// A borrowed, read-only view of a value stored as limbs in base 2^32, least
+    /// significant first.
+    ///
+    /// It relates to `BigInt` like `str` relates to `String`: values embedded in larger
+    /// buffers can be compared, formatted and used as operands without copying them out.
+    #[derive(Debug, Clone, Copy)]
+    pub struct BigIntSlice<'a> {
+        limbs: &'a [u32],
+    }
+
+    impl<'a> BigIntSlice<'a> {
+        /// Build a view over `limbs`, ignoring the most significant limbs which are zero.
+        pub fn new(limbs: &'a [u32]) -> BigIntSlice<'a> {
+            let mut len = limbs.len();
+            while len > 0 && limbs[len - 1] == 0 {
+                len -= 1;
+            }
+            BigIntSlice {
+                limbs: &limbs[..len],
+            }
+        }
+
+        pub fn as_limbs_le(&self) -> &'a [u32] {
+            self.limbs
+        }
+
+        pub fn is_zero(&self) -> bool {
+            self.limbs.is_empty()
+        }
+
+        pub fn to_bigint(&self) -> BigInt {
+            BigInt {
+                data: self.limbs.to_vec(),
+            }
+        }
+
+        pub fn add(&self, other: BigIntSlice) -> BigInt {
+            add_limbs(self.limbs, other.limbs)
+        }
+
+        pub fn mul(&self, other: BigIntSlice) -> BigInt {
+            mul_limbs(self.limbs, other.limbs)
+        }
+    }
+
+    impl<'a> From<&'a BigInt> for BigIntSlice<'a> {
+        fn from(b: &'a BigInt) -> BigIntSlice<'a> {
+            b.as_slice()
+        }
+    }
+
+    impl PartialEq for BigIntSlice<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.limbs == other.limbs
+        }
+    }
+
+    impl Eq for BigIntSlice<'_> {}
+
+    impl PartialEq<BigInt> for BigIntSlice<'_> {
+        fn eq(&self, other: &BigInt) -> bool {
+            self.limbs == other.as_limbs_le()
+        }
+    }
+
+    impl PartialEq<BigIntSlice<'_>> for BigInt {
+        fn eq(&self, other: &BigIntSlice) -> bool {
+            self.as_limbs_le() == other.limbs
+        }
+    }
+
+    impl Ord for BigIntSlice<'_> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            cmp_limbs(self.limbs, other.limbs)
+        }
+    }
+
+    impl PartialOrd for BigIntSlice<'_> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Hash for BigIntSlice<'_> {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.limbs.hash(state);
+        }
+    }
+
+    impl fmt::Display for BigIntSlice<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write_decimal(self.limbs, f)
+        }
+    }
+
+    /// The error returned when a BigInt does not fit in the target primitive.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct TryFromBigIntError {
+        pub(crate) target: &'static str,
+    }
+
+    impl fmt::Display for TryFromBigIntError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "BigInt value is too large to fit in {}", self.target)
+        }
+    }
+
+    impl std::error::Error for TryFromBigIntError {}
+
+    /// The limit of `set_max_parse_digits`, `usize::MAX` for none.
+    static MAX_PARSE_DIGITS: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+    /// Make `from_string_with` and `try_from_string_bounded` reject strings longer than
+    /// `limit` bytes in every thread, or remove the limit with `None`, the default. The
+    /// panicking `from_string` is not affected.
+    pub fn set_max_parse_digits(limit: Option<usize>) {
+        MAX_PARSE_DIGITS.store(limit.unwrap_or(usize::MAX), AtomicOrdering::Relaxed);
+    }
+
+    /// The limit set with `set_max_parse_digits`.
+    pub fn max_parse_digits() -> Option<usize> {
+        match MAX_PARSE_DIGITS.load(AtomicOrdering::Relaxed) {
+            usize::MAX => None,
+            limit => Some(limit),
+        }
+    }
+
+    /// The sum of `heap_size` over `values`. The storage of the collection itself is not
+    /// included: for a `Vec<BigInt>` add `capacity() * size_of::<BigInt>()`.
+    pub fn total_heap_size<'a, I>(values: I) -> usize
+    where
+        I: IntoIterator<Item = &'a BigInt>,
+    {
+        values.into_iter().map(BigInt::heap_size).sum()
+    }
+
+    /// The error returned when a `should_stop` callback interrupted an operation, with
+    /// the partial result it had reached.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Cancelled<T> {
+        pub partial: T,
+    }
+
+    impl<T> fmt::Display for Cancelled<T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "operation cancelled")
+        }
+    }
+
+    impl<T: fmt::Debug> std::error::Error for Cancelled<T> {}
+
+    /// The error returned when a result would exceed the bit budget of `checked_pow` or
+    /// `checked_mul_limited`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct LimitExceededError {
+        max_bits: usize,
+    }
+
+    impl fmt::Display for LimitExceededError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "result would exceed the limit of {} bits", self.max_bits)
+        }
+    }
+
+    impl std::error::Error for LimitExceededError {}
+
+    /// How strictly `BigInt::from_string_with` reads its input. Every mode rejects
+    /// characters other than ASCII digits.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ParseMode {
+        /// The semantics of `from_string`: the empty string is zero and leading zeros are
+        /// ignored.
+        Lenient,
+        /// The empty string is an error; leading zeros are ignored.
+        Strict,
+        /// Like `Strict`, and leading zeros are an error too, except in `"0"` itself.
+        Canonical,
+    }
+
+    /// The error returned when a string does not hold a valid number.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ParseBigIntError {
+        reason: &'static str,
+    }
+
+    impl fmt::Display for ParseBigIntError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "cannot parse BigInt: {}", self.reason)
+        }
+    }
+
+    impl std::error::Error for ParseBigIntError {}
+
+    /// The error returned when a float has no exact BigInt representation.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct FromFloatError {
+        reason: &'static str,
+    }
+
+    impl fmt::Display for FromFloatError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "cannot convert float to BigInt: value is {}", self.reason)
+        }
+    }
+
+    impl std::error::Error for FromFloatError {}
+
+    /// Implement `TryFrom<&BigInt>` for unsigned primitives using the `to_*` helpers.
+    macro_rules! impl_try_from_bigint {
+        ($($t:ident => $to:ident),*) => {
+            $(
+                impl TryFrom<&BigInt> for $t {
+                    type Error = TryFromBigIntError;
+
+                    fn try_from(b: &BigInt) -> Result<$t, TryFromBigIntError> {
+                        b.$to().ok_or(TryFromBigIntError {
+                            target: stringify!($t),
+                        })
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_try_from_bigint!(u32 => to_u32, u64 => to_u64, u128 => to_u128, usize => to_usize);
+
+    #[deprecated(note = "use `BigInt::add` instead")]
+    pub fn sum(b1: &BigInt, b2: &BigInt) -> BigInt {
+        b1.add(b2)
+    }
+
+    #[deprecated(note = "use `BigInt::mul` instead")]
+    pub fn product(b1: &BigInt, b2: &BigInt) -> BigInt {
+        b1.mul(b2)
+    }
+
+    fn add_limbs(a: &[u32], b: &[u32]) -> BigInt {
+        let largest = std::cmp::max(a.len(), b.len());
+        let mut result = BigInt {
+            data: Vec::with_capacity(largest),
+        };
+        let mut carry = 0;
+        for i in 0..largest {
+            let digit_sum = limb(a, i) as u64 + limb(b, i) as u64 + carry;
+            if digit_sum >= BigInt::BASE {
+                result.data.push((digit_sum - BigInt::BASE) as u32);
+                carry = 1;
+            } else {
+                result.data.push(digit_sum as u32);
+                carry = 0;
+            }
+        }
+
+        if carry == 1 {
+            result.data.push(1);
+        }
+
+        result
+    }
+
+    /// Operands of at least this many limbs, both of them, are multiplied by Karatsuba's
+    /// method, below it by schoolbook multiplication.
+    const KARATSUBA_THRESHOLD: usize = 48;
+
+    fn mul_limbs(a: &[u32], b: &[u32]) -> BigInt {
+        #[cfg(feature = "tracing")]
+        crate::stats::record_mul(false, a.len(), b.len());
+        if a.is_empty() || b.is_empty() {
+            return BigInt::zero();
+        }
+        let mut result = BigInt { data: vec![0; a.len() + b.len()] };
+        mul_into(&mut result.data, a, b);
+        result.normalize();
+        result
+    }
+
+    /// Helper function for `mul_limbs`: `r += a * b`, where `r` has `a.len() + b.len()`
+    /// limbs and the sum does not overflow them.
+    ///
+    /// Karatsuba's method computes the cross terms `a0 b1 + a1 b0` of the halves as
+    /// `(a0 + a1)(b0 + b1) - a0 b0 - a1 b1`, three half-size products instead of four.
+    /// An operand more than twice as long as the other is cut in pieces of the length of
+    /// the shorter one, whose products are added up.
+    fn mul_into(r: &mut [u32], a: &[u32], b: &[u32]) {
+        let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+        if b.len() < KARATSUBA_THRESHOLD {
+            for (j, &d) in b.iter().enumerate() {
+                let carry = limb_ops::addmul_1(&mut r[j..j + a.len()], a, d);
+                limb_ops::add(&mut r[j + a.len()..], &[carry]);
+            }
+            return;
+        }
+        if a.len() >= 2 * b.len() {
+            for (i, piece) in a.chunks(b.len()).enumerate() {
+                let start = i * b.len();
+                mul_into(&mut r[start..start + piece.len() + b.len()], piece, b);
+            }
+            return;
+        }
+        let m = a.len() / 2;
+        let (a0, a1) = a.split_at(m);
+        let (b0, b1) = b.split_at(m);
+        let mut low = vec![0; 2 * m];
+        mul_into(&mut low, a0, b0);
+        let mut high = vec![0; a1.len() + b1.len()];
+        mul_into(&mut high, a1, b1);
+
+        let sum = |x: &[u32], y: &[u32]| {
+            let (long, short) = if x.len() >= y.len() { (x, y) } else { (y, x) };
+            let mut sum = Vec::with_capacity(long.len() + 1);
+            sum.extend_from_slice(long);
+            sum.push(0);
+            limb_ops::add(&mut sum, short);
+            sum
+        };
+        let (sa, sb) = (sum(a0, a1), sum(b0, b1));
+        let mut middle = vec![0; sa.len() + sb.len()];
+        mul_into(&mut middle, &sa, &sb);
+        limb_ops::sub(&mut middle, &low);
+        limb_ops::sub(&mut middle, &high);
+
+        // The cross terms are at most a b / 2^(32 m), so they fit in `r` from limb `m`.
+        let len = middle.iter().rposition(|&limb| limb != 0).map_or(0, |i| i + 1);
+        limb_ops::add(r, &low);
+        limb_ops::add(&mut r[m..], &middle[..len]);
+        limb_ops::add(&mut r[2 * m..], &high);
+    }
+
+    /// Long division of limb slices without trailing zeros, `v` being nonzero.
+    ///
+    /// This is Knuth's algorithm D: each quotient limb is estimated from the top limbs
+    /// of the running remainder and corrected at most twice, after normalizing the
+    /// divisor so that its top bit is set.
+    fn div_rem_limbs(u: &[u32], v: &[u32]) -> (BigInt, BigInt) {
+        #[cfg(feature = "tracing")]
+        crate::stats::record_division(u.len());
+        if cmp_limbs(u, v) == Ordering::Less {
+            return (BigInt::zero(), BigInt { data: u.to_vec() });
+        }
+        if v.len() == 1 {
+            let (quotient, remainder) = div_rem_limb(u, v[0]);
+            return (quotient, BigInt::from(remainder));
+        }
+
+        let n = v.len();
+        let m = u.len() - n;
+        let shift = v[n - 1].leading_zeros() as usize;
+        let vn = (BigInt { data: v.to_vec() } << shift).data;
+        let mut un = (BigInt { data: u.to_vec() } << shift).data;
+        un.resize(u.len() + 1, 0);
+        let mut quotient = vec![0; m + 1];
+
+        for j in (0..=m).rev() {
+            let top = (un[j + n] as u64) << 32 | un[j + n - 1] as u64;
+            let mut qhat = top / vn[n - 1] as u64;
+            let mut rhat = top % vn[n - 1] as u64;
+            while qhat >= BigInt::BASE
+                || qhat * vn[n - 2] as u64 > (rhat << 32 | un[j + n - 2] as u64)
+            {
+                qhat -= 1;
+                rhat += vn[n - 1] as u64;
+                if rhat >= BigInt::BASE {
+                    break;
+                }
+            }
+
+            // Multiply and subtract, tracking the borrow as a signed value.
+            let mut borrow: i64 = 0;
+            for i in 0..n {
+                let p = qhat * vn[i] as u64;
+                let t = un[i + j] as i64 - borrow - (p & 0xffff_ffff) as i64;
+                un[i + j] = t as u32;
+                borrow = (p >> 32) as i64 - (t >> 32);
+            }
+            let t = un[j + n] as i64 - borrow;
+            un[j + n] = t as u32;
+
+            if t < 0 {
+                // The estimate was one too large: add the divisor back.
+                qhat -= 1;
+                let mut carry = 0;
+                for i in 0..n {
+                    let digit_sum = un[i + j] as u64 + vn[i] as u64 + carry;
+                    un[i + j] = digit_sum as u32;
+                    carry = digit_sum >> 32;
+                }
+                un[j + n] = un[j + n].wrapping_add(carry as u32);
+            }
+            quotient[j] = qhat as u32;
+        }
+
+        un.truncate(n);
+        let remainder = BigInt { data: un } >> shift;
+        let mut quotient = BigInt { data: quotient };
+        quotient.normalize();
+        (quotient, remainder)
+    }
+
+    /// Division of a limb slice by a single nonzero limb.
+    fn div_rem_limb(u: &[u32], d: u32) -> (BigInt, u32) {
+        let mut quotient = vec![0; u.len()];
+        let mut remainder = 0;
+        for i in (0..u.len()).rev() {
+            let temp = (remainder << 32) | u[i] as u64;
+            quotient[i] = (temp / d as u64) as u32;
+            remainder = temp % d as u64;
+        }
+        let mut quotient = BigInt { data: quotient };
+        quotient.normalize();
+        (quotient, remainder as u32)
+    }
+
+    /// Compare two limb slices without trailing zeros.
+    fn cmp_limbs(a: &[u32], b: &[u32]) -> Ordering {
+        match a.len().cmp(&b.len()) {
+            Ordering::Equal => {}
+            ordering => return ordering,
+        }
+        for i in (0..a.len()).rev() {
+            match a[i].cmp(&b[i]) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// The limb at position `i`, or zero past the end of the slice.
+    fn limb(limbs: &[u32], i: usize) -> u32 {
+        if i < limbs.len() {
+            limbs[i]
+        } else {
+            0
+        }
+    }
+
+    /// Hexadecimal representation of a limb slice without trailing zeros, each limb
+    /// giving eight digits.
+    fn hex_string(limbs: &[u32], upper: bool) -> String {
+        let mut s = String::with_capacity(8 * limbs.len().max(1));
+        push_hex(limbs, upper, &mut s);
+        s
+    }
+
+    /// Helper function for `hex_string` and `write_hex_into`: append the digits of
+    /// `hex_string` to `buf`.
+    fn push_hex(limbs: &[u32], upper: bool, buf: &mut String) {
+        use std::fmt::Write;
+        let top = match limbs.last() {
+            None => return buf.push('0'),
+            Some(top) => top,
+        };
+        // Writing to a String cannot fail.
+        if upper {
+            write!(buf, "{:X}", top).unwrap();
+        } else {
+            write!(buf, "{:x}", top).unwrap();
+        }
+        for d in limbs.iter().rev().skip(1) {
+            if upper {
+                write!(buf, "{:08X}", d).unwrap();
+            } else {
+                write!(buf, "{:08x}", d).unwrap();
+            }
+        }
+    }
+
+    /// The base 10^9 digits of a limb slice without trailing zeros, least significant
+    /// first. Zero has no digits.
+    pub(crate) fn decimal_chunks(limbs: &[u32]) -> Vec<u32> {
+        decimal_chunks_with_progress(limbs, &mut |_, _| {})
+    }
+
+    /// Helper function for `decimal_chunks` and `to_string_with_progress`: reports
+    /// `n^2 - q^2` out of `n^2` whenever the quotient shrinks from `n` limbs to `q`, the
+    /// work done so far.
+    fn decimal_chunks_with_progress(
+        limbs: &[u32],
+        progress: &mut dyn FnMut(u64, u64),
+    ) -> Vec<u32> {
+        #[cfg(feature = "tracing")]
+        crate::stats::record_to_decimal();
+        const CHUNK: u64 = 1_000_000_000;
+        let total = (limbs.len() as u64).pow(2);
+        // Repeatedly divide by 10^9, collecting the remainders as base 10^9 digits.
+        let mut quotient = limbs.to_vec();
+        let mut chunks = Vec::new();
+        while !quotient.is_empty() {
+            let len = quotient.len();
+            let mut remainder = 0;
+            for d in quotient.iter_mut().rev() {
+                let temp = (remainder << 32) | *d as u64;
+                *d = (temp / CHUNK) as u32;
+                remainder = temp % CHUNK;
+            }
+            chunks.push(remainder as u32);
+            while let Some(&0) = quotient.last() {
+                quotient.pop();
+            }
+            if quotient.len() < len {
+                progress(total - (quotient.len() as u64).pow(2), total);
+            }
+        }
+        chunks
+    }
+
+    /// Write the decimal representation of a limb slice without trailing zeros.
+    ///
+    /// With a precision, as in `{:.12}`, a value with more digits is truncated to that
+    /// many leading digits followed by `…e+N`, where `N` is the number of digits dropped.
+    fn write_decimal(limbs: &[u32], f: &mut fmt::Formatter) -> fmt::Result {
+        write_signed_decimal(limbs, true, f)
+    }
+
+    /// `write_decimal` for a magnitude with a sign, as `pad_integral` takes it.
+    pub(crate) fn write_signed_decimal(
+        limbs: &[u32],
+        nonnegative: bool,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        if limbs.is_empty() {
+            return f.pad_integral(nonnegative, "", "0");
+        }
+        let chunks = decimal_chunks(limbs);
+        let mut s = String::with_capacity(9 * chunks.len());
+        push_decimal_chunks(&chunks, &mut s);
+        match f.precision() {
+            Some(precision) if precision.max(1) < s.len() => {
+                let kept = precision.max(1);
+                let truncated = format!("{}…e+{}", &s[..kept], s.len() - kept);
+                pad_truncated(f, nonnegative, &truncated)
+            }
+            _ => f.pad_integral(nonnegative, "", &s),
+        }
+    }
+
+    /// Helper function for `write_signed_decimal`: `pad_integral` with the width counted
+    /// in characters rather than bytes, which matters for the ellipsis.
+    fn pad_truncated(f: &mut fmt::Formatter, nonnegative: bool, digits: &str) -> fmt::Result {
+        use std::fmt::Write;
+        let sign = if !nonnegative {
+            "-"
+        } else if f.sign_plus() {
+            "+"
+        } else {
+            ""
+        };
+        let len = sign.len() + digits.chars().count();
+        let padding = f.width().map_or(0, |width| width.saturating_sub(len));
+        if f.sign_aware_zero_pad() {
+            return write!(f, "{}{}{}", sign, "0".repeat(padding), digits);
+        }
+        let (before, after) = match f.align() {
+            Some(fmt::Alignment::Left) => (0, padding),
+            Some(fmt::Alignment::Center) => (padding / 2, padding - padding / 2),
+            _ => (padding, 0),
+        };
+        let fill = f.fill();
+        for _ in 0..before {
+            f.write_char(fill)?;
+        }
+        write!(f, "{}{}", sign, digits)?;
+        for _ in 0..after {
+            f.write_char(fill)?;
+        }
+        Ok(())
+    }
+
+    /// Helper function for the decimal output: append the digits of base 10^9 chunks,
+    /// least significant first, with the leading zeros of the top chunk dropped, or `"0"`
+    /// if there are no chunks.
+    fn push_decimal_chunks(chunks: &[u32], buf: &mut String) {
+        use std::fmt::Write;
+        let top = match chunks.last() {
+            None => return buf.push('0'),
+            Some(top) => top,
+        };
+        // Writing to a String cannot fail.
+        write!(buf, "{}", top).unwrap();
+        for chunk in chunks.iter().rev().skip(1) {
+            write!(buf, "{:09}", chunk).unwrap();
+        }
+    }
+
+    /// Helper function for `cmp_decimal_str`: bounds on the number of decimal digits of the
+    /// values with exactly `bits` bits, `floor(log10(x)) + 1` for `x` in
+    /// `[2^(bits - 1), 2^bits)`. They differ by at most one.
+    pub(crate) fn decimal_digit_bounds(bits: usize) -> (usize, usize) {
+        // log10(2) in 64.64 fixed point, rounded down; adding one rounds up.
+        const LOG10_2: u128 = 0x4d10_4d42_7de7_fbcc;
+        let fewest = (((bits as u128 - 1) * LOG10_2) >> 64) as usize + 1;
+        let most = ((bits as u128 * (LOG10_2 + 1)) >> 64) as usize + 1;
+        (fewest, most)
+    }
+
+    /// Helper function for `from_string`: the values of consecutive runs of `chunk_size`
+    /// decimal digits, the last one possibly shorter.
+    ///
+    /// Panics on any byte that is not an ASCII digit.
+    fn split_string(s: &[u8], chunk_size: usize) -> Vec<u64> {
+        s.chunks(chunk_size)
+            .map(|chunk| {
+                chunk.iter().fold(0, |value, &b| {
+                    assert!(b.is_ascii_digit(), "invalid decimal digit");
+                    value * 10 + (b - b'0') as u64
+                })
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use test::Bencher;
+
+        #[bench]
+        fn bench_sum_short(b: &mut Bencher) {
+            let b1 = BigInt::from_string("34324");
+            let b2 = BigInt::from_string("11");
+            b.iter(|| b1.add(&b2))
+        }
+
+        #[bench]
+        fn bench_cmp_decimal_str(b: &mut Bencher) {
+            let value = BigInt::ten().pow(17000);
+            let shorter = "9".repeat(16999);
+            b.iter(|| value.cmp_decimal_str(&shorter))
+        }
+
+        #[bench]
+        fn bench_sum_long(b: &mut Bencher) {
+            let b1 = BigInt::from_string("9999999999999999999999999999999999999999999999999");
+            let b2 = BigInt::from_string("111111111111111111111111111111111123432342342111");
+            b.iter(|| b1.add(&b2))
+        }
+
+        #[bench]
+        fn bench_product_short(b: &mut Bencher) {
+            let b1 = BigInt::from_string("34324");
+            let b2 = BigInt::from_string("11");
+            b.iter(|| b1.mul(&b2))
+        }
+
+        #[bench]
+        fn bench_product_huge(b: &mut Bencher) {
+            let b1 = BigInt::from(3u32).pow(20000);
+            let b2 = BigInt::from(7u32).pow(11000);
+            b.iter(|| b1.mul(&b2))
+        }
+
+        #[bench]
+        fn bench_sqrt_rem_huge(b: &mut Bencher) {
+            let value = BigInt::from(3u32).pow(40000);
+            b.iter(|| value.sqrt_rem())
+        }
+
+        #[bench]
+        fn bench_product_long(b: &mut Bencher) {
+            let b1 = BigInt::from_string("9999999999999999999999999999999999999999999999999");
+            let b2 = BigInt::from_string("111111111111111111111111111111111123432342342111");
+            b.iter(|| b1.mul(&b2))
+        }
+
+        #[test]
+        fn test_eq() {
+            assert!(BigInt::from_string("") == BigInt::from_string(""));
+            assert!(BigInt::from_string("") == BigInt{data: vec![0, 0]});
+            assert!(BigInt::from_string("342") == BigInt{data: vec![342]});
+            assert!(BigInt::from_string("342") == BigInt{data: vec![342, 0, 0]});
+            assert!(BigInt{data: vec![342, 0, 0, 0]} == BigInt{data: vec![342, 0]});
+            assert!(BigInt{data: vec![0, 342, 0, 0]} != BigInt{data: vec![342, 0, 0]});
+        }
+
+        #[test]
+        fn test_hash() {
+            use std::collections::hash_map::DefaultHasher;
+            use std::collections::HashSet;
+
+            fn hash(b: &BigInt) -> u64 {
+                let mut hasher = DefaultHasher::new();
+                b.hash(&mut hasher);
+                hasher.finish()
+            }
+
+            assert_eq!(hash(&BigInt::zero()), hash(&BigInt { data: vec![0, 0] }));
+            assert_eq!(
+                hash(&BigInt { data: vec![342] }),
+                hash(&BigInt { data: vec![342, 0, 0] })
+            );
+
+            let mut set = HashSet::new();
+            set.insert(BigInt::from_string("4294967296"));
+            set.insert(BigInt { data: vec![0, 1, 0] });
+            set.insert(BigInt::from_string("0"));
+            set.insert(BigInt::zero());
+            assert_eq!(2, set.len());
+            assert!(set.contains(&BigInt { data: vec![0, 1] }));
+        }
+
+        #[test]
+        fn test_constants() {
+            static TABLE: [BigInt; 2] = [BigInt::ZERO, BigInt::zero()];
+            assert_eq!(BigInt::from_string("0"), TABLE[0]);
+            assert_eq!(BigInt::from_string("0"), TABLE[1]);
+            assert_eq!(BigInt::zero(), BigInt::default());
+            assert_eq!(BigInt::from_string("1"), BigInt::one());
+            assert_eq!(BigInt::from_string("2"), BigInt::two());
+            assert_eq!(BigInt::from_string("10"), BigInt::ten());
+
+            static SMALL: [ConstBigInt<1>; 3] = [ConstBigInt::ONE, ConstBigInt::TWO, ConstBigInt::TEN];
+            assert_eq!(BigInt::one(), SMALL[0].to_bigint());
+            assert_eq!(BigInt::two(), SMALL[1].to_bigint());
+            assert_eq!(BigInt::ten(), SMALL[2].to_bigint());
+        }
+
+        #[test]
+        fn test_cmp() {
+            assert_eq!(Ordering::Equal, BigInt::zero().cmp(&BigInt { data: vec![0, 0] }));
+            assert_eq!(
+                Ordering::Equal,
+                BigInt { data: vec![342] }.cmp(&BigInt { data: vec![342, 0, 0] })
+            );
+            assert!(BigInt { data: vec![342, 0] } < BigInt { data: vec![0, 1] });
+            assert!(BigInt { data: vec![1, 2, 0] } > BigInt { data: vec![2, 1] });
+            assert!(BigInt { data: vec![2, 1] } < BigInt { data: vec![1, 2] });
+            assert!(BigInt::from_string("99999999999999999999") < BigInt::from_string("100000000000000000000"));
+
+            let mut v = vec![
+                BigInt::from_string("4294967296"),
+                BigInt::from_string("12"),
+                BigInt::zero(),
+                BigInt::from_string("4294967295"),
+            ];
+            v.sort();
+            assert_eq!(
+                vec![
+                    BigInt::zero(),
+                    BigInt::from_string("12"),
+                    BigInt::from_string("4294967295"),
+                    BigInt::from_string("4294967296"),
+                ],
+                v
+            );
+        }
+
+        #[test]
+        fn test_cmp_primitive() {
+            assert!(BigInt::zero() == 0u8);
+            assert!(BigInt { data: vec![342, 0, 0] } == 342u16);
+            assert!(BigInt::from_string("4294967296") > 4294967295u32);
+            assert!(BigInt::from_string("4294967296") == 4294967296u64);
+            assert!(BigInt::from_string("18446744073709551616") > u64::MAX);
+            assert!(BigInt::from_string("340282366920938463463374607431768211455") == u128::MAX);
+            assert!(BigInt::from_string("340282366920938463463374607431768211456") > u128::MAX);
+            assert!(BigInt::from_string("12") < 13usize);
+            assert!(1u64 < BigInt::from_string("12"));
+            assert!(12u64 == BigInt::from_string("12"));
+            assert!(u64::MAX >= BigInt::from_string("18446744073709551615"));
+        }
+
+        #[test]
+        fn test_cmp_f64() {
+            let zero = BigInt::zero();
+            assert!(zero == 0.0);
+            assert!(zero == -0.0);
+            assert!(zero < 0.5);
+            assert!(zero > -1.0);
+            assert!(zero > f64::NEG_INFINITY);
+            assert_eq!(None, zero.partial_cmp(&f64::NAN));
+            assert!(!zero.eq(&f64::NAN));
+
+            let twelve = BigInt::from(12u32);
+            assert!(twelve == 12.0);
+            assert!(twelve > 11.5);
+            assert!(twelve < 12.5);
+            assert!(twelve > 0.5);
+            assert!(twelve < 16.0);
+            assert!(twelve > 8.0);
+            assert!(twelve < f64::INFINITY);
+            assert!(12.5 > twelve);
+            assert!(12.0 == twelve);
+
+            let googol = BigInt::from_string(
+                "10000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+            );
+            // 1e100 is slightly above 10^100 as a float.
+            assert!(googol < 1e100);
+            assert!(googol > 1e99);
+            assert!(BigInt::try_from_f64(1e100).unwrap() == 1e100);
+            assert!(&BigInt::try_from_f64(1e100).unwrap() | &BigInt::one() > 1e100);
+            assert!(BigInt::one() << 2000 < f64::INFINITY);
+            assert!(BigInt::one() << 2000 > f64::MAX);
+        }
+
+        #[test]
+        fn test_from_primitive() {
+            assert!(BigInt::from(0u8).data.is_empty());
+            assert_eq!(BigInt { data: vec![255] }, BigInt::from(255u8));
+            assert_eq!(BigInt { data: vec![65535] }, BigInt::from(65535u16));
+            assert_eq!(
+                BigInt {
+                    data: vec![4294967295]
+                },
+                BigInt::from(4294967295u32)
+            );
+            assert_eq!(BigInt { data: vec![0, 1] }, BigInt::from(4294967296u64));
+            assert_eq!(BigInt { data: vec![12] }, BigInt::from(12usize));
+            assert_eq!(
+                BigInt::from_string("42949672963434342343243324343232890890"),
+                BigInt::from(42949672963434342343243324343232890890u128)
+            );
+        }
+
+        #[test]
+        fn test_predicates() {
+            assert!(BigInt::zero().is_zero());
+            assert!(BigInt { data: vec![0, 0] }.is_zero());
+            assert!(!BigInt::one().is_zero());
+            assert!(BigInt::one().is_one());
+            assert!(BigInt { data: vec![1, 0] }.is_one());
+            assert!(!BigInt { data: vec![1, 1] }.is_one());
+            assert!(!BigInt::zero().is_one());
+            assert!(BigInt::zero().is_even());
+            assert!(!BigInt::zero().is_odd());
+            assert!(BigInt::from_string("4294967297").is_odd());
+            assert!(BigInt::from_string("4294967296").is_even());
+        }
+
+        #[test]
+        fn test_bits() {
+            assert_eq!(0, BigInt::zero().bits());
+            assert_eq!(1, BigInt::one().bits());
+            assert_eq!(32, BigInt::from(4294967295u32).bits());
+            assert_eq!(33, BigInt { data: vec![0, 1, 0] }.bits());
+            assert_eq!(32, BigInt::zero().leading_zeros_in_top_limb());
+            assert_eq!(31, BigInt { data: vec![0, 1, 0] }.leading_zeros_in_top_limb());
+            assert_eq!(0, BigInt::from(4294967295u32).leading_zeros_in_top_limb());
+        }
+
+        #[test]
+        fn test_bit() {
+            let b = BigInt::from(0b1011u32) | (BigInt::one() << 70);
+            assert!(b.bit(0));
+            assert!(b.bit(1));
+            assert!(!b.bit(2));
+            assert!(b.bit(3));
+            assert!(!b.bit(69));
+            assert!(b.bit(70));
+            assert!(!b.bit(1000));
+            assert_eq!(4, b.count_ones());
+            assert_eq!(0, BigInt::zero().count_ones());
+            assert_eq!(64, BigInt::from(u64::MAX).count_ones());
+        }
+
+        #[test]
+        fn test_hamming_distance() {
+            let a = BigInt::from(0b1011u32);
+            let b = BigInt::from(0b0110u32);
+            assert_eq!(3, a.hamming_distance(&b));
+            assert_eq!(0, a.hamming_distance(&a));
+            assert_eq!(3, a.hamming_distance(&BigInt::zero()));
+            let long = (BigInt::one() << 1000) | BigInt::from(u64::MAX);
+            assert_eq!(&long ^ &a, BigInt::from(u64::MAX - 0b1011) | (BigInt::one() << 1000));
+            assert_eq!((&long ^ &a).count_ones(), long.hamming_distance(&a));
+            assert_eq!(long.hamming_distance(&a), a.hamming_distance(&long));
+            let unnormalized = BigInt { data: vec![0b1011, 0, 0] };
+            assert_eq!(0, unnormalized.hamming_distance(&a));
+        }
+
+        #[test]
+        fn test_bits_le_be() {
+            assert_eq!(0, BigInt::zero().bits_le().len());
+            assert_eq!(None, BigInt::zero().bits_be().next());
+            let b = BigInt::from(0b1011u32) | (BigInt::one() << 70);
+            let le: Vec<bool> = b.bits_le().collect();
+            assert_eq!(71, le.len());
+            assert_eq!(vec![true, true, false, true, false], le[..5].to_vec());
+            assert!(le[70] && le[4..70].iter().all(|&x| !x));
+            let mut be: Vec<bool> = b.bits_be().collect();
+            be.reverse();
+            assert_eq!(le, be);
+            let mut bits = b.bits_be();
+            assert_eq!(71, bits.len());
+            assert_eq!(Some(true), bits.next());
+            assert_eq!(Some(false), bits.next());
+            assert_eq!(69, bits.len());
+            assert_eq!(Some(true), bits.next_back());
+            assert_eq!(68, bits.len());
+            let unnormalized = BigInt { data: vec![5, 0] };
+            assert_eq!(vec![true, false, true], unnormalized.bits_be().collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_iter_limbs() {
+            let b = BigInt { data: vec![1, 2, 3, 0] };
+            assert_eq!(vec![1, 2, 3], b.iter_limbs_le().collect::<Vec<_>>());
+            assert_eq!(vec![3, 2, 1], b.iter_limbs_be().collect::<Vec<_>>());
+            assert_eq!(3, b.iter_limbs_be().len());
+            assert_eq!(vec![3, (2 << 32) | 1], b.iter_u64_chunks_be().collect::<Vec<_>>());
+            assert_eq!(2, b.iter_u64_chunks_be().len());
+            let c = BigInt::from(u64::MAX) | (BigInt::from(7u32) << 64);
+            assert_eq!(vec![7, u64::MAX], c.iter_u64_chunks_be().collect::<Vec<_>>());
+            assert_eq!(vec![u64::MAX, 7], c.iter_u64_chunks_be().rev().collect::<Vec<_>>());
+            assert_eq!(0, BigInt::zero().iter_limbs_le().len());
+            assert_eq!(0, BigInt::zero().iter_u64_chunks_be().len());
+        }
+
+        #[test]
+        fn test_set_bit() {
+            let mut b = BigInt::zero();
+            b.set_bit(70, true);
+            assert_eq!(BigInt::one() << 70, b);
+            b.set_bit(1, true);
+            assert_eq!(&(BigInt::one() << 70) | &BigInt::two(), b);
+            b.set_bit(1, true);
+            assert_eq!(&(BigInt::one() << 70) | &BigInt::two(), b);
+            b.set_bit(70, false);
+            assert_eq!(BigInt::two(), b);
+            assert_eq!(1, b.data.len());
+            b.set_bit(200, false);
+            assert_eq!(BigInt::two(), b);
+            b.set_bit(1, false);
+            assert_eq!(BigInt::zero(), b);
+        }
+
+        #[test]
+        fn test_ilog() {
+            assert_eq!(0, BigInt::one().ilog2());
+            assert_eq!(31, BigInt::from(4294967295u32).ilog2());
+            assert_eq!(32, BigInt::from(4294967296u64).ilog2());
+            assert_eq!(1000, (BigInt::one() << 1000).ilog2());
+
+            assert_eq!(0, BigInt::one().ilog10());
+            assert_eq!(0, BigInt::from(9u32).ilog10());
+            assert_eq!(1, BigInt::ten().ilog10());
+            assert_eq!(19, BigInt::from_string("10000000000000000000").ilog10());
+            assert_eq!(18, BigInt::from_string("9999999999999999999").ilog10());
+            let googol = BigInt::from_string(
+                "10000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+            );
+            assert_eq!(100, googol.ilog10());
+            assert_eq!(100, BigInt::try_from_f64(1e100).unwrap().ilog10());
+            assert_eq!(
+                99,
+                BigInt::from_string(
+                    "9999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999"
+                )
+                .ilog10()
+            );
+
+            assert_eq!(Some(4), BigInt::from(81u32).checked_ilog(&BigInt::from(3u32)));
+            assert_eq!(Some(3), BigInt::from(80u32).checked_ilog(&BigInt::from(3u32)));
+            assert_eq!(Some(0), BigInt::from(2u32).checked_ilog(&BigInt::from(3u32)));
+            assert_eq!(Some(2), googol.checked_ilog(&(BigInt::one() << 150)));
+            assert_eq!(None, BigInt::zero().checked_ilog(&BigInt::ten()));
+            assert_eq!(None, googol.checked_ilog(&BigInt::one()));
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_ilog2_zero() {
+            BigInt::zero().ilog2();
+        }
+
+        #[test]
+        fn test_limbs() {
+            let b = BigInt::from_limbs_le(&[937946958, 287445, 0]);
+            assert_eq!(BigInt::from_string("1234567812345678"), b);
+            assert_eq!(&[937946958, 287445], b.as_limbs_le());
+            assert_eq!(vec![937946958, 287445], b.into_limbs());
+            assert_eq!(vec![342], BigInt { data: vec![342, 0, 0] }.into_limbs());
+            assert!(BigInt::from_limbs_le(&[0, 0]).as_limbs_le().is_empty());
+            assert_eq!(
+                BigInt::from_string("1234567812345678"),
+                vec![937946958, 287445, 0].into_iter().collect::<BigInt>()
+            );
+            assert_eq!(BigInt::zero(), Vec::new().into_iter().collect::<BigInt>());
+        }
+
+        #[test]
+        fn test_to_primitive() {
+            assert_eq!(Some(0), BigInt::zero().to_u32());
+            assert_eq!(Some(4294967295), BigInt::from_string("4294967295").to_u32());
+            assert_eq!(None, BigInt::from_string("4294967296").to_u32());
+            assert_eq!(Some(4294967296), BigInt::from_string("4294967296").to_u64());
+            assert_eq!(Some(342), BigInt { data: vec![342, 0, 0, 0, 0, 0] }.to_u64());
+            assert_eq!(None, BigInt::from_string("18446744073709551616").to_u64());
+            assert_eq!(
+                Some(u128::MAX),
+                BigInt::from_string("340282366920938463463374607431768211455").to_u128()
+            );
+            assert_eq!(
+                None,
+                BigInt::from_string("340282366920938463463374607431768211456").to_u128()
+            );
+            assert_eq!(Some(12), BigInt::from_string("12").to_usize());
+        }
+
+        #[test]
+        fn test_saturating() {
+            assert_eq!(0, BigInt::zero().to_u64_saturating());
+            assert_eq!(4294967296, BigInt::from_string("4294967296").to_u64_saturating());
+            assert_eq!(u64::MAX, BigInt::from_string("18446744073709551616").to_u64_saturating());
+            assert_eq!(
+                18446744073709551616,
+                BigInt::from_string("18446744073709551616").to_u128_saturating()
+            );
+            assert_eq!(u128::MAX, (BigInt::one() << 128).to_u128_saturating());
+
+            assert_eq!(BigInt::from(1000u32), BigInt::from(1000u32).clamp_to(10));
+            assert_eq!(BigInt::from(1023u32), BigInt::from(1024u32).clamp_to(10));
+            assert_eq!(BigInt::from(u64::MAX), (BigInt::one() << 100).clamp_to(64));
+            assert_eq!(BigInt::zero(), BigInt::one().clamp_to(0));
+            assert_eq!(
+                BigInt { data: vec![u32::MAX, u32::MAX, 1] },
+                (BigInt::one() << 100).clamp_to(65)
+            );
+        }
+
+        #[test]
+        fn test_truncate_to() {
+            let b = BigInt::from_string("340282366920938463463374607431768211455");
+            assert_eq!(BigInt::from(u64::MAX), b.truncate_to(64));
+            assert_eq!(BigInt::from(0x7fu32), b.truncate_to(7));
+            assert_eq!(b, b.truncate_to(200));
+            assert_eq!(BigInt::zero(), b.truncate_to(0));
+            assert_eq!(BigInt::zero(), (BigInt::one() << 64).truncate_to(64));
+            assert_eq!(BigInt::one(), (BigInt::one() << 64).add(&BigInt::one()).truncate_to(33));
+            assert_eq!(BigInt::from(0x7fu32), b.low_bits(7));
+        }
+
+        #[test]
+        fn test_bytes_be() {
+            assert_eq!(vec![0], BigInt::zero().to_bytes_be());
+            assert_eq!(BigInt::zero(), BigInt::from_bytes_be(&[0, 0]));
+            let value = BigInt::from_string("1311768467463790320");
+            let bytes = vec![0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0];
+            assert_eq!(bytes, value.to_bytes_be());
+            assert_eq!(value, BigInt::from_bytes_be(&[&[0], &bytes[..]].concat()));
+            assert_eq!(vec![1, 0, 0, 0, 0], (BigInt::one() << 32).to_bytes_be());
+            assert_eq!(BigInt::from(258u32), BigInt::from_bytes_be(&[1, 2]));
+        }
+
+        #[test]
+        fn test_from_string_with() {
+            use ParseMode::*;
+
+            let parse = |s: &str, mode| BigInt::from_string_with(s, mode).map_err(|e| e.reason);
+            assert_eq!(BigInt::zero(), BigInt::from_string(""));
+            assert_eq!(Ok(BigInt::zero()), parse("", Lenient));
+            assert_eq!(Err("empty string"), parse("", Strict));
+            assert_eq!(Err("empty string"), parse("", Canonical));
+
+            let value = BigInt::from(12345678901234567u64);
+            for mode in [Lenient, Strict, Canonical] {
+                assert_eq!(Ok(value.clone()), parse("12345678901234567", mode));
+                assert_eq!(Ok(BigInt::zero()), parse("0", mode));
+                assert_eq!(Err("invalid decimal digit"), parse("+1", mode));
+                assert_eq!(Err("invalid decimal digit"), parse("12 34", mode));
+                assert_eq!(Err("invalid decimal digit"), parse("-0", mode));
+            }
+            assert_eq!(Ok(value.clone()), parse("000000012345678901234567", Lenient));
+            assert_eq!(Ok(value.clone()), parse("000000012345678901234567", Strict));
+            assert_eq!(Err("redundant leading zero"), parse("012345678901234567", Canonical));
+            assert_eq!(Ok(BigInt::zero()), parse("0000", Strict));
+            assert_eq!(Err("redundant leading zero"), parse("00", Canonical));
+
+            // Leading zeros do not change the result, whatever their number.
+            let expected = BigInt::from_string("98765432109876543210");
+            for zeros in 0..20 {
+                let padded = format!("{}{}", "0".repeat(zeros), "98765432109876543210");
+                assert_eq!(expected, BigInt::from_string(&padded));
+            }
+            assert_eq!(
+                "cannot parse BigInt: empty string",
+                BigInt::from_string_with("", Strict).unwrap_err().to_string()
+            );
+        }
+
+        #[test]
+        fn test_from_string_bounded() {
+            let parse = |s: &str, max| BigInt::try_from_string_bounded(s, max).map_err(|e| e.reason);
+            assert_eq!(Ok(BigInt::from(12345u32)), parse("12345", 5));
+            assert_eq!(Err("too many digits"), parse("123456", 5));
+            assert_eq!(Err("too many digits"), parse("0012345", 5));
+            assert_eq!(Err("invalid decimal digit"), parse("12a45", 5));
+            assert_eq!(Ok(BigInt::zero()), parse("", 0));
+
+            // Far above the inputs of the other tests, which may run meanwhile.
+            let long = "1".repeat(2_000_001);
+            set_max_parse_digits(Some(2_000_000));
+            assert_eq!(Some(2_000_000), max_parse_digits());
+            let error = BigInt::from_string_with(&long, ParseMode::Strict).unwrap_err();
+            let bounded = parse(&long, usize::MAX);
+            set_max_parse_digits(None);
+            assert_eq!("too many digits", error.reason);
+            assert_eq!(Err("too many digits"), bounded);
+            assert_eq!(None, max_parse_digits());
+            assert!(BigInt::from_string_with(&long[..100], ParseMode::Strict).is_ok());
+        }
+
+        #[test]
+        fn test_cmp_decimal_str() {
+            let value = BigInt::from_string("123456789012345678901234567890");
+            for s in [
+                "",
+                "0",
+                "9",
+                "99999999999999999999999999999",
+                "123456789012345678901234567889",
+                "123456789012345678901234567890",
+                "000123456789012345678901234567890",
+                "123456789012345678901234567891",
+                "100000000000000000000000000000",
+                "999999999999999999999999999999",
+                "1000000000000000000000000000000",
+                "123456789012345678901234567890123456789",
+            ] {
+                let expected = value.cmp(&BigInt::from_string(s));
+                assert_eq!(Some(expected), value.cmp_decimal_str(s), "{}", s);
+                let expected = BigInt::zero().cmp(&BigInt::from_string(s));
+                assert_eq!(Some(expected), BigInt::zero().cmp_decimal_str(s), "{}", s);
+            }
+            for k in 1..80 {
+                let power = BigInt::ten().pow(k);
+                let below = power.sub(&BigInt::one());
+                assert_eq!(Some(Ordering::Equal), power.cmp_decimal_str(&power.to_string()));
+                assert_eq!(Some(Ordering::Less), below.cmp_decimal_str(&power.to_string()));
+                assert_eq!(Some(Ordering::Greater), power.cmp_decimal_str(&below.to_string()));
+            }
+            assert_eq!(None, BigInt::one().cmp_decimal_str("1e5"));
+            assert_eq!(None, BigInt::one().cmp_decimal_str("-1"));
+        }
+
+        #[test]
+        fn test_decimal_digit_bounds() {
+            for bits in 1..2000 {
+                let (fewest, most) = decimal_digit_bounds(bits);
+                let low = BigInt::one() << (bits - 1);
+                let high = (BigInt::one() << bits).sub(&BigInt::one());
+                assert_eq!(fewest, low.to_string().len(), "{}", bits);
+                assert_eq!(most, high.to_string().len(), "{}", bits);
+            }
+            // Far beyond what can be stored, the bounds stay at most one apart.
+            for &bits in [1usize << 40, (1 << 50) + 7, usize::MAX / 2].iter() {
+                let (fewest, most) = decimal_digit_bounds(bits);
+                assert!(most - fewest <= 1);
+            }
+        }
+
+        #[test]
+        fn test_from_string_non_ascii() {
+            use ParseMode::*;
+
+            // Multi-byte characters, including digits of other scripts, at every position
+            // relative to the chunks of `PARSE_STEP` digits.
+            for input in ["1234567é", "12345678é", "é", "١٢٣", "１２", "12345678901234567٣"] {
+                let error = BigInt::from_string_with(input, Lenient).unwrap_err();
+                assert_eq!("invalid decimal digit", error.reason);
+                let panic = std::panic::catch_unwind(|| BigInt::from_string(input)).unwrap_err();
+                assert_eq!(Some(&"invalid decimal digit"), panic.downcast_ref::<&str>());
+            }
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_from_string_invalid() {
+            BigInt::from_string("123456å");
+        }
+
+        #[test]
+        fn test_checked_limits() {
+            let ten = BigInt::ten();
+            let error = LimitExceededError { max_bits: 1000 };
+            assert_eq!(Ok(ten.pow(301)), ten.checked_pow(301, 1000));
+            assert_eq!(Err(error.clone()), ten.checked_pow(302, 1000));
+            assert_eq!(Err(error.clone()), ten.checked_pow(u32::MAX, 1000));
+            assert_eq!(Ok(BigInt::one() << 1000), BigInt::two().checked_pow(1000, 1001));
+            assert_eq!(
+                Err(LimitExceededError { max_bits: 1000 }),
+                BigInt::two().checked_pow(1000, 1000)
+            );
+            assert_eq!(Ok(BigInt::one()), BigInt::one().checked_pow(u32::MAX, 1));
+            assert_eq!(Ok(BigInt::zero()), BigInt::zero().checked_pow(u32::MAX, 0));
+            assert_eq!(Ok(BigInt::one()), ten.checked_pow(0, 1));
+
+            // 2^500 - 1 squared has 1000 bits, 2^500 squared 1001.
+            let below = (BigInt::one() << 500).sub(&BigInt::one());
+            let power = BigInt::one() << 500;
+            assert_eq!(Ok(below.mul(&below)), below.checked_mul_limited(&below, 1000));
+            assert_eq!(Err(error.clone()), power.checked_mul_limited(&power, 1000));
+            assert_eq!(Err(error), power.checked_mul_limited(&(power.mul(&power)), 1000));
+            assert_eq!(Ok(BigInt::zero()), power.checked_mul_limited(&BigInt::zero(), 0));
+            assert_eq!(
+                "result would exceed the limit of 64 bits",
+                power.checked_mul_limited(&power, 64).unwrap_err().to_string()
+            );
+        }
+
+        #[test]
+        fn test_heap_size() {
+            assert_eq!(0, BigInt::zero().heap_size());
+            let mut value = BigInt::one() << 95;
+            assert!(value.heap_size() >= 12);
+            value.data.reserve_exact(100);
+            assert_eq!(4 * value.data.capacity(), value.heap_size());
+
+            let values = vec![BigInt::zero(), BigInt::one() << 95, value.clone()];
+            let expected: usize = values.iter().map(|v| v.heap_size()).sum();
+            assert_eq!(expected, total_heap_size(&values));
+            assert_eq!(0, total_heap_size(&[] as &[BigInt]));
+        }
+
+        #[test]
+        fn test_mul_until() {
+            let a = (BigInt::one() << 10000).sub(&BigInt::from(12345u32));
+            let b = BigInt::from_string("98765432109876543210987654321");
+            assert_eq!(Ok(a.mul(&b)), a.mul_until(&b, &|| false));
+            assert_eq!(Ok(b.mul(&a)), b.mul_until(&a, &|| false));
+            assert_eq!(Ok(BigInt::zero()), a.mul_until(&BigInt::zero(), &|| true));
+            assert!(a.mul_until(&a, &|| true).is_err());
+
+            // One check per `MUL_ROWS_PER_CHECK` rows.
+            let checks = std::cell::Cell::new(0);
+            let count = || {
+                checks.set(checks.get() + 1);
+                false
+            };
+            assert_eq!(Ok(a.mul(&a)), a.mul_until(&a, &count));
+            assert_eq!(a.as_limbs_le().len().div_ceil(BigInt::MUL_ROWS_PER_CHECK), checks.get());
+        }
+
+        #[test]
+        fn test_to_string_with_progress() {
+            let value = BigInt::ten().pow(2000).sub(&BigInt::one());
+            let mut reports = Vec::new();
+            let s = value.to_string_with_progress(&mut |done, total| reports.push((done, total)));
+            assert_eq!(value.to_string(), s);
+            assert!(reports.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 == w[1].1));
+            let total = (value.as_limbs_le().len() as u64).pow(2);
+            assert_eq!(Some(&(total, total)), reports.last());
+
+            let mut calls = 0;
+            assert_eq!("0", BigInt::zero().to_string_with_progress(&mut |_, _| calls += 1));
+            assert_eq!(0, calls);
+        }
+
+        #[test]
+        fn test_stable_hash64() {
+            assert_eq!(0xcbf2_9ce4_8422_2325, BigInt::zero().stable_hash64());
+            assert_eq!(0xaf63_bc4c_8601_b62c, BigInt::one().stable_hash64());
+            let value = BigInt::from(u64::MAX).add(&BigInt::from(256u32));
+            assert_eq!(0x2032_de64_3620_26e1, value.stable_hash64());
+            let padded = BigInt::from_limbs_le(&[1, 0, 0]);
+            assert_eq!(BigInt::one().stable_hash64(), padded.stable_hash64());
+        }
+
+        #[test]
+        fn test_write_into() {
+            let mut buf = String::from("n=");
+            BigInt::zero().write_decimal_into(&mut buf);
+            buf.push(' ');
+            let value = BigInt::ten().pow(30).add(&BigInt::from(42u32));
+            value.write_decimal_into(&mut buf);
+            assert_eq!(format!("n=0 {}", value), buf);
+
+            buf.clear();
+            BigInt::zero().write_hex_into(&mut buf);
+            value.write_hex_into(&mut buf);
+            assert_eq!(format!("0{:x}", value), buf);
+        }
+
+        #[test]
+        fn test_display_precision() {
+            let value = BigInt::ten().pow(50).mul(&BigInt::from(123456789u32));
+            assert_eq!("123456789000…e+47", format!("{:.12}", value));
+            assert_eq!("1…e+58", format!("{:.0}", value));
+            assert_eq!(value.to_string(), format!("{:.59}", value));
+            assert_eq!("0", format!("{:.3}", BigInt::zero()));
+            assert_eq!("  12…e+1", format!("{:>8.2}", BigInt::from(123u32)));
+            assert_eq!("12…e+1  ", format!("{:<8.2}", BigInt::from(123u32)));
+            assert_eq!("+0012…e+1", format!("{:+09.2}", BigInt::from(123u32)));
+            assert_eq!("12…e+1", format!("{:.2}", BigIntSlice::from(&BigInt::from(123u32))));
+        }
+
+        #[test]
+        fn test_mul_karatsuba() {
+            fn schoolbook(a: &[u32], b: &[u32]) -> BigInt {
+                let mut data = vec![0; a.len() + b.len()];
+                for (j, &d) in b.iter().enumerate() {
+                    data[j + a.len()] = limb_ops::addmul_1(&mut data[j..j + a.len()], a, d);
+                }
+                BigInt { data }
+            }
+            let mut state = 1u64;
+            let mut limbs = |len: usize| -> Vec<u32> {
+                let mut next = || {
+                    state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    (state >> 32) as u32
+                };
+                (0..len).map(|_| next()).collect()
+            };
+            let sizes = [1, 47, 48, 49, 96, 97, 150, 300];
+            for &m in sizes.iter() {
+                for &n in sizes.iter() {
+                    let (a, b) = (limbs(m), limbs(n));
+                    let expected = schoolbook(&a, &b);
+                    assert_eq!(expected, mul_limbs(&a, &b), "{} {}", m, n);
+                    let mut product = BigInt::zero();
+                    product.assign_mul(&BigInt { data: a }, &BigInt { data: b });
+                    assert_eq!(expected, product, "{} {}", m, n);
+                }
+                // All ones maximizes the carries.
+                let ones = vec![u32::MAX; m];
+                assert_eq!(schoolbook(&ones, &ones), mul_limbs(&ones, &ones), "{}", m);
+            }
+            let x = BigInt::from(3u32).pow(5000);
+            assert_eq!(x.pow(2).sqrt_rem(), (x, BigInt::zero()));
+        }
+
+        #[test]
+        fn test_keep_low_bits() {
+            let mut b = BigInt::from_string("340282366920938463463374607431768211455");
+            b.keep_low_bits(200);
+            assert_eq!(BigInt::from_string("340282366920938463463374607431768211455"), b);
+            b.keep_low_bits(64);
+            assert_eq!(BigInt::from(u64::MAX), b);
+            assert_eq!(2, b.data.len());
+            b.keep_low_bits(33);
+            assert_eq!(BigInt::from((1u64 << 33) - 1), b);
+            b.keep_low_bits(32);
+            assert_eq!(BigInt::from(u32::MAX), b);
+            b.keep_low_bits(0);
+            assert_eq!(BigInt::zero(), b);
+            let mut c = BigInt::one() << 64;
+            c.keep_low_bits(64);
+            assert_eq!(BigInt::zero(), c);
+        }
+
+        #[test]
+        fn test_bit_slice() {
+            let b = BigInt::from_string("340282366920938463463374607431768211455") ^ BigInt::from(0b1010u32);
+            assert_eq!(BigInt::from(0b0101u32), b.bit_slice(0, 4));
+            assert_eq!(BigInt::from(0b10u32), b.bit_slice(1, 2));
+            assert_eq!(BigInt::from(u64::MAX), b.bit_slice(64, 64));
+            assert_eq!(BigInt::from(u64::MAX), b.bit_slice(64, 200));
+            assert_eq!(BigInt::from(0xffu32), b.bit_slice(120, 100));
+            assert_eq!(BigInt::from(u32::MAX), b.bit_slice(35, 32));
+            assert_eq!(BigInt::zero(), b.bit_slice(128, 10));
+            assert_eq!(BigInt::zero(), b.bit_slice(1000, 10));
+            assert_eq!(BigInt::zero(), b.bit_slice(3, 0));
+            // Aligned starts beyond the last limb.
+            assert_eq!(BigInt::zero(), BigInt::from(5u32).bit_slice(64, 8));
+            assert_eq!(BigInt::zero(), b.bit_slice(160, 32));
+            assert_eq!(BigInt::zero(), b.bit_slice(1024, 64));
+            assert_eq!(BigInt::zero(), BigInt::zero().bit_slice(0, 8));
+            let value = BigInt::from(3u32).pow(300);
+            for start in [0, 5, 32, 37, 64, 100] {
+                for len in [1, 31, 32, 33, 64, 90, 500] {
+                    let expected = (value.clone() >> start).truncate_to(len);
+                    assert_eq!(expected, value.bit_slice(start, len), "{} {}", start, len);
+                }
+            }
+        }
+
+        #[test]
+        fn test_try_from() {
+            assert_eq!(Ok(12), u32::try_from(&BigInt::from_string("12")));
+            assert_eq!(Ok(4294967296), u64::try_from(&BigInt::from_string("4294967296")));
+            assert_eq!(Ok(12), usize::try_from(&BigInt::from_string("12")));
+            let err = u32::try_from(&BigInt::from_string("4294967296")).unwrap_err();
+            assert_eq!("BigInt value is too large to fit in u32", err.to_string());
+            let err = u128::try_from(&BigInt::from_string("340282366920938463463374607431768211456"))
+                .unwrap_err();
+            assert_eq!("BigInt value is too large to fit in u128", err.to_string());
+        }
+
+        #[test]
+        fn test_to_float() {
+            assert_eq!(0.0, BigInt::zero().to_f64());
+            assert_eq!(4294967296.0, BigInt::from_string("4294967296").to_f64());
+            assert_eq!(1e30, BigInt::from_string("1000000000000000000000000000000").to_f64());
+            assert_eq!(
+                1.8446744073709552e19,
+                BigInt::from_string("18446744073709551615").to_f64()
+            );
+            // 2^64 + 2^11 lies exactly between two f64 values and rounds to even...
+            assert_eq!(
+                18446744073709551616.0,
+                BigInt::from_string("18446744073709553664").to_f64()
+            );
+            // ...but any lower bit breaks the tie upwards.
+            assert_eq!(
+                18446744073709555712.0,
+                BigInt::from_string("18446744073709553665").to_f64()
+            );
+            assert_eq!(
+                18446744073709555712.0,
+                (&(BigInt::from_string("18446744073709553664") << 64) | &BigInt::one()).to_f64()
+                    / 18446744073709551616.0
+            );
+            assert_eq!(2f64.powi(1023), (BigInt::one() << 1023).to_f64());
+            assert_eq!(f64::INFINITY, (BigInt::one() << 1024).to_f64());
+            assert_eq!(
+                f64::INFINITY,
+                BigInt::from_limbs_le(&[u32::MAX; 32]).to_f64()
+            );
+
+            assert_eq!(0.0, BigInt::zero().to_f32());
+            assert_eq!(1e30, BigInt::from_string("1000000000000000000000000000000").to_f32());
+            assert_eq!(2f32.powi(127), (BigInt::one() << 127).to_f32());
+            assert_eq!(f32::INFINITY, (BigInt::one() << 128).to_f32());
+        }
+
+        #[test]
+        fn test_try_from_f64() {
+            assert_eq!(Ok(BigInt::zero()), BigInt::try_from_f64(0.0));
+            assert_eq!(Ok(BigInt::zero()), BigInt::try_from_f64(-0.0));
+            assert_eq!(Ok(BigInt::one()), BigInt::try_from_f64(1.0));
+            assert_eq!(Ok(BigInt::from(4294967296u64)), BigInt::try_from_f64(4294967296.0));
+            assert_eq!(
+                Ok(BigInt::from(9007199254740992u64)),
+                BigInt::try_from_f64(9007199254740992.0)
+            );
+            assert_eq!(
+                Ok(BigInt::from_string("1000000000000000019884624838656")),
+                BigInt::try_from_f64(1e30)
+            );
+            assert_eq!(Ok(BigInt::one() << 1023), BigInt::try_from_f64(2f64.powi(1023)));
+            assert_eq!(
+                Ok(BigInt::from_string("18446744073709555712")),
+                BigInt::try_from_f64(BigInt::from_string("18446744073709555712").to_f64())
+            );
+
+            assert_eq!(
+                "cannot convert float to BigInt: value is NaN",
+                BigInt::try_from_f64(f64::NAN).unwrap_err().to_string()
+            );
+            assert_eq!(
+                "cannot convert float to BigInt: value is infinite",
+                BigInt::try_from_f64(f64::INFINITY).unwrap_err().to_string()
+            );
+            assert_eq!(
+                "cannot convert float to BigInt: value is negative",
+                BigInt::try_from_f64(-1.0).unwrap_err().to_string()
+            );
+            assert_eq!(
+                "cannot convert float to BigInt: value is not an integer",
+                BigInt::try_from_f64(2.5).unwrap_err().to_string()
+            );
+            assert!(BigInt::try_from_f64(0.5).is_err());
+            assert!(BigInt::try_from_f64(f64::MIN_POSITIVE / 2.0).is_err());
+        }
+
+        #[test]
+        fn test_from_string() {
+            assert_eq!(BigInt { data: vec![4] }, BigInt::from_string("4"));
+            assert_eq!(BigInt::zero(), BigInt::from_string(""));
+            assert!(BigInt::from_string("0").data.is_empty());
+            assert_eq!(
+                BigInt {
+                    data: vec![4294967295]
+                },
+                BigInt::from_string("4294967295")
+            );
+            assert_eq!(
+                BigInt { data: vec![0, 1] },
+                BigInt::from_string("4294967296")
+            );
+            assert_eq!(
+                BigInt {
+                    data: vec![937946958, 287445]
+                },
+                BigInt::from_string("1234567812345678")
+            );
+            assert_eq!(
+                BigInt {
+                    data: vec![3435973836, 214748364]
+                },
+                BigInt::from_string("922337203685477580")
+            );
+            assert_eq!(
+                BigInt {
+                    data: vec![4294963245, 4294967295, 499]
+                },
+                BigInt::from_string("9223372036854775803949")
+            );
+            assert_eq!(
+                BigInt {
+                    data: vec![3461744650, 2330743505, 1228788904, 542101086]
+                },
+                BigInt::from_string("42949672963434342343243324343232890890")
+            );
+        }
+
+        #[test]
+        #[allow(deprecated)]
+        fn test_sum() {
+            assert_eq!(
+                BigInt {
+                    data: vec![0, 3, 1]
+                },
+                sum(
+                    &BigInt {
+                        data: vec![(BigInt::BASE - 1) as u32, 1]
+                    },
+                    &BigInt {
+                        data: vec![1, 1, 1]
+                    }
+                )
+            );
+            assert_eq!(
+                BigInt::from_string("683598743919434280434619734254544588"),
+                sum(
+                    &BigInt::from_string("683598349590386730945834985730495834"),
+                    &BigInt::from_string("394329047549488784748524048754")
+                )
+            );
+            assert_eq!(
+                BigInt::from_string("10111111111111111111111111111111111123432342342110"),
+                sum(
+                    &BigInt::from_string("9999999999999999999999999999999999999999999999999"),
+                    &BigInt::from_string("111111111111111111111111111111111123432342342111")
+                )
+            );
+        }
+
+        #[test]
+        #[allow(deprecated)]
+        fn test_product() {
+            assert_eq!(
+                BigInt {
+                    data: vec![4294931842, 177267, 35464, 2]
+                },
+                product(
+                    &BigInt {
+                        data: vec![35454, 2]
+                    },
+                    &BigInt {
+                        data: vec![(BigInt::BASE - 1) as u32, 4, 1]
+                    }
+                )
+            );
+            assert_eq!(BigInt::from_string("1111111111111111111111111111111111234323423421109888888888888888888888888888888888876567657657889"),
+                       product(&BigInt::from_string("9999999999999999999999999999999999999999999999999"),
+                               &BigInt::from_string("111111111111111111111111111111111123432342342111")));
+        }
+
+        #[test]
+        fn test_add_mul() {
+            let b1 = BigInt::from_string("9999999999999999999999999999999999999999999999999");
+            let b2 = BigInt::from_string("111111111111111111111111111111111123432342342111");
+            assert_eq!(
+                BigInt::from_string("10111111111111111111111111111111111123432342342110"),
+                b1.add(&b2)
+            );
+            assert_eq!(BigInt::from_string("1111111111111111111111111111111111234323423421109888888888888888888888888888888888876567657657889"),
+                       b1.mul(&b2));
+            assert_eq!(
+                BigInt::from(10u32),
+                BigInt::one().add(&BigInt::two()).mul(&BigInt::from(3u32)).add(&BigInt::one())
+            );
+        }
+
+        #[test]
+        fn test_pow() {
+            assert_eq!(BigInt::one(), BigInt::zero().pow(0));
+            assert_eq!(BigInt::zero(), BigInt::zero().pow(3));
+            assert_eq!(BigInt::from(1024u32), BigInt::two().pow(10));
+            assert_eq!(BigInt::one() << 1000, BigInt::two().pow(1000));
+            assert_eq!(
+                BigInt::from_string("1000000000000000000000000000000"),
+                BigInt::ten().pow(30)
+            );
+        }
+
+        #[test]
+        fn test_gcd_lcm() {
+            let a = BigInt::from_string("1234567890123456789012345678901234567890");
+            let b = BigInt::from_string("9876543210");
+            assert_eq!(BigInt::from(90u32), a.gcd(&b));
+            assert_eq!(a.gcd(&b), b.gcd(&a));
+            assert_eq!(a, a.gcd(&BigInt::zero()));
+            assert_eq!(a, BigInt::zero().gcd(&a));
+            assert_eq!(BigInt::zero(), BigInt::zero().gcd(&BigInt::zero()));
+            assert_eq!(BigInt::from(36u32), BigInt::from(12u32).lcm(&BigInt::from(18u32)));
+            assert_eq!(BigInt::zero(), a.lcm(&BigInt::zero()));
+            assert_eq!(a.mul(&b).div(&a.gcd(&b)), a.lcm(&b));
+        }
+
+        #[test]
+        fn test_midpoint() {
+            assert_eq!(BigInt::zero(), BigInt::zero().midpoint(&BigInt::one()));
+            assert_eq!(BigInt::from(5u32), BigInt::from(3u32).midpoint(&BigInt::from(7u32)));
+            assert_eq!(BigInt::from(5u32), BigInt::from(7u32).midpoint(&BigInt::from(4u32)));
+            let max = BigInt::one().shl(2048).sub(&BigInt::one());
+            assert_eq!(max, max.midpoint(&max));
+            assert_eq!(BigInt::one().shl(2047), BigInt::one().midpoint(&max));
+
+            // Binary search for the largest x with x^3 <= n.
+            let n = BigInt::from(12345u32).pow(3).add(&BigInt::from(100u32));
+            let (mut low, mut high) = (BigInt::zero(), n.clone());
+            while low < high {
+                let middle = low.midpoint(&high).add(&BigInt::one());
+                if middle.pow(3) <= n {
+                    low = middle;
+                } else {
+                    high = middle.sub(&BigInt::one());
+                }
+            }
+            assert_eq!(BigInt::from(12345u32), low);
+        }
+
+        #[test]
+        fn test_min_max_clamp_ref() {
+            let small = BigInt::from(3u32);
+            let large = BigInt::one() << 100;
+            assert_eq!(&small, small.min_ref(&large));
+            assert_eq!(&small, large.min_ref(&small));
+            assert_eq!(&large, small.max_ref(&large));
+            assert_eq!(&large, large.max_ref(&small));
+            // Ties pick the same side as std::cmp.
+            let same = BigInt { data: vec![3, 0] };
+            assert!(std::ptr::eq(&small, small.min_ref(&same)));
+            assert!(std::ptr::eq(&same, small.max_ref(&same)));
+
+            let middle = BigInt::from(1000u32);
+            assert_eq!(&middle, middle.clamp_ref(&small, &large));
+            assert_eq!(&small, BigInt::one().clamp_ref(&small, &large));
+            assert_eq!(&large, (BigInt::one() << 200).clamp_ref(&small, &large));
+            assert_eq!(&small, middle.clamp_ref(&small, &small));
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_clamp_ref_empty() {
+            BigInt::one().clamp_ref(&BigInt::two(), &BigInt::one());
+        }
+
+        #[test]
+        fn test_sqrt() {
+            for n in 0..1000u32 {
+                let r = BigInt::from(n).sqrt().to_u32().unwrap();
+                assert!(r * r <= n && (r + 1) * (r + 1) > n, "{}", n);
+            }
+            let a = BigInt::from_string("123456789012345678901234567890");
+            assert_eq!(a, a.mul(&a).sqrt());
+            assert_eq!(a, a.mul(&a).add(&a).add(&a).sqrt());
+            assert_eq!(a.sub(&BigInt::one()), a.mul(&a).sub(&BigInt::one()).sqrt());
+        }
+
+        #[test]
+        fn test_sqrt_rem() {
+            assert_eq!((BigInt::zero(), BigInt::zero()), BigInt::zero().sqrt_rem());
+            assert_eq!((BigInt::two(), BigInt::two()), BigInt::from(6u32).sqrt_rem());
+            let u128_max = BigInt::from(u128::MAX);
+            assert_eq!((BigInt::from(u64::MAX), BigInt::from(u64::MAX).shl(1)), u128_max.sqrt_rem());
+            let mut value = BigInt::from(7u32);
+            for i in 0..400 {
+                // Every residue of the bit length modulo 4, with various bit patterns.
+                value = value.mul(&BigInt::from(3u32)).add(&BigInt::from(i as u32));
+                let (s, r) = value.sqrt_rem();
+                assert_eq!(value, s.mul(&s).add(&r), "{}", value);
+                assert!(r <= s.shl(1), "{}", value);
+            }
+            let a = BigInt::from(3u32).pow(2000);
+            assert_eq!((a.clone(), BigInt::zero()), a.mul(&a).sqrt_rem());
+            let below = a.mul(&a).sub(&BigInt::one());
+            assert_eq!((a.sub(&BigInt::one()), a.shl(1).sub(&BigInt::two())), below.sqrt_rem());
+        }
+
+        #[test]
+        fn test_nth_root() {
+            for n in 0..3000u32 {
+                for k in 1..6 {
+                    let r = BigInt::from(n).nth_root(k).to_u64().unwrap();
+                    assert!(r.pow(k) <= n as u64 && (r + 1).pow(k) > n as u64, "{} {}", n, k);
+                }
+            }
+            let a = BigInt::from_string("123456789012345678901234567890");
+            assert_eq!(a, a.pow(7).nth_root(7));
+            assert_eq!(a.sub(&BigInt::one()), a.pow(7).sub(&BigInt::one()).nth_root(7));
+            assert_eq!(BigInt::one(), a.nth_root(200));
+            assert_eq!(a.sqrt(), a.nth_root(2));
+        }
+
+        #[test]
+        fn test_range() {
+            let start = BigInt::from_string("18446744073709551614");
+            let end = BigInt::from_string("18446744073709551618");
+            assert_eq!(
+                vec![
+                    BigInt::from_string("18446744073709551614"),
+                    BigInt::from_string("18446744073709551615"),
+                    BigInt::from_string("18446744073709551616"),
+                    BigInt::from_string("18446744073709551617"),
+                ],
+                range(&start, &end).collect::<Vec<_>>()
+            );
+            assert_eq!(5, range_inclusive(&start, &end).count());
+            assert_eq!(0, range(&end, &start).count());
+            assert_eq!(0, range(&start, &start).count());
+            assert_eq!(1, range_inclusive(&start, &start).count());
+            assert_eq!(
+                vec![
+                    BigInt::from_string("18446744073709551614"),
+                    BigInt::from_string("18446744073709551617"),
+                ],
+                range_inclusive(&start, &end)
+                    .with_stride(BigInt::from(3u32))
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                3,
+                range(&BigInt::zero(), &BigInt::from(10u64))
+                    .with_stride(BigInt::from(4u32))
+                    .count()
+            );
+        }
+
+        #[test]
+        fn test_step() {
+            let start = BigInt::from_string("4294967294");
+            let end = BigInt::from_string("4294967298");
+            let mut values = Vec::new();
+            for i in start.clone()..end.clone() {
+                values.push(i);
+            }
+            assert_eq!(
+                vec![
+                    BigInt::from_string("4294967294"),
+                    BigInt::from_string("4294967295"),
+                    BigInt::from_string("4294967296"),
+                    BigInt::from_string("4294967297"),
+                ],
+                values
+            );
+            assert_eq!(5, (start.clone()..=end.clone()).count());
+            assert_eq!(
+                Some(BigInt::from_string("4294967297")),
+                (start.clone()..end.clone()).next_back()
+            );
+            assert_eq!((4, Some(4)), BigInt::steps_between(&start, &end));
+            assert_eq!((0, None), BigInt::steps_between(&end, &start));
+            assert_eq!(
+                Some(BigInt::from_string("4294967295")),
+                BigInt::backward_checked(end.clone(), 3)
+            );
+            assert_eq!(None, BigInt::backward_checked(BigInt::two(), 3));
+        }
+
+        #[test]
+        fn test_sub() {
+            assert_eq!(
+                BigInt::from_string("9999999999999999999999999999999999999999999999999"),
+                BigInt::from_string("10111111111111111111111111111111111123432342342110")
+                    .sub(&BigInt::from_string("111111111111111111111111111111111123432342342111"))
+            );
+            assert_eq!(
+                BigInt::from(u32::MAX),
+                BigInt::from_string("4294967296").sub(&BigInt::one())
+            );
+            assert_eq!(BigInt::zero(), BigInt::ten().sub(&BigInt::ten()));
+            assert_eq!(Some(BigInt::from(8u32)), BigInt::ten().checked_sub(&BigInt::two()));
+            assert_eq!(None, BigInt::two().checked_sub(&BigInt::ten()));
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_sub_underflow() {
+            BigInt::two().sub(&BigInt::ten());
+        }
+
+        #[test]
+        fn test_div_rem() {
+            assert_eq!(
+                (BigInt::from(33u32), BigInt::from(1u32)),
+                BigInt::from(100u32).div_rem(&BigInt::from(3u32))
+            );
+            assert_eq!(
+                (BigInt::zero(), BigInt::from(3u32)),
+                BigInt::from(3u32).div_rem(&BigInt::from(100u32))
+            );
+            let b1 = BigInt::from_string("9999999999999999999999999999999999999999999999999");
+            let b2 = BigInt::from_string("111111111111111111111111111111111123432342342111");
+            let p = b1.mul(&b2);
+            assert_eq!((b1.clone(), BigInt::zero()), p.div_rem(&b2));
+            assert_eq!((b2.clone(), BigInt::zero()), p.div_rem(&b1));
+            let n = p.add(&BigInt::from(12345u32));
+            assert_eq!((b1.clone(), BigInt::from(12345u32)), n.div_rem(&b2));
+            assert_eq!(BigInt::from(12345u32), n.rem(&b1));
+            assert_eq!(b2, n.div(&b1));
+
+            // The first quotient estimate is one too large here, so the divisor is added back.
+            assert_eq!(
+                (
+                    BigInt::from(u32::MAX),
+                    BigInt::from_string("18446744069414584321")
+                ),
+                BigInt::from_limbs_le(&[0, 0, 0, 1]).div_rem(&BigInt::from_limbs_le(&[1, 0, 1]))
+            );
+
+            // Check q * b + r == a and r < b over pseudo-random operands, including limbs
+            // close to the base which exercise the quotient correction steps.
+            let mut seed: u64 = 42;
+            let mut next_limb = || {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                match seed >> 62 {
+                    0 => u32::MAX,
+                    1 => 0x8000_0000,
+                    _ => (seed >> 32) as u32,
+                }
+            };
+            for a_len in 1..8 {
+                for b_len in 1..=a_len {
+                    let a: BigInt = (0..a_len).map(|_| next_limb()).collect();
+                    let b: BigInt = (0..b_len).map(|_| next_limb()).collect();
+                    if b.is_zero() {
+                        continue;
+                    }
+                    let (q, r) = a.div_rem(&b);
+                    assert!(r < b);
+                    assert_eq!(a, q.mul(&b).add(&r));
+                }
+            }
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_div_by_zero() {
+            BigInt::ten().div_rem(&BigInt::zero());
+        }
+
+        #[test]
+        fn test_iter_sum() {
+            let v = vec![
+                BigInt::from_string("9999999999999999999999999999999999999999999999999"),
+                BigInt::from_string("111111111111111111111111111111111123432342342111"),
+                BigInt { data: vec![(BigInt::BASE - 1) as u32, 0, 0] },
+                BigInt::zero(),
+            ];
+            assert_eq!(
+                BigInt::from_string("10111111111111111111111111111111111123436637309405"),
+                v.iter().sum::<BigInt>()
+            );
+            assert_eq!(
+                BigInt::from_string("10111111111111111111111111111111111123436637309405"),
+                v.into_iter().sum::<BigInt>()
+            );
+            assert_eq!(BigInt::zero(), Vec::<BigInt>::new().into_iter().sum::<BigInt>());
+            assert_eq!(BigInt::from(5050u32), (1..101u32).map(BigInt::from).sum::<BigInt>());
+        }
+
+        #[test]
+        fn test_iter_product() {
+            let v = vec![
+                BigInt::from_string("9999999999999999999999999999999999999999999999999"),
+                BigInt::from_string("111111111111111111111111111111111123432342342111"),
+            ];
+            assert_eq!(BigInt::from_string("1111111111111111111111111111111111234323423421109888888888888888888888888888888888876567657657889"),
+                       v.iter().product::<BigInt>());
+            assert_eq!(BigInt::from_string("1111111111111111111111111111111111234323423421109888888888888888888888888888888888876567657657889"),
+                       v.into_iter().product::<BigInt>());
+            assert_eq!(BigInt::from(1u32), Vec::<BigInt>::new().into_iter().product::<BigInt>());
+            assert_eq!(
+                BigInt::from_string("2432902008176640000"),
+                (1..21u32).map(BigInt::from).product::<BigInt>()
+            );
+            let factors = [BigInt::from(u64::MAX), BigInt::from(3u32), BigInt::from(u64::MAX)];
+            assert_eq!(
+                BigInt::from(u64::MAX).mul(&BigInt::from(3u32)).mul(&BigInt::from(u64::MAX)),
+                factors.iter().product::<BigInt>()
+            );
+            assert_eq!(
+                BigInt::zero(),
+                [BigInt::from(u64::MAX), BigInt::zero(), BigInt::from(7u32)].iter().product::<BigInt>()
+            );
+        }
+
+        #[test]
+        fn test_const_bigint() {
+            static POWERS_OF_TEN: [ConstBigInt<2>; 3] = [
+                ConstBigInt::from_limbs_le([1, 0]),
+                ConstBigInt::from_limbs_le([10, 0]),
+                ConstBigInt::from_limbs_le([1410065408, 2]),
+            ];
+            assert_eq!(BigInt::one(), POWERS_OF_TEN[0].to_bigint());
+            assert_eq!(BigInt::ten(), BigInt::from(&POWERS_OF_TEN[1]));
+            assert_eq!(BigInt::ten().pow(10), BigInt::from(POWERS_OF_TEN[2]));
+            assert_eq!(&[10], POWERS_OF_TEN[1].as_limbs_le());
+            assert!(ConstBigInt::from_limbs_le([0, 0]).as_limbs_le().is_empty());
+
+            const BIG: ConstBigInt<4> = ConstBigInt::from_literal("42949672963434342343243324343232890890");
+            assert_eq!(
+                &[3461744650, 2330743505, 1228788904, 542101086],
+                BIG.as_limbs_le()
+            );
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_const_bigint_overflow() {
+            ConstBigInt::<1>::from_literal("4294967296");
+        }
+
+        #[test]
+        fn test_shared() {
+            let b = BigInt::from_string("42949672963434342343243324343232890890");
+            let shared = SharedBigInt::new(b.clone());
+            let mut copy = shared.clone();
+            assert!(shared.ptr_eq(&copy));
+            assert_eq!(b, *copy);
+            assert_eq!(b.add(&BigInt::one()), copy.add(&BigInt::one()));
+
+            copy.make_mut().set_bit(0, true);
+            assert!(!shared.ptr_eq(&copy));
+            assert_eq!(b, *shared);
+            assert_eq!(BigInt::from_string("42949672963434342343243324343232890891"), *copy);
+
+            let other = shared.clone();
+            assert_eq!(b, other.into_inner());
+            assert_eq!(b, shared.into_inner());
+            assert_eq!(SharedBigInt::from(BigInt::zero()), SharedBigInt::default());
+        }
+
+        #[test]
+        fn test_decimal_builder() {
+            assert_eq!(BigInt::zero(), DecimalBuilder::new().finish());
+
+            let digits = "123456789012345678901234567890123456789";
+            for split in [1, 5, 9, 10, 17].iter() {
+                let mut builder = DecimalBuilder::new();
+                for chunk in digits.as_bytes().chunks(*split) {
+                    builder.push_str(std::str::from_utf8(chunk).unwrap());
+                }
+                assert_eq!(BigInt::from_string(digits), builder.finish());
+            }
+
+            let mut builder = DecimalBuilder::new();
+            builder.push_str("000");
+            builder.push_digit(4);
+            builder.push_digit(2);
+            builder.push_str("");
+            assert_eq!(BigInt::from(42u32), builder.finish());
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_decimal_builder_invalid() {
+            DecimalBuilder::new().push_str("12a");
+        }
+
+        #[test]
+        fn test_accumulator() {
+            assert_eq!(BigInt::zero(), BigIntAccumulator::new().finish());
+
+            let values: Vec<BigInt> = (0..100u32)
+                .map(|i| BigInt::from(u32::MAX).pow(i % 7).add(&BigInt::from(i)))
+                .collect();
+            let mut accumulator = BigIntAccumulator::new();
+            let mut expected = BigInt::zero();
+            for v in values.iter() {
+                accumulator.add(v);
+                expected = expected.add(v);
+            }
+            accumulator.add_u64(u64::MAX);
+            expected = expected.add(&BigInt::from(u64::MAX));
+            assert_eq!(expected, accumulator.finish());
+
+            // Every limb at its maximum, checking the running total along the way.
+            let all_ones = BigInt::from_limbs_le(&[u32::MAX; 5]);
+            let mut accumulator = BigIntAccumulator::new();
+            let mut expected = BigInt::zero();
+            for i in 0..1000u32 {
+                accumulator.add(&all_ones);
+                accumulator.add_u64(u64::MAX);
+                expected = expected.add(&all_ones).add(&BigInt::from(u64::MAX));
+                if i % 100 == 0 {
+                    assert_eq!(expected, accumulator.clone().finish());
+                }
+            }
+            assert_eq!(expected, accumulator.finish());
+        }
+
+        #[bench]
+        fn bench_accumulator(b: &mut Bencher) {
+            let values: Vec<BigInt> = (0..1000u32)
+                .map(|i| BigInt::from(u32::MAX).pow(4).add(&BigInt::from(i)))
+                .collect();
+            b.iter(|| {
+                let mut accumulator = BigIntAccumulator::new();
+                for v in values.iter() {
+                    accumulator.add(v);
+                }
+                accumulator.finish()
+            })
+        }
+
+        #[bench]
+        fn bench_repeated_add(b: &mut Bencher) {
+            let values: Vec<BigInt> = (0..1000u32)
+                .map(|i| BigInt::from(u32::MAX).pow(4).add(&BigInt::from(i)))
+                .collect();
+            b.iter(|| values.iter().fold(BigInt::zero(), |acc, v| acc.add(v)))
+        }
+
+        #[test]
+        fn test_display() {
+            assert_eq!("0", BigInt::zero().to_string());
+            assert_eq!("0", BigInt { data: vec![0, 0] }.to_string());
+            assert_eq!("4294967296", BigInt::from_string("4294967296").to_string());
+            assert_eq!("1000000000", BigInt::from(1000000000u32).to_string());
+            assert_eq!(
+                "42949672963434342343243324343232890890",
+                BigInt::from_string("42949672963434342343243324343232890890").to_string()
+            );
+            assert_eq!(
+                "1000000000000000000000000000001",
+                BigInt::ten().pow(30).add(&BigInt::one()).to_string()
+            );
+            assert_eq!("  1234", format!("{:>6}", BigInt::from(1234u32)));
+            assert_eq!("001234", format!("{:06}", BigInt::from(1234u32)));
+        }
+
+        #[test]
+        fn test_hex() {
+            assert_eq!("0", format!("{:x}", BigInt::zero()));
+            assert_eq!("ff", format!("{:x}", BigInt::from(255u32)));
+            assert_eq!("100000000", format!("{:x}", BigInt::from_string("4294967296")));
+            assert_eq!(
+                "1B3A0C5FC0393439D05CC1EF5C9D2A3D",
+                format!("{:X}", BigInt::from(0x1B3A0C5FC0393439D05CC1EF5C9D2A3Du128))
+            );
+            assert_eq!("0x00ff", format!("{:#06x}", BigInt::from(255u32)));
+        }
+
+        #[test]
+        fn test_slice() {
+            let buffer = [7, 937946958, 287445, 0, 9];
+            let slice = BigIntSlice::new(&buffer[1..4]);
+            let b = BigInt::from_string("1234567812345678");
+            assert_eq!(&[937946958, 287445], slice.as_limbs_le());
+            assert!(slice == b);
+            assert!(b == slice);
+            assert_eq!(b.as_slice(), slice);
+            assert_eq!(BigIntSlice::from(&b), slice);
+            assert_eq!(b, slice.to_bigint());
+            assert_eq!("1234567812345678", slice.to_string());
+            assert!(BigIntSlice::new(&buffer[..1]) < slice);
+            assert!(BigIntSlice::new(&buffer[3..]) < slice);
+            assert!(BigIntSlice::new(&buffer[3..4]).is_zero());
+            assert_eq!(b.add(&b), slice.add(slice));
+            assert_eq!(b.mul(&BigInt::from(7u32)), slice.mul(BigIntSlice::new(&buffer[..1])));
+        }
+
+        #[test]
+        fn test_bigint_macro() {
+            assert_eq!(BigInt::zero(), bigint!(0));
+            assert_eq!(BigInt::from(42u32), bigint!(42));
+            assert_eq!(BigInt::from(1000000u32), bigint!(1_000_000));
+            assert_eq!(
+                BigInt::from_string("42949672963434342343243324343232890890"),
+                bigint!(42949672963434342343243324343232890890)
+            );
+            assert_eq!(
+                BigInt::from_string("1111111111111111111111111111111111234323423421109888888888888888888888888888888888876567657657889"),
+                bigint!(1111111111111111111111111111111111234323423421109888888888888888888888888888888888876567657657889)
+            );
+            assert_eq!(
+                BigInt::from_string("683598743919434280434619734254544588"),
+                bigint!("683598743919434280434619734254544588")
+            );
+        }
+
+        #[test]
+        fn test_shl() {
+            assert_eq!(BigInt { data: vec![8] }, &BigInt { data: vec![1] } << 3);
+            assert_eq!(
+                BigInt { data: vec![0, 0, 1] },
+                BigInt { data: vec![1] } << 64
+            );
+            assert_eq!(
+                BigInt {
+                    data: vec![4294967294, 1]
+                },
+                BigInt {
+                    data: vec![(BigInt::BASE - 1) as u32]
+                } << 1
+            );
+            assert_eq!(BigInt::zero(), BigInt::zero() << 100);
+            assert_eq!(
+                BigInt::from_string("1267650600228229401496703205376"),
+                BigInt::from_string("1") << 100
+            );
+        }
+
+        #[test]
+        fn test_shr() {
+            assert_eq!(BigInt { data: vec![1] }, &BigInt { data: vec![8] } >> 3);
+            assert_eq!(
+                BigInt { data: vec![1] },
+                BigInt { data: vec![0, 0, 1] } >> 64
+            );
+            assert_eq!(
+                BigInt {
+                    data: vec![(BigInt::BASE - 1) as u32]
+                },
+                BigInt {
+                    data: vec![4294967294, 1]
+                } >> 1
+            );
+            assert_eq!(BigInt::zero(), BigInt { data: vec![1, 1] } >> 64);
+            assert_eq!(
+                BigInt::from_string("1"),
+                BigInt::from_string("1267650600228229401496703205376") >> 100
+            );
+        }
+
+        #[test]
+        fn test_bitwise() {
+            let b1 = BigInt {
+                data: vec![0b1100, 0b1010, 7],
+            };
+            let b2 = BigInt {
+                data: vec![0b1010, 0b0110],
+            };
+            assert_eq!(BigInt { data: vec![0b1000, 0b0010] }, &b1 & &b2);
+            assert_eq!(BigInt { data: vec![0b1110, 0b1110, 7] }, &b1 | &b2);
+            assert_eq!(BigInt { data: vec![0b0110, 0b1100, 7] }, &b1 ^ &b2);
+            assert_eq!(BigInt::zero(), b1.clone() ^ b1.clone());
+            assert_eq!(BigInt::zero(), b1 & BigInt::zero());
+        }
+    }
+
+}
+
+pub mod easy {
+    //! Short, non-optimized implementation of BigInt.
+
+    use std::cmp::Ordering;
+    use std::fmt;
+    use std::ops::{Add, Mul, Sub};
+
+    /// Binary digits, least significant first, packed eight to a byte. Zeros above the
+    /// most significant digit are allowed, and ignored by the comparisons.
+    #[derive(Debug, Clone)]
+    pub struct BigInt {
+        data: Vec<u8>,
+        /// Number of digits stored in `data`.
+        len: usize,
+        /// Number of zero digits below those of `data`, so that doubling is O(1).
+        shift: usize,
+    }
+
+    impl BigInt {
+        pub fn zero() -> BigInt {
+            BigInt {
+                data: Vec::new(),
+                len: 0,
+                shift: 0,
+            }
+        }
+
+        /// Parse a string of binary digits, least significant first.
+        ///
+        /// Panics on any character other than `0` and `1`.
+        pub fn from_binary_string(s: &str) -> BigInt {
+            BigInt::try_from_binary_string(s).unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        /// Like `from_binary_string`, but an error on any character other than `0` and `1`.
+        pub fn try_from_binary_string(s: &str) -> Result<BigInt, ParseBigIntError> {
+            let digits = s
+                .bytes()
+                .map(|c| match c {
+                    b'0' | b'1' => Ok(c - b'0'),
+                    _ => Err(ParseBigIntError {
+                        reason: "invalid binary digit",
+                    }),
+                })
+                .collect::<Result<Vec<u8>, ParseBigIntError>>()?;
+            Ok(BigInt::from_digits(digits))
+        }
+
+        /// Parse a string of hexadecimal digits of either case, most significant first like
+        /// the output of `{:x}` on `optimized_memory::BigInt`, and unlike
+        /// `from_binary_string`.
+        ///
+        /// Panics on any other character.
+        pub fn from_hex_string(s: &str) -> BigInt {
+            BigInt::try_from_hex_string(s).unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        /// Like `from_hex_string`, but an error on any character that is not a hexadecimal
+        /// digit.
+        pub fn try_from_hex_string(s: &str) -> Result<BigInt, ParseBigIntError> {
+            let mut digits = Vec::with_capacity(4 * s.len());
+            for c in s.chars().rev() {
+                let nibble = c.to_digit(16).ok_or(ParseBigIntError {
+                    reason: "invalid hex digit",
+                })?;
+                digits.extend((0..4).map(|i| (nibble >> i) as u8 & 1));
+            }
+            Ok(BigInt::from_digits(digits))
+        }
+
+        /// The value in lowercase hexadecimal, most significant digit first, or `"0"` for
+        /// zero. Each hexadecimal digit groups four binary digits.
+        pub fn to_hex_string(&self) -> String {
+            let len = self.significant_len();
+            if len == 0 {
+                return "0".to_string();
+            }
+            (0..len.div_ceil(4))
+                .rev()
+                .map(|group| {
+                    let nibble = (0..4).fold(0, |n, i| n | (self.get(4 * group + i) as u32) << i);
+                    std::char::from_digit(nibble, 16).unwrap()
+                })
+                .collect()
+        }
+
+        /// Build a BigInt from bytes, most significant first, like
+        /// `optimized_memory::BigInt::from_bytes_be`. The packed digits of the result are
+        /// the bytes in reverse order.
+        pub fn from_bytes_be(bytes: &[u8]) -> BigInt {
+            BigInt {
+                data: bytes.iter().rev().copied().collect(),
+                len: 8 * bytes.len(),
+                shift: 0,
+            }
+        }
+
+        /// The bytes of the value, most significant first, without leading zeros, or
+        /// `[0]` for zero.
+        pub fn to_bytes_be(&self) -> Vec<u8> {
+            let len = self.significant_len();
+            if len == 0 {
+                return vec![0];
+            }
+            (0..len.div_ceil(8))
+                .rev()
+                .map(|byte| (0..8).fold(0, |b, i| b | self.get(8 * byte + i) << i))
+                .collect()
+        }
+
+        /// Parse a string of decimal digits, by multiplying by ten and adding one digit at
+        /// a time. Panics on any other character.
+        pub fn from_decimal_string(s: &str) -> BigInt {
+            let ten = BigInt::from_binary_string("0101");
+            let mut result = BigInt::zero();
+            for c in s.chars() {
+                let digit = c.to_digit(10).expect("invalid decimal digit");
+                let digit = BigInt::from_digits((0..4).map(|i| (digit >> i) as u8 & 1));
+                result = result.mul(&ten).add(&digit);
+            }
+            result
+        }
+
+        /// The digits up to the most significant one, least significant first like the
+        /// input of `from_binary_string`, or `"0"` for zero.
+        pub fn to_binary_string(&self) -> String {
+            let len = self.significant_len();
+            if len == 0 {
+                return "0".to_string();
+            }
+            (0..len).map(|i| (b'0' + self.get(i)) as char).collect()
+        }
+
+        pub fn add(&self, other: &BigInt) -> BigInt {
+            let mut result = BigInt::zero();
+            let largest = std::cmp::max(self.digit_len(), other.digit_len());
+            let mut carry = 0;
+            for i in 0..largest {
+                match (self.get(i), other.get(i)) {
+                    (0, 0) => {
+                        result.push(carry);
+                        carry = 0;
+                    }
+                    (1, 0) | (0, 1) => result.push((1 + carry) % 2),
+                    (1, 1) => {
+                        result.push(carry);
+                        carry = 1;
+                    }
+                    _ => panic!("Something is wrong"),
+                }
+            }
+
+            if carry == 1 {
+                result.push(1);
+            }
+
+            result
+        }
+
+        /// Panics if `other` is larger than `self`.
+        pub fn sub(&self, other: &BigInt) -> BigInt {
+            self.checked_sub(other)
+                .expect("attempt to subtract a larger BigInt")
+        }
+
+        /// Subtraction with a borrow, one digit at a time, or `None` if `other` is larger
+        /// than `self`.
+        pub fn checked_sub(&self, other: &BigInt) -> Option<BigInt> {
+            let largest = std::cmp::max(self.significant_len(), other.significant_len());
+            let mut digits = Vec::with_capacity(largest);
+            let mut borrow = 0;
+            for i in 0..largest {
+                let difference = self.get(i) as i8 - other.get(i) as i8 - borrow;
+                digits.push(difference.rem_euclid(2) as u8);
+                borrow = (difference < 0) as i8;
+            }
+
+            if borrow == 1 {
+                return None;
+            }
+
+            let len = digits.iter().rposition(|&d| d == 1).map_or(0, |i| i + 1);
+            Some(BigInt::from_digits(digits.into_iter().take(len)))
+        }
+
+        pub fn mul(&self, other: &BigInt) -> BigInt {
+            let mut result = BigInt::zero();
+            let mut temp = BigInt {
+                data: self.data.clone(),
+                ..*self
+            };
+            for i in 0..other.digit_len() {
+                if other.get(i) == 1 {
+                    result = result.add(&temp);
+                }
+                temp.times_two();
+            }
+            result
+        }
+
+        /// Quotient and remainder of the division by `other`, by shifting the digits of
+        /// `self` into the remainder from the top and subtracting `other` whenever it fits.
+        ///
+        /// Panics if `other` is zero.
+        pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+            assert!(other.significant_len() > 0, "attempt to divide by zero");
+            let len = self.significant_len();
+            let mut quotient = vec![0; len];
+            let mut remainder = BigInt::zero();
+            for i in (0..len).rev() {
+                remainder.times_two();
+                if self.get(i) == 1 {
+                    remainder = remainder.add(&BigInt::from_digits(Some(1)));
+                }
+                if remainder >= *other {
+                    remainder = remainder.sub(other);
+                    quotient[i] = 1;
+                }
+            }
+            (BigInt::from_digits(quotient), remainder)
+        }
+
+        /// Long division by a small nonzero divisor, one bit at a time from the top.
+        fn div_rem_small(&self, d: u8) -> (BigInt, u8) {
+            let len = self.significant_len();
+            let mut quotient = vec![0; len];
+            let mut remainder = 0;
+            for i in (0..len).rev() {
+                remainder = remainder * 2 + self.get(i);
+                if remainder >= d {
+                    quotient[i] = 1;
+                    remainder -= d;
+                }
+            }
+            (BigInt::from_digits(quotient), remainder)
+        }
+
+        /// `2^k`.
+        fn power_of_two(k: usize) -> BigInt {
+            let mut result = BigInt::from_digits(Some(1));
+            result.shift = k;
+            result
+        }
+
+        /// The value with the given digits, least significant first.
+        fn from_digits(digits: impl IntoIterator<Item = u8>) -> BigInt {
+            let mut result = BigInt::zero();
+            for d in digits {
+                result.push(d);
+            }
+            result
+        }
+
+        /// Append a digit above the others.
+        fn push(&mut self, d: u8) {
+            if self.len.is_multiple_of(8) {
+                self.data.push(0);
+            }
+            self.data[self.len / 8] |= d << (self.len % 8);
+            self.len += 1;
+        }
+
+        /// Number of digits, including the zeros above the most significant one.
+        fn digit_len(&self) -> usize {
+            self.shift + self.len
+        }
+
+        /// Number of digits up to the most significant one.
+        fn significant_len(&self) -> usize {
+            self.data.iter().rposition(|&byte| byte != 0).map_or(0, |i| {
+                self.shift + 8 * i + 8 - self.data[i].leading_zeros() as usize
+            })
+        }
+
+        fn times_two(&mut self) {
+            self.shift += 1;
+        }
+
+        fn get(&self, i: usize) -> u8 {
+            if i >= self.shift && i < self.digit_len() {
+                let i = i - self.shift;
+                self.data[i / 8] >> (i % 8) & 1
+            } else {
+                0
+            }
+        }
+    }
+
+    impl crate::unsigned::BigUnsigned for BigInt {
+        fn zero() -> BigInt {
+            BigInt::zero()
+        }
+
+        fn from_decimal(s: &str) -> BigInt {
+            BigInt::from_decimal_string(s)
+        }
+
+        fn add(&self, other: &BigInt) -> BigInt {
+            BigInt::add(self, other)
+        }
+
+        fn mul(&self, other: &BigInt) -> BigInt {
+            BigInt::mul(self, other)
+        }
+
+        fn cmp(&self, other: &BigInt) -> Ordering {
+            Ord::cmp(self, other)
+        }
+
+        fn to_string(&self) -> String {
+            ToString::to_string(self)
+        }
+    }
+
+    forward_ref_binop!(Add, add);
+    // Panics if the right operand is larger, like the subtraction of primitive integers.
+    forward_ref_binop!(Sub, sub);
+    forward_ref_binop!(Mul, mul);
+
+    impl PartialEq for BigInt {
+        fn eq(&self, other: &BigInt) -> bool {
+            self.cmp(other) == Ordering::Equal
+        }
+    }
+
+    impl Eq for BigInt {}
+
+    impl PartialOrd for BigInt {
+        fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for BigInt {
+        fn cmp(&self, other: &BigInt) -> Ordering {
+            let len = self.significant_len();
+            len.cmp(&other.significant_len()).then_with(|| {
+                let digits = (0..len).rev().map(|i| self.get(i));
+                digits.cmp((0..len).rev().map(|i| other.get(i)))
+            })
+        }
+    }
+
+    impl From<&BigInt> for crate::optimized_memory::BigInt {
+        /// Packs the digits 32 at a time into limbs.
+        fn from(value: &BigInt) -> crate::optimized_memory::BigInt {
+            let digits: Vec<u8> = (0..value.significant_len()).map(|i| value.get(i)).collect();
+            let limbs: Vec<u32> = digits
+                .chunks(32)
+                .map(|chunk| chunk.iter().rev().fold(0, |limb, &d| limb << 1 | d as u32))
+                .collect();
+            crate::optimized_memory::BigInt::from_limbs_le(&limbs)
+        }
+    }
+
+    impl From<&crate::optimized_memory::BigInt> for BigInt {
+        /// Unpacks each limb into 32 digits, dropping the zeros above the top digit.
+        fn from(value: &crate::optimized_memory::BigInt) -> BigInt {
+            let digits: Vec<u8> = value
+                .as_limbs_le()
+                .iter()
+                .flat_map(|&limb| (0..32).map(move |i| (limb >> i) as u8 & 1))
+                .collect();
+            let len = digits.iter().rposition(|&d| d == 1).map_or(0, |i| i + 1);
+            BigInt::from_digits(digits.into_iter().take(len))
+        }
+    }
+
+    impl fmt::Display for BigInt {
+        /// The value in decimal, by repeated division by ten.
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let mut digits = Vec::new();
+            let mut rest = BigInt {
+                data: self.data.clone(),
+                ..*self
+            };
+            while rest.significant_len() > 0 {
+                let (quotient, remainder) = rest.div_rem_small(10);
+                digits.push((b'0' + remainder) as char);
+                rest = quotient;
+            }
+            if digits.is_empty() {
+                digits.push('0');
+            }
+            let digits: String = digits.iter().rev().collect();
+            f.pad_integral(true, "", &digits)
+        }
+    }
+
+    /// A signed integer, stored as a sign and a `BigInt` magnitude like `signed::BigInt`.
+    /// Zero is never negative.
+    ///
+    /// `to_twos_complement` and `from_twos_complement` convert from and to the bit pattern
+    /// of a fixed-width machine integer.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct SignedBigInt {
+        negative: bool,
+        magnitude: BigInt,
+    }
+
+    impl SignedBigInt {
+        /// Build a value from its sign and magnitude. The sign of zero is ignored.
+        pub fn new(negative: bool, magnitude: BigInt) -> SignedBigInt {
+            SignedBigInt {
+                negative: negative && magnitude.significant_len() > 0,
+                magnitude,
+            }
+        }
+
+        /// Parse a string of decimal digits with an optional leading `-` or `+`. Panics on
+        /// any other character.
+        pub fn from_decimal_string(s: &str) -> SignedBigInt {
+            match s.strip_prefix('-') {
+                Some(digits) => SignedBigInt::new(true, BigInt::from_decimal_string(digits)),
+                None => SignedBigInt::new(
+                    false,
+                    BigInt::from_decimal_string(s.strip_prefix('+').unwrap_or(s)),
+                ),
+            }
+        }
+
+        pub fn magnitude(&self) -> &BigInt {
+            &self.magnitude
+        }
+
+        pub fn is_negative(&self) -> bool {
+            self.negative
+        }
+
+        pub fn neg(&self) -> SignedBigInt {
+            SignedBigInt::new(!self.negative, self.magnitude.clone())
+        }
+
+        /// The sum, by adding the magnitudes for equal signs and otherwise subtracting the
+        /// smaller magnitude from the larger.
+        pub fn add(&self, other: &SignedBigInt) -> SignedBigInt {
+            if self.negative == other.negative {
+                return SignedBigInt::new(self.negative, self.magnitude.add(&other.magnitude));
+            }
+            if self.magnitude < other.magnitude {
+                SignedBigInt::new(other.negative, other.magnitude.sub(&self.magnitude))
+            } else {
+                SignedBigInt::new(self.negative, self.magnitude.sub(&other.magnitude))
             }
         }
-    }
 
-    impl PartialEq for BigInt {
-        fn eq(&self, other: &Self) -> bool {
-            let largest = std::cmp::max(self.data.len(), other.data.len());
-            for i in 0..largest {
-                if self.get(i) != other.get(i) {
-                    return false;
-                }
-            }
-            true
+        pub fn sub(&self, other: &SignedBigInt) -> SignedBigInt {
+            self.add(&other.neg())
         }
-    }
 
-    impl Eq for BigInt {}
+        pub fn mul(&self, other: &SignedBigInt) -> SignedBigInt {
+            SignedBigInt::new(
+                self.negative != other.negative,
+                self.magnitude.mul(&other.magnitude),
+            )
+        }
 
-    pub fn sum(b1: &BigInt, b2: &BigInt) -> BigInt {
-        let largest = std::cmp::max(b1.data.len(), b2.data.len());
-        let mut result = BigInt{data: Vec::with_capacity(largest)};
-        let mut carry = 0;
-        for i in 0..largest {
-            let digit_sum = b1.get(i) as u64 + b2.get(i) as u64 + carry;
-            if digit_sum >= BigInt::BASE {
-                result.data.push((digit_sum - BigInt::BASE) as u32);
-                carry = 1;
+        /// The `width`-bit two's complement pattern of the value: the value itself if it is
+        /// non-negative, otherwise `2^width` minus its magnitude.
+        ///
+        /// Panics if the value is outside `[-2^(width-1), 2^(width-1))`.
+        pub fn to_twos_complement(&self, width: usize) -> BigInt {
+            assert!(width > 0, "width must be positive");
+            let half = BigInt::power_of_two(width - 1);
+            if self.negative {
+                assert!(self.magnitude <= half, "value does not fit in {} bits", width);
+                BigInt::power_of_two(width).sub(&self.magnitude)
             } else {
-                result.data.push(digit_sum as u32);
-                carry = 0;
+                assert!(self.magnitude < half, "value does not fit in {} bits", width);
+                self.magnitude.clone()
             }
         }
 
-        if carry == 1 {
-            result.data.push(1);
+        /// The value of a `width`-bit two's complement pattern, negative when digit
+        /// `width - 1` is set.
+        ///
+        /// Panics if `bits` has more than `width` digits.
+        pub fn from_twos_complement(bits: &BigInt, width: usize) -> SignedBigInt {
+            assert!(width > 0, "width must be positive");
+            assert!(bits.significant_len() <= width, "pattern wider than {} bits", width);
+            if bits.get(width - 1) == 1 {
+                SignedBigInt::new(true, BigInt::power_of_two(width).sub(bits))
+            } else {
+                SignedBigInt::new(false, bits.clone())
+            }
         }
-
-        result
     }
 
-    pub fn product(b1: &BigInt, b2: &BigInt) -> BigInt {
-        let mut result = BigInt{data: Vec::with_capacity(b1.data.len())};
-
-        for (i, d) in b2.data.iter().enumerate() {
-            if *d > 0 {
-                let mut temp = BigInt { data: vec![0; i] };
-                temp.data.extend(atomic_product(&b1, *d).data);
-                result = sum(&result, &temp);
-            }
+    impl fmt::Display for SignedBigInt {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.pad_integral(!self.negative, "", &self.magnitude.to_string())
         }
+    }
 
-        result
+    /// The error returned when a string does not hold a valid number.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ParseBigIntError {
+        reason: &'static str,
     }
 
-    fn atomic_product(b1: &BigInt, d: u32) -> BigInt {
-        let mut result = BigInt{data: Vec::with_capacity(b1.data.len())};
-        let mut carry = 0;
-        for d1 in &b1.data {
-            let digit_product = (*d1 as u64 * d as u64) + carry;
-            result.data.push((digit_product % BigInt::BASE) as u32);
-            carry = digit_product / BigInt::BASE;
+    impl fmt::Display for ParseBigIntError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "cannot parse BigInt: {}", self.reason)
         }
+    }
 
-        if carry > 0 {
-            result.data.push(carry as u32);
-        }
+    impl std::error::Error for ParseBigIntError {}
 
-        result
+    #[deprecated(note = "use `BigInt::add` instead")]
+    pub fn sum(b1: &BigInt, b2: &BigInt) -> BigInt {
+        b1.add(b2)
     }
 
-    /// Helper function for `BigInt::from_string`. The input must be a numeric string.
-    ///
-    /// It returns the string split by chunks with size `chunk_size`, except the last
-    /// chunk which may be shorter.
-    fn split_string(s: &str, chunk_size: usize) -> Vec<u64> {
-        let mut result = Vec::new();
-        let mut i = 0;
-        while i < s.len() {
-            let right = std::cmp::min(i + chunk_size, s.len());
-            result.push(s[i..right].parse().unwrap());
-            i = i + chunk_size;
-        }
-        result
+    #[deprecated(note = "use `BigInt::mul` instead")]
+    pub fn product(b1: &BigInt, b2: &BigInt) -> BigInt {
+        b1.mul(b2)
     }
 
     #[cfg(test)]
@@ -163,262 +4504,332 @@ pub mod optimized_memory {
         use super::*;
         use test::Bencher;
 
-        #[bench]
-        fn bench_sum_short(b: &mut Bencher) {
-            let b1 = BigInt::from_string("34324");
-            let b2 = BigInt::from_string("11");
-            b.iter(|| sum(&b1, &b2))
+        fn digits(d: &[u8]) -> BigInt {
+            BigInt::from_digits(d.iter().copied())
         }
 
         #[bench]
-        fn bench_sum_long(b: &mut Bencher) {
-            let b1 = BigInt::from_string("9999999999999999999999999999999999999999999999999");
-            let b2 = BigInt::from_string("111111111111111111111111111111111123432342342111");
-            b.iter(|| sum(&b1, &b2))
+        fn bench_sum_short(b: &mut Bencher) {
+            let b1 = BigInt::from_binary_string("1000011000010100");
+            let b2 = BigInt::from_binary_string("1011");
+            b.iter(|| b1.add(&b2))
         }
 
         #[bench]
         fn bench_product_short(b: &mut Bencher) {
-            let b1 = BigInt::from_string("34324");
-            let b2 = BigInt::from_string("11");
-            b.iter(|| product(&b1, &b2))
+            let b1 = BigInt::from_binary_string("1000011000010100");
+            let b2 = BigInt::from_binary_string("1011");
+            b.iter(|| b1.mul(&b2))
         }
 
-        #[bench]
-        fn bench_product_long(b: &mut Bencher) {
-            let b1 = BigInt::from_string("9999999999999999999999999999999999999999999999999");
-            let b2 = BigInt::from_string("111111111111111111111111111111111123432342342111");
-            b.iter(|| product(&b1, &b2))
+        #[test]
+        fn test_from_binary_string() {
+            assert_eq!(digits(&[1, 0, 1, 1]), BigInt::from_binary_string("1011"));
         }
 
         #[test]
-        fn test_eq() {
-            assert!(BigInt::from_string("") == BigInt::from_string(""));
-            assert!(BigInt::from_string("") == BigInt{data: vec![0, 0]});
-            assert!(BigInt::from_string("342") == BigInt{data: vec![342]});
-            assert!(BigInt::from_string("342") == BigInt{data: vec![342, 0, 0]});
-            assert!(BigInt{data: vec![342, 0, 0, 0]} == BigInt{data: vec![342, 0]});
-            assert!(BigInt{data: vec![0, 342, 0, 0]} != BigInt{data: vec![342, 0, 0]});
+        fn test_try_from_binary_string() {
+            assert_eq!(Ok(digits(&[1, 0, 1, 1])), BigInt::try_from_binary_string("1011"));
+            assert_eq!(Ok(BigInt::zero()), BigInt::try_from_binary_string(""));
+            let error = BigInt::try_from_binary_string("1021").unwrap_err();
+            assert_eq!("invalid binary digit", error.reason);
+            assert_eq!("cannot parse BigInt: invalid binary digit", error.to_string());
+            assert!(BigInt::try_from_binary_string("10 1").is_err());
+            assert!(BigInt::try_from_binary_string("1é").is_err());
         }
 
         #[test]
-        fn test_from_string() {
-            assert_eq!(BigInt { data: vec![4] }, BigInt::from_string("4"));
-            assert_eq!(BigInt::zero(), BigInt::from_string(""));
-            assert_eq!(
-                BigInt {
-                    data: vec![4294967295]
-                },
-                BigInt::from_string("4294967295")
-            );
-            assert_eq!(
-                BigInt { data: vec![0, 1] },
-                BigInt::from_string("4294967296")
-            );
-            assert_eq!(
-                BigInt {
-                    data: vec![937946958, 287445]
-                },
-                BigInt::from_string("1234567812345678")
-            );
-            assert_eq!(
-                BigInt {
-                    data: vec![3435973836, 214748364]
-                },
-                BigInt::from_string("922337203685477580")
-            );
-            assert_eq!(
-                BigInt {
-                    data: vec![4294963245, 4294967295, 499]
-                },
-                BigInt::from_string("9223372036854775803949")
-            );
-            assert_eq!(
-                BigInt {
-                    data: vec![3461744650, 2330743505, 1228788904, 542101086]
-                },
-                BigInt::from_string("42949672963434342343243324343232890890")
-            );
+        #[should_panic]
+        fn test_from_binary_string_invalid() {
+            BigInt::from_binary_string("12");
         }
 
         #[test]
-        fn test_sum() {
-            assert_eq!(
-                BigInt {
-                    data: vec![0, 3, 1]
-                },
-                sum(
-                    &BigInt {
-                        data: vec![(BigInt::BASE - 1) as u32, 1]
-                    },
-                    &BigInt {
-                        data: vec![1, 1, 1]
-                    }
-                )
-            );
-            assert_eq!(
-                BigInt::from_string("683598743919434280434619734254544588"),
-                sum(
-                    &BigInt::from_string("683598349590386730945834985730495834"),
-                    &BigInt::from_string("394329047549488784748524048754")
-                )
-            );
-            assert_eq!(
-                BigInt::from_string("10111111111111111111111111111111111123432342342110"),
-                sum(
-                    &BigInt::from_string("9999999999999999999999999999999999999999999999999"),
-                    &BigInt::from_string("111111111111111111111111111111111123432342342111")
-                )
-            );
+        fn test_hex_string() {
+            assert_eq!("0", BigInt::zero().to_hex_string());
+            assert_eq!(BigInt::zero(), BigInt::from_hex_string(""));
+            assert_eq!(BigInt::from_binary_string("1101"), BigInt::from_hex_string("b"));
+            let value = BigInt::from_hex_string("00DeadBeef0123456789abcdef");
+            assert_eq!("deadbeef0123456789abcdef", value.to_hex_string());
+            let expected =
+                crate::optimized_memory::BigInt::from_string("68915718005617500482515488239");
+            assert_eq!(format!("{:x}", expected), value.to_hex_string());
+            assert_eq!(expected.to_string(), value.to_string());
+
+            let error = BigInt::try_from_hex_string("12g4").unwrap_err();
+            assert_eq!("invalid hex digit", error.reason);
+            assert!(BigInt::try_from_hex_string("0x12").is_err());
         }
 
         #[test]
-        fn test_product() {
+        fn test_bytes_be() {
+            use crate::optimized_memory;
+
+            assert_eq!(vec![0], BigInt::zero().to_bytes_be());
+            assert_eq!(BigInt::zero(), BigInt::from_bytes_be(&[]));
+            assert_eq!(BigInt::from_hex_string("1ff"), BigInt::from_bytes_be(&[0, 0, 1, 255]));
+            assert_eq!(vec![1, 255], BigInt::from_hex_string("1ff").to_bytes_be());
+
+            let bytes: Vec<u8> = (1..=37).map(|i| i * 6).collect();
+            let easy = BigInt::from_bytes_be(&bytes);
+            let optimized = optimized_memory::BigInt::from_bytes_be(&bytes);
+            assert_eq!(optimized, optimized_memory::BigInt::from(&easy));
+            assert_eq!(bytes, easy.to_bytes_be());
+            assert_eq!(bytes, optimized.to_bytes_be());
+            let mut doubled = easy.clone();
+            doubled.times_two();
+            let two = optimized_memory::BigInt::two();
+            assert_eq!(optimized.mul(&two).to_bytes_be(), doubled.to_bytes_be());
+        }
+
+        #[test]
+        fn test_packed_digits() {
+            let value = BigInt::from_binary_string("1011000001");
+            assert_eq!(vec![0b1101, 0b10], value.data);
+            assert_eq!(10, value.len);
+
+            let mut doubled = BigInt::from_binary_string("1011000001");
+            for _ in 0..3 {
+                doubled.times_two();
+            }
+            assert_eq!(value.data, doubled.data);
+            assert_eq!("0001011000001", doubled.to_binary_string());
+            assert_eq!(value.mul(&BigInt::from_binary_string("0001")), doubled);
+        }
+
+        #[test]
+        fn test_from_decimal_string() {
+            use crate::unsigned::BigUnsigned;
+
+            assert_eq!(BigInt::zero(), BigInt::from_decimal_string(""));
             assert_eq!(
-                BigInt {
-                    data: vec![4294931842, 177267, 35464, 2]
-                },
-                product(
-                    &BigInt {
-                        data: vec![35454, 2]
-                    },
-                    &BigInt {
-                        data: vec![(BigInt::BASE - 1) as u32, 4, 1]
-                    }
+                Ordering::Equal,
+                BigUnsigned::cmp(
+                    &BigInt::from_binary_string("0101"),
+                    &BigInt::from_decimal_string("0010")
                 )
             );
-            assert_eq!(BigInt::from_string("1111111111111111111111111111111111234323423421109888888888888888888888888888888888876567657657889"),
-                       product(&BigInt::from_string("9999999999999999999999999999999999999999999999999"),
-                               &BigInt::from_string("111111111111111111111111111111111123432342342111")));
+            let value = BigInt::from_decimal_string("12345678901234567890");
+            let expected = crate::optimized_memory::BigInt::from_string("12345678901234567890");
+            let bits = (0..expected.bits()).map(|i| expected.bit(i) as u8);
+            assert!(bits.eq((0..value.significant_len()).map(|i| value.get(i))));
         }
-    }
 
-}
+        #[test]
+        fn test_to_string() {
+            assert_eq!("0", BigInt::zero().to_binary_string());
+            assert_eq!("1011", digits(&[1, 0, 1, 1, 0, 0]).to_binary_string());
+            let value = BigInt::from_decimal_string("0012345678901234567890");
+            let parsed = BigInt::from_binary_string(&value.to_binary_string());
+            assert_eq!(value.to_string(), parsed.to_string());
 
-pub mod easy {
-    ///! Short, non-optimized implementation of BigInt.
+            assert_eq!("0", format!("{}", BigInt::zero()));
+            assert_eq!("12345678901234567890", format!("{}", value));
+            assert_eq!("    13", format!("{:>6}", BigInt::from_binary_string("1011")));
+        }
 
-    #[derive(Debug, PartialEq, Eq)]
-    pub struct BigInt {
-        data: Vec<u8>,
-    }
+        #[test]
+        #[should_panic]
+        fn test_from_decimal_string_invalid() {
+            BigInt::from_decimal_string("12a");
+        }
 
-    impl BigInt {
-        pub fn zero() -> BigInt {
-            BigInt { data: Vec::new() }
+        #[test]
+        fn test_add_mul() {
+            let b1 = digits(&[1, 1, 1]);
+            let b2 = digits(&[0, 1, 1, 1]);
+            assert_eq!(digits(&[1, 0, 1, 0, 1]), b1.add(&b2));
+            assert_eq!(
+                digits(&[0, 1, 0, 1]),
+                digits(&[0, 1]).mul(&digits(&[1, 0, 1]))
+            );
         }
 
-        pub fn from_binary_string(s: &str) -> BigInt {
-            let mut data = Vec::new();
-            for c in s.chars() {
-                data.push(c as u8 - 48);
-            }
-            BigInt{data}
+        #[test]
+        fn test_sub() {
+            let b1 = digits(&[1, 0, 1, 0, 1]);
+            let b2 = digits(&[0, 1, 1, 1]);
+            assert_eq!(digits(&[1, 1, 1]), b1.sub(&b2));
+            assert_eq!(BigInt::zero(), b1.sub(&b1));
+            assert_eq!(b1, b1.sub(&BigInt::from_binary_string("000")));
+            assert_eq!(None, b2.checked_sub(&b1));
+
+            let a = BigInt::from_decimal_string("98765432109876543210");
+            let b = BigInt::from_decimal_string("12345678901234567890");
+            assert_eq!("86419753208641975320", a.sub(&b).to_string());
+            assert_eq!(a.to_string(), a.sub(&b).add(&b).to_string());
         }
 
-        fn times_two(&mut self) {
-            self.data.insert(0, 0);
+        #[test]
+        #[should_panic]
+        fn test_sub_underflow() {
+            BigInt::from_binary_string("1").sub(&BigInt::from_binary_string("01"));
         }
 
-        fn get(&self, i: usize) -> u8 {
-            if i < self.data.len() {
-                self.data[i]
-            } else {
-                0
-            }
+        #[test]
+        fn test_div_rem() {
+            let seven = BigInt::from_decimal_string("7");
+            let (q, r) = BigInt::from_decimal_string("100").div_rem(&seven);
+            assert_eq!(BigInt::from_decimal_string("14"), q);
+            assert_eq!(BigInt::from_decimal_string("2"), r);
+
+            let a = BigInt::from_decimal_string("98765432109876543210");
+            let b = BigInt::from_decimal_string("12345678901");
+            let (q, r) = a.div_rem(&b);
+            assert_eq!("8000000073", q.to_string());
+            assert_eq!("641983437", r.to_string());
+            assert_eq!(a, q.mul(&b).add(&r));
+
+            let (q, r) = b.div_rem(&a);
+            assert_eq!(BigInt::zero(), q);
+            assert_eq!(b, r);
+            assert_eq!((BigInt::zero(), BigInt::zero()), BigInt::zero().div_rem(&b));
         }
-    }
 
-    pub fn sum(b1: &BigInt, b2: &BigInt) -> BigInt {
-        let mut result = BigInt::zero();
-        let largest = std::cmp::max(b1.data.len(), b2.data.len());
-        let mut carry = 0;
-        for i in 0..largest {
-            match (b1.get(i), b2.get(i)) {
-                (0, 0) => result.data.push(carry),
-                (1, 0) | (0, 1) => result.data.push((1 + carry) % 2),
-                (1, 1) => {
-                    result.data.push(carry);
-                    carry = 1;
-                }
-                _ => panic!("Something is wrong"),
-            }
+        #[test]
+        #[should_panic]
+        fn test_div_rem_zero() {
+            BigInt::from_binary_string("1").div_rem(&BigInt::from_binary_string("00"));
         }
 
-        if carry == 1 {
-            result.data.push(1);
+        #[test]
+        fn test_optimized_memory_conversion() {
+            use crate::optimized_memory;
+
+            for s in ["0", "1", "4294967295", "4294967296", "98765432109876543210987654321"] {
+                let easy = BigInt::from_decimal_string(s);
+                let optimized = optimized_memory::BigInt::from(&easy);
+                assert_eq!(optimized_memory::BigInt::from_string(s), optimized);
+                let back = BigInt::from(&optimized);
+                assert_eq!(easy, back);
+                assert_eq!(back.significant_len(), back.digit_len());
+            }
+            assert_eq!(
+                optimized_memory::BigInt::one(),
+                optimized_memory::BigInt::from(&digits(&[1, 0, 0]))
+            );
         }
 
-        result
-    }
+        #[test]
+        fn test_signed() {
+            let a = SignedBigInt::from_decimal_string("-98765432109876543210");
+            let b = SignedBigInt::from_decimal_string("+12345678901234567890");
+            assert!(a.is_negative());
+            assert_eq!("-86419753208641975320", a.add(&b).to_string());
+            assert_eq!("-111111111011111111100", a.sub(&b).to_string());
+            assert_eq!("111111111011111111100", b.sub(&a).to_string());
+            assert_eq!(
+                "-1219326311370217952237463801111263526900",
+                a.mul(&b).to_string()
+            );
+            assert_eq!("0", a.sub(&a).to_string());
+            assert!(!a.sub(&a).is_negative());
+            assert_eq!(
+                SignedBigInt::from_decimal_string("0"),
+                SignedBigInt::new(true, BigInt::zero())
+            );
+            let minus_42 = SignedBigInt::from_decimal_string("-42");
+            assert_eq!("   -42", format!("{:>6}", minus_42));
+            assert_eq!("42", minus_42.neg().to_string());
+        }
 
-    pub fn product(b1: &BigInt, b2: &BigInt) -> BigInt {
-        let mut result = BigInt::zero();
-        let mut temp = BigInt {
-            data: b1.data.clone(),
-        };
-        for digit in &b2.data {
-            if *digit == 1 {
-                result = sum(&result, &temp);
+        #[test]
+        fn test_twos_complement() {
+            for value in [-128i32, -5, -1, 0, 1, 5, 127] {
+                let signed = SignedBigInt::from_decimal_string(&value.to_string());
+                let bits = signed.to_twos_complement(8);
+                assert_eq!((value as u8).to_string(), bits.to_string());
+                assert_eq!(signed, SignedBigInt::from_twos_complement(&bits, 8));
             }
-            temp.times_two();
+            let minus_one = SignedBigInt::from_decimal_string("-1").to_twos_complement(100);
+            assert_eq!("1".repeat(100), minus_one.to_binary_string());
+            assert_eq!(
+                "-1",
+                SignedBigInt::from_twos_complement(&minus_one, 100).to_string()
+            );
+            let pattern = BigInt::from_decimal_string("255");
+            assert_eq!("255", SignedBigInt::from_twos_complement(&pattern, 9).to_string());
         }
-        result
-    }
 
-    #[cfg(test)]
-    mod tests {
+        #[test]
+        #[should_panic]
+        fn test_twos_complement_overflow() {
+            SignedBigInt::from_decimal_string("128").to_twos_complement(8);
+        }
 
-        use super::*;
-        use test::Bencher;
+        #[test]
+        fn test_operators() {
+            let a = BigInt::from_decimal_string("98765432109876543210");
+            let b = BigInt::from_decimal_string("12345678901234567890");
+            assert_eq!(a.add(&b), &a + &b);
+            assert_eq!(a.sub(&b), &a - &b);
+            assert_eq!(a.mul(&b), &a * &b);
+            let expression = &(&(&a - &b) * &b) + &a;
+            assert_eq!("1066910523837829584834324033346121018010", expression.to_string());
 
-        #[bench]
-        fn bench_sum_short(b: &mut Bencher) {
-            let b1 = BigInt::from_binary_string("1000011000010100");
-            let b2 = BigInt::from_binary_string("1011");
-            b.iter(|| sum(&b1, &b2))
+            // The same expression against the optimized backend.
+            let (a, b) = (
+                crate::optimized_memory::BigInt::from(&a),
+                crate::optimized_memory::BigInt::from(&b),
+            );
+            let expression = &(&(&a - &b) * &b) + &a;
+            assert_eq!("1066910523837829584834324033346121018010", expression.to_string());
+            // The inherent methods are still the ones called with the traits in scope.
+            assert_eq!(expression, a.sub(&b).mul(&b).add(&a));
         }
 
-        #[bench]
-        fn bench_product_short(b: &mut Bencher) {
-            let b1 = BigInt::from_binary_string("1000011000010100");
-            let b2 = BigInt::from_binary_string("1011");
-            b.iter(|| product(&b1, &b2))
+        #[test]
+        fn test_ordering() {
+            assert_eq!(digits(&[1]), digits(&[1, 0]));
+            assert_eq!(BigInt::zero(), digits(&[0, 0]));
+            assert_ne!(digits(&[1]), digits(&[0, 1]));
+
+            let mut values: Vec<BigInt> = ["110", "0", "1000", "01", "0000", "11"]
+                .iter()
+                .map(|s| BigInt::from_binary_string(s))
+                .collect();
+            values.sort();
+            let sorted: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            assert_eq!(vec!["0", "0", "1", "2", "3", "3"], sorted);
+            assert!(BigInt::from_decimal_string("100") > BigInt::from_decimal_string("99"));
+            assert_eq!(
+                Some(Ordering::Less),
+                digits(&[1, 1, 0]).partial_cmp(&digits(&[0, 0, 1]))
+            );
         }
 
         #[test]
-        fn test_from_binary_string() {
-            assert_eq!(BigInt{data: vec![1, 0, 1, 1]}, BigInt::from_binary_string("1011"));
+        fn test_add_resets_carry() {
+            assert_eq!(
+                digits(&[0, 1, 0]),
+                digits(&[1, 0, 0]).add(&digits(&[1]))
+            );
+            assert_eq!(
+                digits(&[0, 1, 1, 0, 1]),
+                digits(&[1, 1, 0, 0, 1]).add(&digits(&[1, 1]))
+            );
         }
 
         #[test]
+        #[allow(deprecated)]
         fn test_sum() {
             assert_eq!(
-                BigInt {
-                    data: vec![1, 0, 1, 0, 1]
-                },
+                digits(&[1, 0, 1, 0, 1]),
                 sum(
-                    &BigInt {
-                        data: vec![1, 1, 1]
-                    },
-                    &BigInt {
-                        data: vec![0, 1, 1, 1]
-                    }
+                    &digits(&[1, 1, 1]),
+                    &digits(&[0, 1, 1, 1])
                 )
             );
         }
 
         #[test]
+        #[allow(deprecated)]
         fn test_product() {
             assert_eq!(
-                BigInt {
-                    data: vec![0, 1, 0, 1]
-                },
+                digits(&[0, 1, 0, 1]),
                 product(
-                    &BigInt { data: vec![0, 1] },
-                    &BigInt {
-                        data: vec![1, 0, 1]
-                    }
+                    &digits(&[0, 1]),
+                    &digits(&[1, 0, 1])
                 )
             );
         }
@@ -0,0 +1,214 @@
+//! Low-level kernels on limb slices in base 2^32, least significant first, in the manner
+//! of GMP's mpn functions. They work in place, never allocate, and return the carry or
+//! borrow out of the most significant limb instead of growing the destination.
+
+/// `a += b`, returning the carry (0 or 1).
+///
+/// Panics if the slices have different lengths.
+pub fn add_n(a: &mut [u32], b: &[u32]) -> u32 {
+    assert_eq!(a.len(), b.len(), "limb slices have different lengths");
+    let mut carry = 0;
+    for (x, &y) in a.iter_mut().zip(b) {
+        let digit_sum = *x as u64 + y as u64 + carry;
+        *x = digit_sum as u32;
+        carry = digit_sum >> 32;
+    }
+    carry as u32
+}
+
+/// `a -= b` modulo 2^(32 * len), returning the borrow (0 or 1).
+///
+/// Panics if the slices have different lengths.
+pub fn sub_n(a: &mut [u32], b: &[u32]) -> u32 {
+    assert_eq!(a.len(), b.len(), "limb slices have different lengths");
+    let mut borrow = false;
+    for (x, &y) in a.iter_mut().zip(b) {
+        let (d, b1) = x.overflowing_sub(y);
+        let (d, b2) = d.overflowing_sub(borrow as u32);
+        *x = d;
+        borrow = b1 || b2;
+    }
+    borrow as u32
+}
+
+/// `a += b` for `b` no longer than `a`, returning the carry (0 or 1).
+///
+/// Panics if `b` is longer than `a`.
+pub fn add(a: &mut [u32], b: &[u32]) -> u32 {
+    assert!(b.len() <= a.len(), "second operand longer than the first");
+    let (low, high) = a.split_at_mut(b.len());
+    let mut carry = add_n(low, b);
+    for x in high {
+        if carry == 0 {
+            break;
+        }
+        let (sum, overflow) = x.overflowing_add(1);
+        *x = sum;
+        carry = overflow as u32;
+    }
+    carry
+}
+
+/// `a -= b` modulo 2^(32 * a.len()) for `b` no longer than `a`, returning the borrow
+/// (0 or 1).
+///
+/// Panics if `b` is longer than `a`.
+pub fn sub(a: &mut [u32], b: &[u32]) -> u32 {
+    assert!(b.len() <= a.len(), "second operand longer than the first");
+    let (low, high) = a.split_at_mut(b.len());
+    let mut borrow = sub_n(low, b);
+    for x in high {
+        if borrow == 0 {
+            break;
+        }
+        let (difference, underflow) = x.overflowing_sub(1);
+        *x = difference;
+        borrow = underflow as u32;
+    }
+    borrow
+}
+
+/// `a *= m`, returning the limb carried out of the top.
+pub fn mul_1(a: &mut [u32], m: u32) -> u32 {
+    let mut carry = 0;
+    for x in a.iter_mut() {
+        let t = *x as u64 * m as u64 + carry;
+        *x = t as u32;
+        carry = t >> 32;
+    }
+    carry as u32
+}
+
+/// `r += a * m`, returning the limb carried out of the top.
+///
+/// Panics if the slices have different lengths.
+pub fn addmul_1(r: &mut [u32], a: &[u32], m: u32) -> u32 {
+    assert_eq!(r.len(), a.len(), "limb slices have different lengths");
+    let mut carry = 0;
+    for (x, &y) in r.iter_mut().zip(a) {
+        // At most (2^32 - 1) + (2^32 - 1)^2 + (2^32 - 1) = 2^64 - 1.
+        let t = *x as u64 + y as u64 * m as u64 + carry;
+        *x = t as u32;
+        carry = t >> 32;
+    }
+    carry as u32
+}
+
+/// `a <<= shift`, returning the bits shifted out of the top in the low bits of the
+/// result.
+///
+/// Panics unless `shift < 32`.
+pub fn shl_n(a: &mut [u32], shift: u32) -> u32 {
+    assert!(shift < 32, "shift must be smaller than the limb size");
+    if shift == 0 {
+        return 0;
+    }
+    let mut carry = 0;
+    for x in a.iter_mut() {
+        let shifted_out = *x >> (32 - shift);
+        *x = (*x << shift) | carry;
+        carry = shifted_out;
+    }
+    carry
+}
+
+/// The remainder of `a` divided by `d`.
+///
+/// Panics if `d` is zero.
+pub fn mod_1(a: &[u32], d: u32) -> u32 {
+    assert!(d != 0, "attempt to divide by zero");
+    let mut remainder = 0u64;
+    for &x in a.iter().rev() {
+        remainder = ((remainder << 32) | x as u64) % d as u64;
+    }
+    remainder as u32
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_add_sub_n() {
+        let mut a = [u32::MAX, u32::MAX, 1];
+        assert_eq!(0, add_n(&mut a, &[1, 0, 0]));
+        assert_eq!([0, 0, 2], a);
+        assert_eq!(1, add_n(&mut a, &[0, 0, u32::MAX - 1]));
+        assert_eq!([0, 0, 0], a);
+
+        let mut a = [0, 0, 2];
+        assert_eq!(0, sub_n(&mut a, &[1, 0, 0]));
+        assert_eq!([u32::MAX, u32::MAX, 1], a);
+        assert_eq!(1, sub_n(&mut a, &[0, 0, 2]));
+        assert_eq!([u32::MAX, u32::MAX, u32::MAX], a);
+
+        let mut empty: [u32; 0] = [];
+        assert_eq!(0, add_n(&mut empty, &[]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_add_n_lengths() {
+        add_n(&mut [1, 2], &[1]);
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let mut a = [u32::MAX, u32::MAX, 1];
+        assert_eq!(0, add(&mut a, &[1]));
+        assert_eq!([0, 0, 2], a);
+        assert_eq!(0, sub(&mut a, &[1]));
+        assert_eq!([u32::MAX, u32::MAX, 1], a);
+        assert_eq!(1, add(&mut [u32::MAX, u32::MAX], &[1]));
+        assert_eq!(1, sub(&mut [0, 0], &[1]));
+        assert_eq!(0, add(&mut [5], &[]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_add_lengths() {
+        add(&mut [1], &[1, 2]);
+    }
+
+    #[test]
+    fn test_mul_1() {
+        let mut a = [u32::MAX, u32::MAX];
+        assert_eq!(u32::MAX - 1, mul_1(&mut a, u32::MAX));
+        assert_eq!([1, u32::MAX], a);
+        let mut a = [5, 7];
+        assert_eq!(0, mul_1(&mut a, 0));
+        assert_eq!([0, 0], a);
+    }
+
+    #[test]
+    fn test_addmul_1() {
+        let mut r = [u32::MAX, u32::MAX];
+        assert_eq!(u32::MAX, addmul_1(&mut r, &[u32::MAX, u32::MAX], u32::MAX));
+        // (2^64 - 1) + (2^64 - 1)(2^32 - 1) = (2^64 - 1) 2^32
+        assert_eq!([0, u32::MAX], r);
+        let mut r = [1, 2, 3];
+        assert_eq!(0, addmul_1(&mut r, &[1, 1, 1], 2));
+        assert_eq!([3, 4, 5], r);
+    }
+
+    #[test]
+    fn test_shl_n() {
+        let mut a = [0x8000_0001, 0xf000_0000];
+        assert_eq!(0xf, shl_n(&mut a, 4));
+        assert_eq!([0x0000_0010, 0x0000_0008], a);
+        assert_eq!(0, shl_n(&mut a, 0));
+        assert_eq!([0x0000_0010, 0x0000_0008], a);
+        assert_eq!(0, shl_n(&mut a, 28));
+        assert_eq!([0, 0x8000_0001], a);
+    }
+
+    #[test]
+    fn test_mod_1() {
+        assert_eq!(0, mod_1(&[], 7));
+        // 2^64 - 1 = 3 * 5 * 17 * 257 * 641 * 65537 * 6700417
+        assert_eq!(0, mod_1(&[u32::MAX, u32::MAX], 6700417));
+        assert_eq!(15, mod_1(&[u32::MAX, u32::MAX], 16));
+        assert_eq!(1, mod_1(&[0, 0, 1], u32::MAX));
+    }
+}
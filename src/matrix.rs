@@ -0,0 +1,296 @@
+//! Small square matrices of signed BigInts, with fast exponentiation for solving linear
+//! recurrences.
+
+use crate::optimized_memory::BigInt as Magnitude;
+use crate::signed::BigInt;
+
+/// A square matrix whose size is chosen at construction, stored in row-major order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Matrix {
+    size: usize,
+    entries: Vec<BigInt>,
+}
+
+impl Matrix {
+    pub fn zero(size: usize) -> Matrix {
+        Matrix {
+            size,
+            entries: vec![BigInt::zero(); size * size],
+        }
+    }
+
+    pub fn identity(size: usize) -> Matrix {
+        let mut result = Matrix::zero(size);
+        for i in 0..size {
+            result.set(i, i, BigInt::one());
+        }
+        result
+    }
+
+    /// Panics if the rows do not all have as many entries as there are rows.
+    pub fn from_rows(rows: Vec<Vec<BigInt>>) -> Matrix {
+        let size = rows.len();
+        assert!(
+            rows.iter().all(|row| row.len() == size),
+            "matrix must be square"
+        );
+        Matrix {
+            size,
+            entries: rows.into_iter().flatten().collect(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> &BigInt {
+        &self.entries[row * self.size + column]
+    }
+
+    pub fn set(&mut self, row: usize, column: usize, value: BigInt) {
+        self.entries[row * self.size + column] = value;
+    }
+
+    /// Panics if the sizes differ.
+    pub fn mul(&self, other: &Matrix) -> Matrix {
+        assert_eq!(self.size, other.size, "matrix sizes differ");
+        let n = self.size;
+        let mut result = Matrix::zero(n);
+        for i in 0..n {
+            for k in 0..n {
+                let a = self.get(i, k);
+                if a.is_zero() {
+                    continue;
+                }
+                for j in 0..n {
+                    let entry = result.get(i, j).add(&a.mul(other.get(k, j)));
+                    result.set(i, j, entry);
+                }
+            }
+        }
+        result
+    }
+
+    /// The product with every entry reduced to `[0, modulus)`.
+    pub fn mul_mod(&self, other: &Matrix, modulus: &Magnitude) -> Matrix {
+        self.mul(other).reduce(modulus)
+    }
+
+    pub fn pow(&self, exponent: &Magnitude) -> Matrix {
+        self.pow_with(exponent, |a, b| a.mul(b))
+    }
+
+    /// Exponentiation with every intermediate product reduced modulo `modulus`, which
+    /// keeps the entries small for huge exponents.
+    pub fn pow_mod(&self, exponent: &Magnitude, modulus: &Magnitude) -> Matrix {
+        self.reduce(modulus)
+            .pow_with(exponent, |a, b| a.mul_mod(b, modulus))
+            .reduce(modulus)
+    }
+
+    /// Multiply the matrix by a column vector.
+    ///
+    /// Panics if the vector length differs from the size.
+    pub fn apply(&self, vector: &[BigInt]) -> Vec<BigInt> {
+        assert_eq!(
+            self.size,
+            vector.len(),
+            "vector length differs from matrix size"
+        );
+        (0..self.size)
+            .map(|i| {
+                vector
+                    .iter()
+                    .enumerate()
+                    .fold(BigInt::zero(), |acc, (j, v)| {
+                        acc.add(&self.get(i, j).mul(v))
+                    })
+            })
+            .collect()
+    }
+
+    fn reduce(&self, modulus: &Magnitude) -> Matrix {
+        let modulus = BigInt::from(modulus.clone());
+        Matrix {
+            size: self.size,
+            entries: self
+                .entries
+                .iter()
+                .map(|e| e.rem_euclid(&modulus))
+                .collect(),
+        }
+    }
+
+    /// Square and multiply, from the most significant bit of `exponent`.
+    fn pow_with<F>(&self, exponent: &Magnitude, mul: F) -> Matrix
+    where
+        F: Fn(&Matrix, &Matrix) -> Matrix,
+    {
+        let mut result = Matrix::identity(self.size);
+        for i in (0..exponent.bits()).rev() {
+            result = mul(&result, &result);
+            if exponent.bit(i) {
+                result = mul(&result, self);
+            }
+        }
+        result
+    }
+}
+
+/// The term `a[n]` of the recurrence
+/// `a[k] = coeffs[0] a[k - 1] + ... + coeffs[d - 1] a[k - d]`
+/// whose first terms are `init = [a[0], ..., a[d - 1]]`.
+///
+/// Panics if `coeffs` and `init` have different lengths.
+pub fn linear_recurrence(coeffs: &[BigInt], init: &[BigInt], n: &Magnitude) -> BigInt {
+    recurrence_term(coeffs, init, n, |m, k| m.pow(k))
+}
+
+/// As `linear_recurrence`, modulo `modulus`. The result is in `[0, modulus)`.
+pub fn linear_recurrence_mod(
+    coeffs: &[BigInt],
+    init: &[BigInt],
+    n: &Magnitude,
+    modulus: &Magnitude,
+) -> BigInt {
+    recurrence_term(coeffs, init, n, |m, k| m.pow_mod(k, modulus))
+        .rem_euclid(&BigInt::from(modulus.clone()))
+}
+
+/// Helper function for `linear_recurrence`: apply a power of the companion matrix to the
+/// state `[a[d - 1], ..., a[0]]`, whose last entry is then `a[n]`.
+fn recurrence_term<F: Fn(&Matrix, &Magnitude) -> Matrix>(
+    coeffs: &[BigInt],
+    init: &[BigInt],
+    n: &Magnitude,
+    pow: F,
+) -> BigInt {
+    assert_eq!(
+        coeffs.len(),
+        init.len(),
+        "a recurrence of order d needs d initial terms"
+    );
+    let d = coeffs.len();
+    if d == 0 {
+        return BigInt::zero();
+    }
+    let mut companion = Matrix::zero(d);
+    for (j, c) in coeffs.iter().enumerate() {
+        companion.set(0, j, c.clone());
+    }
+    for i in 1..d {
+        companion.set(i, i - 1, BigInt::one());
+    }
+    let state: Vec<BigInt> = init.iter().rev().cloned().collect();
+    pow(&companion, n).apply(&state).pop().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn ints(values: &[i64]) -> Vec<BigInt> {
+        values.iter().map(|&v| BigInt::from(v)).collect()
+    }
+
+    fn matrix(rows: &[&[i64]]) -> Matrix {
+        Matrix::from_rows(rows.iter().map(|row| ints(row)).collect())
+    }
+
+    #[test]
+    fn test_mul() {
+        let a = matrix(&[&[1, 2], &[3, 4]]);
+        let b = matrix(&[&[0, -1], &[5, 2]]);
+        assert_eq!(matrix(&[&[10, 3], &[20, 5]]), a.mul(&b));
+        assert_eq!(a, a.mul(&Matrix::identity(2)));
+        assert_eq!(
+            matrix(&[&[3, 3], &[6, 5]]),
+            a.mul_mod(&b, &Magnitude::from(7u32))
+        );
+        assert_eq!(ints(&[5, 11]), a.apply(&ints(&[1, 2])));
+    }
+
+    #[test]
+    fn test_pow() {
+        let fibonacci = matrix(&[&[1, 1], &[1, 0]]);
+        assert_eq!(Matrix::identity(2), fibonacci.pow(&Magnitude::zero()));
+        assert_eq!(
+            matrix(&[&[8, 5], &[5, 3]]),
+            fibonacci.pow(&Magnitude::from(5u32))
+        );
+        assert_eq!(
+            matrix(&[&[1, 1], &[1, 0]]),
+            fibonacci.pow_mod(&Magnitude::from(60u32 + 1), &Magnitude::from(10u32))
+        );
+        let negative = matrix(&[&[-1, 0], &[0, -2]]);
+        assert_eq!(
+            matrix(&[&[4, 0], &[0, 2]]),
+            negative.pow_mod(&Magnitude::from(3u32), &Magnitude::from(5u32))
+        );
+    }
+
+    #[test]
+    fn test_linear_recurrence() {
+        let coeffs = ints(&[1, 1]);
+        let init = ints(&[0, 1]);
+        assert_eq!(
+            BigInt::zero(),
+            linear_recurrence(&coeffs, &init, &Magnitude::zero())
+        );
+        assert_eq!(
+            BigInt::one(),
+            linear_recurrence(&coeffs, &init, &Magnitude::one())
+        );
+        assert_eq!(
+            BigInt::from_string("354224848179261915075"),
+            linear_recurrence(&coeffs, &init, &Magnitude::from(100u32))
+        );
+        // An order three recurrence with a negative coefficient: a[k] = 2a[k-1] - a[k-3].
+        let coeffs = ints(&[2, 0, -1]);
+        let init = ints(&[1, 1, 1]);
+        let mut terms = ints(&[1, 1, 1]);
+        for k in 3..30 {
+            let next = terms[k - 1].mul(&BigInt::from(2i32)).sub(&terms[k - 3]);
+            terms.push(next);
+        }
+        for (n, term) in terms.iter().enumerate() {
+            assert_eq!(
+                *term,
+                linear_recurrence(&coeffs, &init, &Magnitude::from(n as u32))
+            );
+        }
+    }
+
+    #[test]
+    fn test_linear_recurrence_mod() {
+        let coeffs = ints(&[1, 1]);
+        let init = ints(&[0, 1]);
+        let modulus = Magnitude::from(1_000_000_007u32);
+        let exact = linear_recurrence(&coeffs, &init, &Magnitude::from(1000u32));
+        assert_eq!(
+            exact.rem_euclid(&BigInt::from(modulus.clone())),
+            linear_recurrence_mod(&coeffs, &init, &Magnitude::from(1000u32), &modulus)
+        );
+        // The Pisano period modulo 10 is 60 and 10^18 = 40 (mod 60), F(40) = 102334155.
+        let n = Magnitude::from(10u32).pow(18);
+        assert_eq!(
+            BigInt::from(5i32),
+            linear_recurrence_mod(&coeffs, &init, &n, &Magnitude::from(10u32))
+        );
+        let alternating = linear_recurrence_mod(
+            &ints(&[-1]),
+            &ints(&[3]),
+            &n.add(&Magnitude::one()),
+            &Magnitude::from(7u32),
+        );
+        assert_eq!(BigInt::from(4i32), alternating);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mismatched_init() {
+        linear_recurrence(&ints(&[1, 1]), &ints(&[1]), &Magnitude::one());
+    }
+}
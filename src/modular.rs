@@ -0,0 +1,1015 @@
+//! Arithmetic modulo a fixed BigInt. A `ModRing` holds the modulus together with the
+//! constants for Barrett reduction, and every `ModInt` it produces shares them.
+//! Moduli of the form `2^k - c` with a small `c`, such as Mersenne primes or
+//! `2^255 - 19`, are detected and reduced with shifts and additions instead.
+//! Powers of two get the cheaper `Wrapping2k`, which reduces by masking, and `modpow`
+//! with an even modulus takes the power modulo its odd part by Montgomery reduction.
+//!
+//! A `RingCache`, or the global one behind `ModRing::cached`, keeps the rings of recent
+//! moduli for code that would otherwise rebuild them for every operation.
+
+use crate::limb_ops;
+use crate::optimized_memory::BigInt;
+use crate::signed;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, PartialEq, Eq)]
+struct Context {
+    modulus: BigInt,
+    /// Bit length of the modulus.
+    k: usize,
+    /// floor(4^k / modulus).
+    mu: BigInt,
+    /// `c` when the modulus is `2^k - c` with `c` below `2^(k/2)`.
+    offset: Option<BigInt>,
+}
+
+impl Context {
+    /// Reduction of a value smaller than modulus^2.
+    fn reduce(&self, x: BigInt) -> BigInt {
+        match &self.offset {
+            Some(c) => self.reduce_pseudo_mersenne(x, c),
+            None => self.reduce_barrett(x),
+        }
+    }
+
+    /// Since `2^k = c`, the bits above `k` fold back in multiplied by `c`. Each fold
+    /// shrinks the value by about `k / 2` bits, and once it is below `2^k = modulus + c`
+    /// a single subtraction remains.
+    fn reduce_pseudo_mersenne(&self, mut x: BigInt, c: &BigInt) -> BigInt {
+        while x.bits() > self.k {
+            let low = x.truncate_to(self.k);
+            x = (x >> self.k).mul(c).add(&low);
+        }
+        if x >= self.modulus {
+            x = x.sub(&self.modulus);
+        }
+        x
+    }
+
+    fn reduce_barrett(&self, x: BigInt) -> BigInt {
+        let q = ((x.clone() >> (self.k - 1)).mul(&self.mu)) >> (self.k + 1);
+        let mut r = x.sub(&q.mul(&self.modulus));
+        while r >= self.modulus {
+            r = r.sub(&self.modulus);
+        }
+        r
+    }
+}
+
+/// The ring of integers modulo `modulus`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModRing {
+    context: Arc<Context>,
+}
+
+impl ModRing {
+    /// Panics if `modulus` is zero.
+    pub fn new(modulus: BigInt) -> ModRing {
+        assert!(!modulus.is_zero(), "modulus must be nonzero");
+        let k = modulus.bits();
+        let mu = (BigInt::one() << (2 * k)).div(&modulus);
+        let c = (BigInt::one() << k).sub(&modulus);
+        let offset = if c.bits() <= k / 2 { Some(c) } else { None };
+        ModRing {
+            context: Arc::new(Context {
+                modulus,
+                k,
+                mu,
+                offset,
+            }),
+        }
+    }
+
+    /// The ring for `modulus` from a global `RingCache` of the `GLOBAL_RING_CACHE_SIZE`
+    /// most recently used moduli, so that one-off operations against a few recurring
+    /// moduli share their reduction constants.
+    ///
+    /// Panics if `modulus` is zero.
+    pub fn cached(modulus: &BigInt) -> ModRing {
+        GLOBAL_RING_CACHE.lock().unwrap().get(modulus)
+    }
+
+    pub fn modulus(&self) -> &BigInt {
+        &self.context.modulus
+    }
+
+    /// `c` if the modulus is `2^k - c` with `c` small enough for the special reduction,
+    /// where `k` is the bit length of the modulus.
+    pub fn pseudo_mersenne_offset(&self) -> Option<&BigInt> {
+        self.context.offset.as_ref()
+    }
+
+    /// The residue class of `value`.
+    pub fn element(&self, value: &BigInt) -> ModInt {
+        self.wrap(value.rem(&self.context.modulus))
+    }
+
+    pub fn zero(&self) -> ModInt {
+        self.wrap(BigInt::zero())
+    }
+
+    pub fn one(&self) -> ModInt {
+        self.element(&BigInt::one())
+    }
+
+    fn wrap(&self, value: BigInt) -> ModInt {
+        ModInt {
+            value,
+            context: self.context.clone(),
+        }
+    }
+}
+
+/// The capacity of the cache behind `ModRing::cached`.
+pub const GLOBAL_RING_CACHE_SIZE: usize = 16;
+
+static GLOBAL_RING_CACHE: Mutex<RingCache> = Mutex::new(RingCache::new(GLOBAL_RING_CACHE_SIZE));
+
+/// The rings of the most recently used moduli, up to a fixed number, the least recently
+/// used going first when a new one is needed.
+#[derive(Debug, Clone)]
+pub struct RingCache {
+    capacity: usize,
+    /// From the most recently used.
+    rings: Vec<ModRing>,
+}
+
+impl RingCache {
+    /// Panics if `capacity` is zero.
+    pub const fn new(capacity: usize) -> RingCache {
+        assert!(capacity > 0, "the cache needs room for a ring");
+        RingCache {
+            capacity,
+            rings: Vec::new(),
+        }
+    }
+
+    /// The ring for `modulus`, built only if it is not in the cache.
+    ///
+    /// Panics if `modulus` is zero.
+    pub fn get(&mut self, modulus: &BigInt) -> ModRing {
+        let ring = match self.rings.iter().position(|r| r.modulus() == modulus) {
+            Some(i) => self.rings.remove(i),
+            None => {
+                self.rings.truncate(self.capacity - 1);
+                ModRing::new(modulus.clone())
+            }
+        };
+        self.rings.insert(0, ring.clone());
+        ring
+    }
+
+    pub fn len(&self) -> usize {
+        self.rings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rings.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.rings.clear();
+    }
+}
+
+/// An element of a `ModRing`, always stored as its smallest nonnegative representative.
+///
+/// Operators panic if the operands come from rings with different moduli.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModInt {
+    value: BigInt,
+    context: Arc<Context>,
+}
+
+impl ModInt {
+    pub fn value(&self) -> &BigInt {
+        &self.value
+    }
+
+    pub fn into_value(self) -> BigInt {
+        self.value
+    }
+
+    pub fn ring(&self) -> ModRing {
+        ModRing {
+            context: self.context.clone(),
+        }
+    }
+
+    /// The multiplicative inverse, or `None` if the value is not coprime with the modulus.
+    pub fn inv(&self) -> Option<ModInt> {
+        // Extended Euclid, tracking only the coefficient of the value.
+        let modulus = signed::BigInt::from(self.context.modulus.clone());
+        let (mut r0, mut r1) = (modulus.clone(), signed::BigInt::from(self.value.clone()));
+        let (mut t0, mut t1) = (signed::BigInt::zero(), signed::BigInt::one());
+        while !r1.is_zero() {
+            let (q, r) = r0.div_rem(&r1);
+            r0 = std::mem::replace(&mut r1, r);
+            let t = t0.sub(&q.mul(&t1));
+            t0 = std::mem::replace(&mut t1, t);
+        }
+        if !r0.magnitude().is_one() {
+            return None;
+        }
+        let inverse = t0.rem_euclid(&modulus).into_magnitude();
+        Some(self.with_value(inverse.rem(&self.context.modulus)))
+    }
+
+    /// Square and multiply, from the most significant bit of `exponent`.
+    pub fn pow(&self, exponent: &BigInt) -> ModInt {
+        let mut result = self.ring().one();
+        for bit in exponent.bits_be() {
+            result = &result * &result;
+            if bit {
+                result = &result * self;
+            }
+        }
+        result
+    }
+
+    fn with_value(&self, value: BigInt) -> ModInt {
+        ModInt {
+            value,
+            context: self.context.clone(),
+        }
+    }
+
+    fn check_same_ring(&self, other: &ModInt) {
+        assert!(
+            Arc::ptr_eq(&self.context, &other.context) || self.context == other.context,
+            "ModInt operands belong to different rings"
+        );
+    }
+}
+
+impl Add for &ModInt {
+    type Output = ModInt;
+
+    fn add(self, other: &ModInt) -> ModInt {
+        self.check_same_ring(other);
+        let mut sum = self.value.add(&other.value);
+        if sum >= self.context.modulus {
+            sum = sum.sub(&self.context.modulus);
+        }
+        self.with_value(sum)
+    }
+}
+
+impl Sub for &ModInt {
+    type Output = ModInt;
+
+    fn sub(self, other: &ModInt) -> ModInt {
+        self.check_same_ring(other);
+        let difference = if self.value >= other.value {
+            self.value.sub(&other.value)
+        } else {
+            self.value.add(&self.context.modulus).sub(&other.value)
+        };
+        self.with_value(difference)
+    }
+}
+
+impl Mul for &ModInt {
+    type Output = ModInt;
+
+    fn mul(self, other: &ModInt) -> ModInt {
+        self.check_same_ring(other);
+        self.with_value(self.context.reduce(self.value.mul(&other.value)))
+    }
+}
+
+impl Neg for &ModInt {
+    type Output = ModInt;
+
+    fn neg(self) -> ModInt {
+        &self.ring().zero() - self
+    }
+}
+
+/// Forward an operator on owned values to the implementation on references.
+macro_rules! forward_owned_binop {
+    ($t:ident: $($tr:ident :: $method:ident),*) => {
+        $(
+            impl $tr for $t {
+                type Output = $t;
+
+                fn $method(self, other: $t) -> $t {
+                    (&self).$method(&other)
+                }
+            }
+        )*
+    };
+}
+
+forward_owned_binop!(ModInt: Add::add, Sub::sub, Mul::mul);
+
+impl Neg for ModInt {
+    type Output = ModInt;
+
+    fn neg(self) -> ModInt {
+        -&self
+    }
+}
+
+impl fmt::Display for ModInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.value, f)
+    }
+}
+
+/// One-off modular operations, for when building a `ModRing` is not worth it.
+impl BigInt {
+    /// `self^exponent mod modulus`. An even modulus `2^k m` is split: the power is taken
+    /// modulo the odd `m` in Montgomery form and, by masking, modulo `2^k`, then the two
+    /// are recombined.
+    ///
+    /// Panics if `modulus` is zero.
+    pub fn modpow(&self, exponent: &BigInt, modulus: &BigInt) -> BigInt {
+        if modulus.is_even() && !modulus.is_zero() {
+            return modpow_even(self, exponent, modulus);
+        }
+        ModRing::new(modulus.clone())
+            .element(self)
+            .pow(exponent)
+            .into_value()
+    }
+
+    /// The inverse modulo `modulus`, or `None` if the value is not coprime with it.
+    ///
+    /// Panics if `modulus` is zero.
+    pub fn mod_inverse(&self, modulus: &BigInt) -> Option<BigInt> {
+        ModRing::new(modulus.clone())
+            .element(self)
+            .inv()
+            .map(ModInt::into_value)
+    }
+}
+
+/// Helper function for `modpow` with an even modulus.
+fn modpow_even(base: &BigInt, exponent: &BigInt, modulus: &BigInt) -> BigInt {
+    let k = modulus.trailing_zeros();
+    let odd = modulus.clone() >> k;
+    let mut low = Wrapping2k::new(&BigInt::one(), k);
+    let base_low = Wrapping2k::new(base, k);
+    for bit in exponent.bits_be() {
+        low = &low * &low;
+        if bit {
+            low = &low * &base_low;
+        }
+    }
+    if odd.is_one() {
+        return low.into_value();
+    }
+    let high = montgomery_pow(base, exponent, &odd);
+    // The result is high + odd t, with odd t = low - high modulo 2^k.
+    let t = &(&low - &Wrapping2k::new(&high, k)) * &inverse_2k(&odd, k);
+    high.add(&odd.mul(t.value()))
+}
+
+/// Helper function for `modpow_even`: `base^exponent mod odd`, in Montgomery form. A value
+/// `x` is held as `x R mod odd`, with `R = 2^(32 n)` for an `odd` of `n` limbs, so that
+/// reducing a product takes one multiplication of `odd` by a limb per limb and a shift,
+/// instead of a division.
+fn montgomery_pow(base: &BigInt, exponent: &BigInt, odd: &BigInt) -> BigInt {
+    let m = odd.as_limbs_le();
+    let n = m.len();
+    // -odd^-1 mod 2^32, by the same Newton iteration as `inverse_2k`.
+    let mut inverse = m[0];
+    for _ in 0..4 {
+        inverse = inverse.wrapping_mul(2u32.wrapping_sub(m[0].wrapping_mul(inverse)));
+    }
+    let m_inv = inverse.wrapping_neg();
+    let to_montgomery = |x: &BigInt| {
+        let mut limbs = (x.rem(odd) << (32 * n)).rem(odd).into_limbs();
+        limbs.resize(n, 0);
+        limbs
+    };
+    let base = to_montgomery(base);
+    let mut x = to_montgomery(&BigInt::one());
+    for bit in exponent.bits_be() {
+        x = montgomery_mul(&x, &x, m, m_inv);
+        if bit {
+            x = montgomery_mul(&x, &base, m, m_inv);
+        }
+    }
+    let mut one = vec![0; n];
+    one[0] = 1;
+    BigInt::from_limbs_le(&montgomery_mul(&x, &one, m, m_inv))
+}
+
+/// Helper function for `montgomery_pow`: `a b / R mod m` for `a` and `b` below `m`, all
+/// of `n` limbs, where `m_inv` is `-m^-1 mod 2^32`. This is REDC: adding a multiple of `m`
+/// clears the low limb of the product, `n` times, and leaves a value below `2 m`.
+fn montgomery_mul(a: &[u32], b: &[u32], m: &[u32], m_inv: u32) -> Vec<u32> {
+    let n = m.len();
+    let mut t = vec![0; 2 * n + 1];
+    for (j, &d) in b.iter().enumerate() {
+        let carry = limb_ops::addmul_1(&mut t[j..j + n], a, d);
+        limb_ops::add(&mut t[j + n..], &[carry]);
+    }
+    for i in 0..n {
+        let u = t[i].wrapping_mul(m_inv);
+        let carry = limb_ops::addmul_1(&mut t[i..i + n], m, u);
+        limb_ops::add(&mut t[i + n..], &[carry]);
+    }
+    let mut r = t.split_off(n);
+    if r[n] != 0 || r[..n].iter().rev().cmp(m.iter().rev()) != Ordering::Less {
+        limb_ops::sub(&mut r, m);
+    }
+    r.truncate(n);
+    r
+}
+
+/// Helper function for `modpow_even`: the inverse of an odd value modulo 2^k, by Newton's
+/// iteration `x = x (2 - a x)`, which doubles the number of correct bits each time.
+fn inverse_2k(odd: &BigInt, k: usize) -> Wrapping2k {
+    let a = Wrapping2k::new(odd, k);
+    let two = Wrapping2k::new(&BigInt::two(), k);
+    // Every odd value is its own inverse modulo 8.
+    let mut x = a.clone();
+    let mut correct = 3;
+    while correct < k {
+        x = &x * &(&two - &(&a * &x));
+        correct *= 2;
+    }
+    x
+}
+
+impl signed::BigInt {
+    /// `self^exponent mod modulus` as a value in `[0, modulus)`. A negative exponent
+    /// raises the inverse of `self` to the absolute value of the exponent, which fails
+    /// when `self` is not coprime with the modulus.
+    ///
+    /// Panics if `modulus` is zero.
+    pub fn modpow(
+        &self,
+        exponent: &signed::BigInt,
+        modulus: &BigInt,
+    ) -> Result<BigInt, NotInvertibleError> {
+        let base = ModRing::new(modulus.clone()).element(
+            &self
+                .rem_euclid(&signed::BigInt::from(modulus.clone()))
+                .into_magnitude(),
+        );
+        let base = if exponent.is_negative() {
+            base.inv().ok_or(NotInvertibleError)?
+        } else {
+            base
+        };
+        Ok(base.pow(exponent.magnitude()).into_value())
+    }
+}
+
+/// The error returned when a value has no inverse modulo the modulus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotInvertibleError;
+
+impl fmt::Display for NotInvertibleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value is not invertible modulo the modulus")
+    }
+}
+
+impl std::error::Error for NotInvertibleError {}
+
+/// The inverses of all `values` modulo `m`, `None` for those not coprime with `m`, by
+/// Montgomery's trick: the running products are inverted with a single extended gcd,
+/// and each inverse is recovered with two multiplications on the way back.
+///
+/// If some value is not invertible, finding which ones costs a gcd each before the
+/// others are inverted together.
+///
+/// Panics if `m` is zero.
+pub fn mod_inverse_batch(values: &[BigInt], m: &BigInt) -> Vec<Option<BigInt>> {
+    let ring = ModRing::new(m.clone());
+    let elements: Vec<ModInt> = values.iter().map(|v| ring.element(v)).collect();
+    if let Some(inverses) = invert_all(&ring, &elements) {
+        return inverses.into_iter().map(|x| Some(x.into_value())).collect();
+    }
+    let invertible: Vec<bool> = elements.iter().map(|e| e.value().gcd(m).is_one()).collect();
+    let replaced: Vec<ModInt> = elements
+        .iter()
+        .zip(invertible.iter())
+        .map(|(e, &ok)| if ok { e.clone() } else { ring.one() })
+        .collect();
+    invert_all(&ring, &replaced)
+        .expect("the remaining values are invertible")
+        .into_iter()
+        .zip(invertible)
+        .map(|(x, ok)| if ok { Some(x.into_value()) } else { None })
+        .collect()
+}
+
+/// `(a + b) mod m`. The operands are reduced first unless they are already below `m`,
+/// so the sum before the final subtraction stays below `2m`.
+///
+/// Panics if `m` is zero.
+pub fn mod_add(a: &BigInt, b: &BigInt, m: &BigInt) -> BigInt {
+    let sum = reduce_once(a, m).add(&reduce_once(b, m));
+    if sum >= *m {
+        sum.sub(m)
+    } else {
+        sum
+    }
+}
+
+/// `(a - b) mod m`, in `[0, m)` even when `a < b`. Like `mod_add`, no intermediate value
+/// reaches `2m`.
+///
+/// Panics if `m` is zero.
+pub fn mod_sub(a: &BigInt, b: &BigInt, m: &BigInt) -> BigInt {
+    let (a, b) = (reduce_once(a, m), reduce_once(b, m));
+    if a >= b {
+        a.sub(&b)
+    } else {
+        m.sub(&b).add(&a)
+    }
+}
+
+/// `(a * b) mod m`. The operands are reduced first, so the product divided by `m` is
+/// below `m^2`.
+///
+/// Panics if `m` is zero.
+pub fn mod_mul(a: &BigInt, b: &BigInt, m: &BigInt) -> BigInt {
+    reduce_once(a, m).mul(&reduce_once(b, m)).rem(m)
+}
+
+/// Helper function for `mod_add`, `mod_sub` and `mod_mul`: `x mod m`, without a division
+/// when `x` is already reduced.
+fn reduce_once(x: &BigInt, m: &BigInt) -> BigInt {
+    assert!(!m.is_zero(), "modulus must be nonzero");
+    if x < m {
+        x.clone()
+    } else {
+        x.rem(m)
+    }
+}
+
+/// Helper function for `mod_inverse_batch`: all the inverses, or `None` if one of the
+/// elements is not invertible.
+fn invert_all(ring: &ModRing, elements: &[ModInt]) -> Option<Vec<ModInt>> {
+    let mut prefix = Vec::with_capacity(elements.len());
+    let mut product = ring.one();
+    for e in elements {
+        product = &product * e;
+        prefix.push(product.clone());
+    }
+    // At step i, `inverse` inverts the product of `elements[..=i]`.
+    let mut inverse = product.inv()?;
+    let mut inverses = vec![ring.zero(); elements.len()];
+    for i in (0..elements.len()).rev() {
+        inverses[i] = if i == 0 {
+            inverse.clone()
+        } else {
+            &inverse * &prefix[i - 1]
+        };
+        inverse = &inverse * &elements[i];
+    }
+    Some(inverses)
+}
+
+/// An integer modulo 2^k, wrapping around like a k-bit hardware register.
+///
+/// Operators panic if the operands have different widths.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Wrapping2k {
+    value: BigInt,
+    bits: usize,
+}
+
+impl Wrapping2k {
+    /// The low `bits` bits of `value`.
+    pub fn new(value: &BigInt, bits: usize) -> Wrapping2k {
+        Wrapping2k {
+            value: value.truncate_to(bits),
+            bits,
+        }
+    }
+
+    pub fn value(&self) -> &BigInt {
+        &self.value
+    }
+
+    pub fn into_value(self) -> BigInt {
+        self.value
+    }
+
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    pub fn pow(&self, exponent: u32) -> Wrapping2k {
+        let mut result = Wrapping2k::new(&BigInt::one(), self.bits);
+        for i in (0..32 - exponent.leading_zeros()).rev() {
+            result = &result * &result;
+            if (exponent >> i) & 1 == 1 {
+                result = &result * self;
+            }
+        }
+        result
+    }
+
+    /// 2^k itself.
+    fn modulus(&self) -> BigInt {
+        BigInt::one() << self.bits
+    }
+
+    fn check_same_width(&self, other: &Wrapping2k) {
+        assert_eq!(
+            self.bits, other.bits,
+            "Wrapping2k operands have different widths"
+        );
+    }
+}
+
+impl Add for &Wrapping2k {
+    type Output = Wrapping2k;
+
+    fn add(self, other: &Wrapping2k) -> Wrapping2k {
+        self.check_same_width(other);
+        Wrapping2k::new(&self.value.add(&other.value), self.bits)
+    }
+}
+
+impl Sub for &Wrapping2k {
+    type Output = Wrapping2k;
+
+    fn sub(self, other: &Wrapping2k) -> Wrapping2k {
+        self.check_same_width(other);
+        Wrapping2k::new(
+            &self.value.add(&self.modulus()).sub(&other.value),
+            self.bits,
+        )
+    }
+}
+
+impl Mul for &Wrapping2k {
+    type Output = Wrapping2k;
+
+    fn mul(self, other: &Wrapping2k) -> Wrapping2k {
+        self.check_same_width(other);
+        Wrapping2k::new(&self.value.mul(&other.value), self.bits)
+    }
+}
+
+impl Neg for &Wrapping2k {
+    type Output = Wrapping2k;
+
+    fn neg(self) -> Wrapping2k {
+        &Wrapping2k::new(&BigInt::zero(), self.bits) - self
+    }
+}
+
+forward_owned_binop!(Wrapping2k: Add::add, Sub::sub, Mul::mul);
+
+impl Neg for Wrapping2k {
+    type Output = Wrapping2k;
+
+    fn neg(self) -> Wrapping2k {
+        -&self
+    }
+}
+
+impl fmt::Display for Wrapping2k {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.value, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn ring(m: u64) -> ModRing {
+        ModRing::new(BigInt::from(m))
+    }
+
+    #[test]
+    fn test_element() {
+        let r = ring(7);
+        assert_eq!(BigInt::from(3u32), *r.element(&BigInt::from(17u32)).value());
+        assert_eq!(BigInt::zero(), *r.zero().value());
+        assert_eq!(BigInt::zero(), *ring(1).one().value());
+    }
+
+    #[test]
+    fn test_add_sub_neg() {
+        let r = ring(7);
+        let a = r.element(&BigInt::from(5u32));
+        let b = r.element(&BigInt::from(4u32));
+        assert_eq!(BigInt::from(2u32), *(&a + &b).value());
+        assert_eq!(BigInt::from(1u32), *(&a - &b).value());
+        assert_eq!(BigInt::from(6u32), *(&b - &a).value());
+        assert_eq!(BigInt::from(2u32), *(-a.clone()).value());
+        assert_eq!(r.zero(), a.clone() + -a);
+    }
+
+    #[test]
+    fn test_mul() {
+        let m = BigInt::from_string("170141183460469231731687303715884105727");
+        let r = ModRing::new(m.clone());
+        let a = BigInt::from_string("123456789012345678901234567890123456789");
+        let b = BigInt::from_string("98765432109876543210987654321098765432");
+        let expected = a.mul(&b).rem(&m);
+        assert_eq!(expected, (r.element(&a) * r.element(&b)).into_value());
+        for x in 0..50u32 {
+            for y in 0..50u32 {
+                let r = ring(47);
+                let p = r.element(&BigInt::from(x)) * r.element(&BigInt::from(y));
+                assert_eq!(BigInt::from(x * y % 47), p.into_value());
+            }
+        }
+    }
+
+    #[test]
+    fn test_pseudo_mersenne() {
+        let p25519 = (BigInt::one() << 255).sub(&BigInt::from(19u32));
+        let r = ModRing::new(p25519.clone());
+        assert_eq!(Some(&BigInt::from(19u32)), r.pseudo_mersenne_offset());
+        let a = p25519.sub(&BigInt::from(12345u32));
+        let b = BigInt::from_string("98765432109876543210987654321098765432109876543210");
+        assert_eq!(
+            a.mul(&a).rem(&p25519),
+            (r.element(&a) * r.element(&a)).into_value()
+        );
+        assert_eq!(
+            a.mul(&b).rem(&p25519),
+            (r.element(&a) * r.element(&b)).into_value()
+        );
+        let x = r.element(&BigInt::from(9u32));
+        assert_eq!(r.one(), x.pow(&p25519.sub(&BigInt::one())));
+
+        assert_eq!(Some(&BigInt::one()), ring(127).pseudo_mersenne_offset());
+        assert_eq!(None, ring(1_000_000_007).pseudo_mersenne_offset());
+        assert_eq!(None, ring(1).pseudo_mersenne_offset());
+        for m in [3u64, 5, 7, 13, 15, 61, 127, 251, 4093] {
+            let r = ring(m);
+            for x in 0..m {
+                for y in [0, 1, x / 2, m - 1] {
+                    let p = r.element(&BigInt::from(x)) * r.element(&BigInt::from(y));
+                    assert_eq!(BigInt::from(x * y % m), p.into_value(), "{}", m);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ring_cache() {
+        let mut cache = RingCache::new(2);
+        assert!(cache.is_empty());
+        let seven = cache.get(&BigInt::from(7u32));
+        let again = cache.get(&BigInt::from(7u32));
+        assert!(Arc::ptr_eq(&seven.context, &again.context));
+        assert_eq!(1, cache.len());
+        cache.get(&BigInt::from(11u32));
+        // 7 is the least recently used, so 13 replaces it.
+        let eleven = cache.get(&BigInt::from(11u32));
+        cache.get(&BigInt::from(13u32));
+        assert_eq!(2, cache.len());
+        assert!(Arc::ptr_eq(
+            &eleven.context,
+            &cache.get(&BigInt::from(11u32)).context
+        ));
+        assert!(!Arc::ptr_eq(
+            &seven.context,
+            &cache.get(&BigInt::from(7u32)).context
+        ));
+        cache.clear();
+        assert!(cache.is_empty());
+
+        let m = BigInt::from(1_000_000_007u32);
+        let ring = ModRing::cached(&m);
+        assert_eq!(&m, ring.modulus());
+        assert_eq!(ring, ModRing::cached(&m));
+        let x = ModRing::cached(&m)
+            .element(&BigInt::two())
+            .pow(&BigInt::from(100u32));
+        assert_eq!(
+            BigInt::two().modpow(&BigInt::from(100u32), &m),
+            x.into_value()
+        );
+    }
+
+    #[test]
+    fn test_pow_inv() {
+        let m = BigInt::from_string("170141183460469231731687303715884105727");
+        let r = ModRing::new(m.clone());
+        let a = r.element(&BigInt::from(3u32));
+        // Fermat's little theorem, the modulus being the Mersenne prime 2^127 - 1.
+        assert_eq!(r.one(), a.pow(&m.sub(&BigInt::one())));
+        assert_eq!(r.one(), a.pow(&BigInt::zero()));
+        let inverse = a.inv().unwrap();
+        assert_eq!(r.one(), &a * &inverse);
+        assert_eq!(a.pow(&m.sub(&BigInt::two())), inverse);
+
+        let r = ring(12);
+        assert_eq!(None, r.element(&BigInt::from(8u32)).inv());
+        assert_eq!(
+            BigInt::from(7u32),
+            r.element(&BigInt::from(7u32)).inv().unwrap().into_value()
+        );
+        assert_eq!(None, r.zero().inv());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_different_rings() {
+        let _ = ring(7).one() + ring(11).one();
+    }
+
+    #[test]
+    fn test_modpow_mod_inverse() {
+        let m = BigInt::from(1_000_000_007u32);
+        assert_eq!(
+            BigInt::from(976371285u32),
+            BigInt::two().modpow(&BigInt::from(100u32), &m)
+        );
+        assert_eq!(
+            BigInt::zero(),
+            BigInt::from(7u32).modpow(&BigInt::from(3u32), &BigInt::one())
+        );
+        assert_eq!(BigInt::one(), BigInt::zero().modpow(&BigInt::zero(), &m));
+        assert_eq!(
+            Some(BigInt::from(4u32)),
+            BigInt::from(3u32).mod_inverse(&BigInt::from(11u32))
+        );
+        assert_eq!(None, BigInt::from(6u32).mod_inverse(&BigInt::from(9u32)));
+    }
+
+    #[test]
+    fn test_montgomery_pow() {
+        let base = BigInt::from_string("123456789012345678901234567890123456789");
+        let exponent = BigInt::from_string("98765432109876543210");
+        let moduli = [
+            BigInt::from(3u32),
+            BigInt::from(u32::MAX),
+            BigInt::from(u64::MAX),
+            BigInt::from(1000003u32),
+            (BigInt::one() << 127).sub(&BigInt::one()),
+            base.add(&BigInt::two()),
+            base.mul(&base).add(&BigInt::two()),
+        ];
+        for m in moduli.iter() {
+            let ring = ModRing::new(m.clone());
+            for b in [base.clone(), BigInt::zero(), m.sub(&BigInt::one())].iter() {
+                let expected = ring.element(b).pow(&exponent);
+                assert_eq!(*expected.value(), montgomery_pow(b, &exponent, m), "{}", m);
+            }
+            assert_eq!(BigInt::one(), montgomery_pow(&base, &BigInt::zero(), m));
+        }
+    }
+
+    #[test]
+    fn test_modpow_even_modulus() {
+        let base = BigInt::from_string("123456789012345678901234567890");
+        let exponent = BigInt::from_string("98765432109876543210");
+        let moduli = [
+            BigInt::two(),
+            BigInt::from(12u32),
+            BigInt::from(1u32 << 20),
+            BigInt::from(1000000u32),
+            (BigInt::one() << 130).mul(&BigInt::from(1000003u32)),
+            BigInt::from_string("340282366920938463463374607431768211456"),
+            BigInt::from_string("340282366920938463463374607431768211458"),
+        ];
+        for m in moduli.iter() {
+            let expected = ModRing::new(m.clone()).element(&base).pow(&exponent);
+            assert_eq!(*expected.value(), base.modpow(&exponent, m), "{}", m);
+            let even_base = base.add(&BigInt::one());
+            let expected = ModRing::new(m.clone()).element(&even_base).pow(&exponent);
+            assert_eq!(*expected.value(), even_base.modpow(&exponent, m), "{}", m);
+            assert_eq!(BigInt::one(), base.modpow(&BigInt::zero(), m));
+        }
+        for x in 0..40u32 {
+            for e in 0..12u32 {
+                let expected = BigInt::from(u64::from(x).pow(e) % 48);
+                assert_eq!(
+                    expected,
+                    BigInt::from(x).modpow(&BigInt::from(e), &BigInt::from(48u32))
+                );
+            }
+        }
+        let odd = base.add(&base).add(&BigInt::one());
+        for k in 1..70 {
+            let product = &inverse_2k(&odd, k) * &Wrapping2k::new(&odd, k);
+            assert!(product.value().is_one(), "{}", k);
+        }
+    }
+
+    #[test]
+    fn test_signed_modpow() {
+        let int = |n: i64| signed::BigInt::from(n);
+        let m = BigInt::from(11u32);
+        assert_eq!(Ok(BigInt::from(4u32)), int(3).modpow(&int(-1), &m));
+        assert_eq!(Ok(BigInt::from(5u32)), int(3).modpow(&int(-2), &m));
+        assert_eq!(Ok(BigInt::from(9u32)), int(3).modpow(&int(2), &m));
+        assert_eq!(Ok(BigInt::from(9u32)), int(-3).modpow(&int(2), &m));
+        assert_eq!(Ok(BigInt::from(6u32)), int(-3).modpow(&int(3), &m));
+        assert_eq!(Ok(BigInt::from(7u32)), int(-3).modpow(&int(-1), &m));
+        assert_eq!(Ok(BigInt::one()), int(0).modpow(&int(0), &m));
+        assert_eq!(Err(NotInvertibleError), int(22).modpow(&int(-1), &m));
+        assert_eq!(
+            Err(NotInvertibleError),
+            int(6).modpow(&int(-3), &BigInt::from(9u32))
+        );
+        assert_eq!(
+            Ok(BigInt::zero()),
+            int(6).modpow(&int(3), &BigInt::from(9u32))
+        );
+        assert_eq!(
+            "value is not invertible modulo the modulus",
+            NotInvertibleError.to_string()
+        );
+
+        let p = BigInt::from_string("170141183460469231731687303715884105727");
+        let a = int(-123456789);
+        let inverse = a.modpow(&int(-5), &p).unwrap();
+        let power = a.modpow(&int(5), &p).unwrap();
+        assert_eq!(BigInt::one(), inverse.mul(&power).rem(&p));
+    }
+
+    #[test]
+    fn test_mod_inverse_batch() {
+        let m = BigInt::from(1000003u32);
+        let values: Vec<BigInt> = (1..500u32).map(|v| BigInt::from(v * 7919)).collect();
+        let inverses = mod_inverse_batch(&values, &m);
+        for (v, inverse) in values.iter().zip(inverses.iter()) {
+            assert_eq!(v.mod_inverse(&m), *inverse);
+        }
+
+        // Values sharing a factor with the modulus, zero among them.
+        let m = BigInt::from(360u32);
+        let values: Vec<BigInt> = (0..100u32).map(BigInt::from).collect();
+        let inverses = mod_inverse_batch(&values, &m);
+        for (v, inverse) in values.iter().zip(inverses.iter()) {
+            assert_eq!(v.mod_inverse(&m), *inverse, "{}", v);
+        }
+        assert!(mod_inverse_batch(&[], &m).is_empty());
+    }
+
+    #[test]
+    fn test_mod_add_sub_mul() {
+        let m = BigInt::from_string("340282366920938463463374607431768211507");
+        let values = [
+            BigInt::zero(),
+            BigInt::one(),
+            m.sub(&BigInt::one()),
+            m.clone(),
+            m.mul(&BigInt::from(3u32)).add(&BigInt::from(5u32)),
+            BigInt::from_string("123456789012345678901234567890123456789012"),
+        ];
+        let ring = ModRing::new(m.clone());
+        for a in values.iter() {
+            for b in values.iter() {
+                let (x, y) = (ring.element(a), ring.element(b));
+                assert_eq!((&x + &y).into_value(), mod_add(a, b, &m));
+                assert_eq!((&x - &y).into_value(), mod_sub(a, b, &m));
+                assert_eq!((&x * &y).into_value(), mod_mul(a, b, &m));
+            }
+        }
+        let one = BigInt::one();
+        assert_eq!(
+            BigInt::zero(),
+            mod_add(&BigInt::from(5u32), &one, &BigInt::two().add(&one))
+        );
+        assert_eq!(BigInt::zero(), mod_sub(&one, &one, &one));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mod_add_zero_modulus() {
+        mod_add(&BigInt::one(), &BigInt::one(), &BigInt::zero());
+    }
+
+    #[test]
+    fn test_wrapping2k() {
+        let max = Wrapping2k::new(&(BigInt::one() << 320).sub(&BigInt::one()), 320);
+        let one = Wrapping2k::new(&BigInt::one(), 320);
+        assert_eq!(BigInt::zero(), *(&max + &one).value());
+        assert_eq!(max, Wrapping2k::new(&BigInt::zero(), 320) - one.clone());
+        assert_eq!(max, -one.clone());
+        assert_eq!(one, &max * &max);
+        assert_eq!(
+            BigInt::from(3u32),
+            *Wrapping2k::new(&BigInt::from(11u32), 3).value()
+        );
+        let three = Wrapping2k::new(&BigInt::from(3u32), 8);
+        assert_eq!(BigInt::from(3u32.pow(5) % 256), three.pow(5).into_value());
+        assert_eq!(BigInt::one(), three.pow(0).into_value());
+        assert_eq!(
+            BigInt::zero(),
+            *Wrapping2k::new(&BigInt::from(5u32), 0).value()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_wrapping2k_different_widths() {
+        let _ = Wrapping2k::new(&BigInt::one(), 8) + Wrapping2k::new(&BigInt::one(), 16);
+    }
+}
@@ -0,0 +1,110 @@
+//! A hook for offloading the multiplication of very large operands to another
+//! implementation, such as a GPU, behind the `mul-backend` feature.
+//!
+//! The crate ships no such backend: a `MulBackend` registered with `set_mul_backend` is
+//! tried by `optimized_memory::BigInt::mul` whenever both operands have at least
+//! `min_limbs` limbs, and the built-in multiplication runs below that size or when the
+//! backend declines.
+
+use crate::optimized_memory::BigInt;
+use std::sync::{Arc, RwLock};
+
+/// An implementation of the product of two limb slices, little-endian in base 2^32.
+pub trait MulBackend: Send + Sync {
+    /// The smallest operands, in limbs, worth handing to the backend.
+    fn min_limbs(&self) -> usize;
+
+    /// The limbs of `a * b`, possibly with trailing zeros, or `None` to fall back to the
+    /// built-in multiplication, for instance when the device is unavailable.
+    fn mul(&self, a: &[u32], b: &[u32]) -> Option<Vec<u32>>;
+}
+
+/// The backend registered with `set_mul_backend`.
+static BACKEND: RwLock<Option<Arc<dyn MulBackend>>> = RwLock::new(None);
+
+/// Register the backend used by every later multiplication, or remove it with `None`.
+pub fn set_mul_backend(backend: Option<Arc<dyn MulBackend>>) {
+    *BACKEND.write().unwrap() = backend;
+}
+
+/// Helper function for `BigInt::mul`: the product computed by the registered backend, if
+/// there is one, the operands are large enough and it accepts them.
+pub(crate) fn offload(a: &[u32], b: &[u32]) -> Option<BigInt> {
+    let backend = BACKEND.read().unwrap().clone()?;
+    if a.len().min(b.len()) < backend.min_limbs() {
+        return None;
+    }
+    let product = backend.mul(a, b)?;
+    #[cfg(feature = "tracing")]
+    crate::stats::record_mul(true, a.len(), b.len());
+    Some(BigInt::from_limbs_le(&product))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Schoolbook multiplication that counts its calls, and the calls with operands below
+    /// `min_limbs`, and declines when asked to.
+    struct Counting {
+        calls: AtomicUsize,
+        undersized: AtomicUsize,
+        decline: bool,
+    }
+
+    impl MulBackend for Counting {
+        fn min_limbs(&self) -> usize {
+            8
+        }
+
+        fn mul(&self, a: &[u32], b: &[u32]) -> Option<Vec<u32>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if a.len().min(b.len()) < self.min_limbs() {
+                self.undersized.fetch_add(1, Ordering::SeqCst);
+            }
+            if self.decline {
+                return None;
+            }
+            let mut product = vec![0u32; a.len() + b.len() + 1];
+            for (i, &x) in a.iter().enumerate() {
+                let mut carry = 0u64;
+                for (j, &y) in b.iter().enumerate() {
+                    let t = product[i + j] as u64 + x as u64 * y as u64 + carry;
+                    product[i + j] = t as u32;
+                    carry = t >> 32;
+                }
+                product[i + b.len()] = carry as u32;
+            }
+            Some(product)
+        }
+    }
+
+    #[test]
+    fn test_mul_backend() {
+        let small = BigInt::from(u64::MAX);
+        let large = (BigInt::one() << 400).sub(&BigInt::one());
+        let expected = (BigInt::one() << 800)
+            .sub(&(BigInt::one() << 401))
+            .add(&BigInt::one());
+
+        for &decline in [false, true].iter() {
+            let backend = Arc::new(Counting {
+                calls: AtomicUsize::new(0),
+                undersized: AtomicUsize::new(0),
+                decline,
+            });
+            set_mul_backend(Some(backend.clone()));
+            let product = large.mul(&large);
+            let mixed = large.mul(&small);
+            set_mul_backend(None);
+
+            assert_eq!(expected, product);
+            assert_eq!(large.mul(&small), mixed);
+            // Other tests running meanwhile may go through the backend too.
+            assert!(backend.calls.load(Ordering::SeqCst) >= 1);
+            assert_eq!(0, backend.undersized.load(Ordering::SeqCst));
+        }
+    }
+}
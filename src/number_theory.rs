@@ -0,0 +1,646 @@
+//! Elementary number theory: quadratic residue symbols and friends.
+
+use crate::modular::ModRing;
+use crate::optimized_memory::BigInt;
+use crate::signed;
+use std::collections::HashMap;
+
+/// The Jacobi symbol `(a/n)`, by the binary algorithm: factors of two are shifted out
+/// of `a` and the larger odd value is reduced by subtraction, so there is no division
+/// after the first reduction.
+///
+/// Panics if `n` is even.
+pub fn jacobi(a: &BigInt, n: &BigInt) -> i32 {
+    assert!(n.is_odd(), "the Jacobi symbol needs an odd modulus");
+    let low_bits = |x: &BigInt| x.as_limbs_le().first().map_or(0, |&l| l & 7);
+    let mut a = a.rem(n);
+    let mut n = n.clone();
+    let mut result = 1;
+    while !a.is_zero() {
+        // (2/n) is -1 exactly when n is 3 or 5 modulo 8.
+        let zeros = a.trailing_zeros();
+        a = a >> zeros;
+        if zeros % 2 == 1 && matches!(low_bits(&n), 3 | 5) {
+            result = -result;
+        }
+        // Reciprocity for odd a and n: the sign flips when both are 3 modulo 4.
+        if a < n {
+            std::mem::swap(&mut a, &mut n);
+            if low_bits(&a) & 3 == 3 && low_bits(&n) & 3 == 3 {
+                result = -result;
+            }
+        }
+        a = a.sub(&n);
+    }
+    if n.is_one() {
+        result
+    } else {
+        0
+    }
+}
+
+/// The Legendre symbol `(a/p)` for an odd prime `p`: 0 if `p` divides `a`, 1 if `a` is a
+/// quadratic residue modulo `p` and -1 otherwise. The primality of `p` is not checked; for
+/// a composite `p` this is the Jacobi symbol.
+///
+/// Panics if `p` is even.
+pub fn legendre(a: &BigInt, p: &BigInt) -> i32 {
+    jacobi(a, p)
+}
+
+/// The Kronecker symbol `(a/n)`, the extension of the Jacobi symbol to every integer `n`
+/// with `(a/2)` depending on `a` modulo 8, `(a/-1)` on the sign of `a`, and `(a/0)` being
+/// 1 only for `a = 1` and `a = -1`.
+pub fn kronecker(a: &signed::BigInt, n: &signed::BigInt) -> i32 {
+    let magnitude = n.magnitude();
+    if magnitude.is_zero() {
+        return if a.magnitude().is_one() { 1 } else { 0 };
+    }
+    let mut result = if n.is_negative() && a.is_negative() {
+        -1
+    } else {
+        1
+    };
+    let zeros = magnitude.trailing_zeros();
+    if zeros > 0 {
+        let a_mod_8 = a.rem_euclid(&signed::BigInt::from(8)).into_magnitude();
+        match a_mod_8.to_u32().unwrap() {
+            3 | 5 if zeros % 2 == 1 => result = -result,
+            1 | 3 | 5 | 7 => {}
+            _ => return 0,
+        }
+    }
+    let odd = magnitude.clone() >> zeros;
+    let a = a
+        .rem_euclid(&signed::BigInt::from(odd.clone()))
+        .into_magnitude();
+    result * jacobi(&a, &odd)
+}
+
+/// The solution of the congruences `x = r (mod m)` for the pairs `(r, m)`, as `(x, l)`
+/// with `l` the least common multiple of the moduli and `0 <= x < l`, or `None` if they
+/// are inconsistent. The moduli need not be coprime: two congruences agree when their
+/// residues are congruent modulo the gcd of the moduli.
+///
+/// No congruence at all gives `(0, 1)`. Panics if a modulus is zero.
+pub fn crt(residues: &[(BigInt, BigInt)]) -> Option<(BigInt, BigInt)> {
+    let mut x = BigInt::zero();
+    let mut l = BigInt::one();
+    for (r, m) in residues {
+        assert!(!m.is_zero(), "moduli must be nonzero");
+        let r = r.rem(m);
+        // x + l k = r (mod m) is solvable when g = gcd(l, m) divides r - x.
+        let g = l.gcd(m);
+        let difference = r.add(m).sub(&x.rem(m)).rem(m);
+        if !difference.rem(&g).is_zero() {
+            return None;
+        }
+        let m_g = m.div(&g);
+        let k = match l.div(&g).mod_inverse(&m_g) {
+            Some(inverse) => difference.div(&g).mul(&inverse).rem(&m_g),
+            // Only when m divides l.
+            None => BigInt::zero(),
+        };
+        x = x.add(&l.mul(&k));
+        l = l.mul(&m_g);
+    }
+    Some((x, l))
+}
+
+/// The fraction `n / d` with `n = a d (mod m)`, `|n|` and `d` at most `sqrt(m / 2)`, `d`
+/// positive and `gcd(n, d) = 1`; it is unique when it exists. Wang's algorithm: the
+/// extended Euclid on `m` and `a` stops at the first remainder within the bound, and the
+/// cofactor of `a` there is the candidate denominator.
+///
+/// Panics if `m` is zero.
+pub fn rational_reconstruct(a: &BigInt, m: &BigInt) -> Option<(signed::BigInt, BigInt)> {
+    assert!(!m.is_zero(), "modulus must be nonzero");
+    let bound = m.div(&BigInt::two()).sqrt();
+    // The invariant is r = t a (mod m) for both rows.
+    let (mut r0, mut r1) = (m.clone(), a.rem(m));
+    let (mut t0, mut t1) = (signed::BigInt::zero(), signed::BigInt::one());
+    while r1 > bound {
+        let (q, r) = r0.div_rem(&r1);
+        r0 = std::mem::replace(&mut r1, r);
+        let t = t0.sub(&signed::BigInt::from(q).mul(&t1));
+        t0 = std::mem::replace(&mut t1, t);
+    }
+    if *t1.magnitude() > bound || !r1.gcd(t1.magnitude()).is_one() {
+        return None;
+    }
+    let n = signed::BigInt::new(t1.is_negative(), r1);
+    Some((n, t1.into_magnitude()))
+}
+
+/// The smallest `x` with `base^x = target (mod modulus)`, or `None` if there is none.
+///
+/// This is `discrete_log_below` with the modulus as the bound, so it needs memory for
+/// about `sqrt(modulus)` values: pass a bound such as the order of `base` for anything
+/// beyond 40 bits or so.
+///
+/// Panics if `base` is not coprime with `modulus`.
+pub fn discrete_log(base: &BigInt, target: &BigInt, modulus: &BigInt) -> Option<BigInt> {
+    discrete_log_below(base, target, modulus, modulus)
+}
+
+/// The smallest `x < bound` with `base^x = target (mod modulus)`, by baby-step giant-step:
+/// the powers `base^j` for `j < n = ceil(sqrt(bound))` go into a hash map, then
+/// `target * base^(-n i)` is looked up for `i = 0, 1, ...`.
+///
+/// Panics if `base` is not coprime with `modulus` or if `sqrt(bound)` does not fit in a
+/// `usize`.
+pub fn discrete_log_below(
+    base: &BigInt,
+    target: &BigInt,
+    modulus: &BigInt,
+    bound: &BigInt,
+) -> Option<BigInt> {
+    let ring = ModRing::new(modulus.clone());
+    let base = ring.element(base);
+    let target = ring.element(target);
+    let inverse = base.inv().expect("base must be coprime with the modulus");
+    let mut n = bound.sqrt();
+    if n.mul(&n) < *bound {
+        n = n.add(&BigInt::one());
+    }
+    let steps = n.to_usize().expect("bound too large for the baby steps");
+
+    let mut baby = HashMap::with_capacity(steps);
+    let mut power = ring.one();
+    for j in 0..steps {
+        baby.entry(power.value().clone()).or_insert(j);
+        power = &power * &base;
+    }
+    let giant = inverse.pow(&n);
+    let mut gamma = target;
+    for i in 0..steps {
+        if let Some(&j) = baby.get(gamma.value()) {
+            let x = BigInt::from(i).mul(&n).add(&BigInt::from(j));
+            return Some(x).filter(|x| x < bound);
+        }
+        gamma = &gamma * &giant;
+    }
+    None
+}
+
+/// The smallest `k >= 1` with `a^k = 1 (mod n)`, or `None` if `a` is not coprime with
+/// `n`.
+///
+/// The order divides Carmichael's `lambda(n)`, so it is found by dividing the prime
+/// factors out of `lambda(n)` while the power stays 1.
+///
+/// Panics if `n` is zero or if `n` or `lambda(n)` cannot be factored completely.
+pub fn multiplicative_order(a: &BigInt, n: &BigInt) -> Option<BigInt> {
+    assert!(!n.is_zero(), "modulus must be nonzero");
+    if !a.gcd(n).is_one() {
+        return None;
+    }
+    let mut order = n.carmichael_lambda();
+    for (q, _) in order.factor().factors().clone() {
+        while order.rem(&q).is_zero() && a.modpow(&order.div(&q), n).is_one() {
+            order = order.div(&q);
+        }
+    }
+    Some(order)
+}
+
+/// The smallest primitive root modulo `n`, a generator of the multiplicative group, or
+/// `None` when the group is not cyclic. Primitive roots exist for `n = 1, 2, 4, p^k` and
+/// `2 p^k` with `p` an odd prime, in particular for every prime.
+///
+/// Panics if `n` is zero or if `n` or `phi(n)` cannot be factored completely.
+pub fn primitive_root(n: &BigInt) -> Option<BigInt> {
+    assert!(!n.is_zero(), "modulus must be nonzero");
+    if n.is_one() {
+        return Some(BigInt::zero());
+    }
+    let factorization = n.factor();
+    let phi = factorization.euler_phi();
+    if factorization.carmichael_lambda() != phi {
+        return None;
+    }
+    let cofactors: Vec<BigInt> = phi.factor().factors().keys().map(|q| phi.div(q)).collect();
+    // g generates the group when no g^(phi / q) is 1.
+    (1u32..)
+        .map(BigInt::from)
+        .filter(|g| g.gcd(n).is_one())
+        .find(|g| cofactors.iter().all(|c| !g.modpow(c, n).is_one()))
+}
+
+impl BigInt {
+    /// `(base, k)` with `base^k` equal to the value and `k > 1` as large as possible, or
+    /// `None` if the value is not a perfect power. 0 and 1 are powers of everything and
+    /// give `None` as well.
+    ///
+    /// Every exponent up to `log2` of the value is tried from the top, by extracting the
+    /// `k`-th root.
+    pub fn as_perfect_power(&self) -> Option<(BigInt, u32)> {
+        if *self < 4u32 {
+            return None;
+        }
+        let max_exponent = self.ilog2() as u32;
+        (2..=max_exponent).rev().find_map(|k| {
+            let root = self.nth_root(k);
+            if root.pow(k) == *self {
+                Some((root, k))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// A square root modulo the prime `p`, or `None` if the value is not a quadratic
+    /// residue. Of the two roots `r` and `p - r` the smaller one is returned.
+    ///
+    /// For `p = 3 (mod 4)` the root is `a^((p+1)/4)`; otherwise Tonelli-Shanks works up
+    /// the 2-power part of `p - 1`. If `p` is not prime the answer may be `None` even for
+    /// squares, but a returned value is always a root.
+    ///
+    /// Panics if `p` is zero.
+    pub fn sqrt_mod(&self, p: &BigInt) -> Option<BigInt> {
+        let a = self.rem(p);
+        if a.is_zero() || *p == 2u32 {
+            return Some(a);
+        }
+        if p.is_even() || jacobi(&a, p) != 1 {
+            return None;
+        }
+        let ring = ModRing::new(p.clone());
+        let a = ring.element(&a);
+        let minus_one = p.sub(&BigInt::one());
+        let root = if p.bit(1) {
+            a.pow(&(p.add(&BigInt::one()) >> 2))
+        } else {
+            let s = minus_one.trailing_zeros();
+            let q = minus_one.clone() >> s;
+            // A non-residue z; its power z^q has order exactly 2^s.
+            let z = (2u32..)
+                .map(BigInt::from)
+                .take_while(|z| z < p)
+                .find(|z| jacobi(z, p) == -1)?;
+            let mut c = ring.element(&z).pow(&q);
+            let mut t = a.pow(&q);
+            let mut r = a.pow(&(q.add(&BigInt::one()) >> 1));
+            // r^2 = a t, and the order of t keeps halving.
+            let mut m = s;
+            while !t.value().is_one() {
+                let mut i = 0;
+                let mut square = t.clone();
+                while !square.value().is_one() {
+                    square = &square * &square;
+                    i += 1;
+                    if i == m {
+                        return None;
+                    }
+                }
+                let mut b = c;
+                for _ in 0..m - i - 1 {
+                    b = &b * &b;
+                }
+                m = i;
+                c = &b * &b;
+                t = &t * &c;
+                r = &r * &b;
+            }
+            r
+        };
+        if &root * &root != a {
+            return None;
+        }
+        let root = root.into_value();
+        let other = p.sub(&root);
+        Some(root.min(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_jacobi() {
+        // (2/n) depends on n mod 8, and (a/n) is 0 when they share a factor.
+        let values = [
+            (2u32, 7u32, 1),
+            (2, 5, -1),
+            (5, 9, 1),
+            (3, 15, 0),
+            (1001, 9907, -1),
+            (0, 1, 1),
+            (0, 3, 0),
+        ];
+        for &(a, n, expected) in values.iter() {
+            assert_eq!(expected, jacobi(&BigInt::from(a), &BigInt::from(n)));
+        }
+    }
+
+    #[test]
+    fn test_legendre() {
+        // Euler's criterion: a^((p-1)/2) is 1 for residues and p - 1 for the others.
+        let p = BigInt::from(1000003u32);
+        let half = p.sub(&BigInt::one()) >> 1;
+        for a in (0..2000u32).step_by(7) {
+            let a = BigInt::from(a);
+            let euler = match a.modpow(&half, &p) {
+                x if x.is_zero() => 0,
+                x if x.is_one() => 1,
+                _ => -1,
+            };
+            assert_eq!(euler, legendre(&a, &p), "{}", a);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_jacobi_even() {
+        jacobi(&BigInt::one(), &BigInt::two());
+    }
+
+    #[test]
+    fn test_kronecker() {
+        let k = |a: i64, n: i64| kronecker(&signed::BigInt::from(a), &signed::BigInt::from(n));
+        // The special cases of n = 0, 2 and -1, then products.
+        assert_eq!(1, k(1, 0));
+        assert_eq!(1, k(-1, 0));
+        assert_eq!(0, k(2, 0));
+        assert_eq!(-1, k(3, 2));
+        assert_eq!(1, k(7, 2));
+        assert_eq!(1, k(3, 4));
+        assert_eq!(0, k(6, 2));
+        assert_eq!(-1, k(-1, -1));
+        assert_eq!(1, k(1, -1));
+        assert_eq!(-1, k(-1, 3));
+        assert_eq!(1, k(-1, 5));
+        assert_eq!(1, k(5, 6));
+        assert_eq!(1, k(-5, -6));
+        // It agrees with the Jacobi symbol for positive odd n.
+        for n in (1..60).step_by(2) {
+            for a in 0..60 {
+                assert_eq!(
+                    jacobi(&BigInt::from(a as u32), &BigInt::from(n as u32)),
+                    k(a, n)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqrt_mod() {
+        let primes = [
+            BigInt::from(3u32),
+            BigInt::from(13u32),
+            BigInt::from(17u32),
+            BigInt::from(1000003u32),
+            // 119 * 2^23 + 1, with a long chain of square roots of unity.
+            BigInt::from(998244353u32),
+            (BigInt::one() << 127).sub(&BigInt::one()),
+        ];
+        for p in primes.iter() {
+            for a in (0..300u32).map(BigInt::from) {
+                match a.sqrt_mod(p) {
+                    Some(r) => {
+                        let r_squared = r.mul(&r).rem(p);
+                        assert_eq!(a.rem(p), r_squared, "{} mod {}", a, p);
+                        assert!(r.add(&r) <= *p);
+                    }
+                    None => assert_eq!(-1, legendre(&a, p), "{} mod {}", a, p),
+                }
+            }
+        }
+        assert_eq!(
+            Some(BigInt::one()),
+            BigInt::from(5u32).sqrt_mod(&BigInt::two())
+        );
+        assert_eq!(
+            Some(BigInt::zero()),
+            BigInt::from(26u32).sqrt_mod(&BigInt::from(13u32))
+        );
+        assert_eq!(
+            Some(BigInt::from(6u32)),
+            BigInt::from(2u32).sqrt_mod(&BigInt::from(17u32))
+        );
+    }
+
+    #[test]
+    fn test_crt() {
+        let pairs = |v: &[(u32, u32)]| -> Vec<(BigInt, BigInt)> {
+            v.iter()
+                .map(|&(r, m)| (BigInt::from(r), BigInt::from(m)))
+                .collect()
+        };
+        let solution = |x: u32, l: u32| Some((BigInt::from(x), BigInt::from(l)));
+        assert_eq!(solution(23, 105), crt(&pairs(&[(2, 3), (3, 5), (2, 7)])));
+        assert_eq!(solution(0, 1), crt(&[]));
+        assert_eq!(solution(2, 5), crt(&pairs(&[(12, 5)])));
+        // Non-coprime moduli, consistent or not.
+        assert_eq!(solution(10, 12), crt(&pairs(&[(4, 6), (2, 4)])));
+        assert_eq!(None, crt(&pairs(&[(1, 6), (2, 4)])));
+        assert_eq!(solution(5, 12), crt(&pairs(&[(5, 12), (1, 4), (2, 3)])));
+        assert_eq!(None, crt(&pairs(&[(5, 12), (2, 4)])));
+
+        // Reassemble a large value from its residues modulo a few primes.
+        let value = BigInt::from_string("12345678901234567890123456");
+        let moduli = [1000000007u32, 998244353, 1000003, 65537];
+        let residues: Vec<(BigInt, BigInt)> = moduli
+            .iter()
+            .map(|&m| (value.rem(&BigInt::from(m)), BigInt::from(m)))
+            .collect();
+        let (x, l) = crt(&residues).unwrap();
+        assert_eq!(value, x);
+        assert_eq!(
+            moduli.iter().map(|&m| BigInt::from(m)).product::<BigInt>(),
+            l
+        );
+    }
+
+    #[test]
+    fn test_rational_reconstruct() {
+        let m = BigInt::from(1_000_000_007u32);
+        for (n, d) in [
+            (1i64, 3u32),
+            (-22, 7),
+            (355, 113),
+            (0, 1),
+            (-1, 1),
+            (12345, 6788),
+        ] {
+            let image = signed::BigInt::from(n)
+                .rem_euclid(&signed::BigInt::from(m.clone()))
+                .into_magnitude()
+                .mul(&BigInt::from(d).mod_inverse(&m).unwrap())
+                .rem(&m);
+            assert_eq!(
+                Some((signed::BigInt::from(n), BigInt::from(d))),
+                rational_reconstruct(&image, &m),
+                "{}/{}",
+                n,
+                d
+            );
+        }
+        // Modulo 13 the bound is 2: 7 is 1/2, but 5 is no such fraction.
+        let thirteen = BigInt::from(13u32);
+        assert_eq!(
+            Some((signed::BigInt::one(), BigInt::two())),
+            rational_reconstruct(&BigInt::from(7u32), &thirteen)
+        );
+        for a in [3u32, 4, 5, 8, 9, 10] {
+            assert_eq!(None, rational_reconstruct(&BigInt::from(a), &thirteen));
+        }
+        let large = BigInt::from_string("170141183460469231731687303715884105727");
+        let fraction = BigInt::from(987654321u32)
+            .mul(&BigInt::from(123456789u32).mod_inverse(&large).unwrap())
+            .rem(&large);
+        assert_eq!(
+            Some((
+                signed::BigInt::from(109739369i64),
+                BigInt::from(13717421u32)
+            )),
+            rational_reconstruct(&fraction, &large)
+        );
+    }
+
+    #[test]
+    fn test_discrete_log() {
+        // Against the powers themselves, for prime and composite moduli.
+        for &m in [7u32, 11, 15, 101, 1000].iter() {
+            for &b in [2u32, 3, 7]
+                .iter()
+                .filter(|&&b| BigInt::from(b).gcd(&BigInt::from(m)).is_one())
+            {
+                let mut first = HashMap::new();
+                let mut power = 1 % m;
+                for x in 0..m {
+                    first.entry(power).or_insert(x);
+                    power = power * b % m;
+                }
+                for t in 0..m {
+                    let expected = first.get(&t).map(|&x| BigInt::from(x));
+                    let (b, t, m) = (BigInt::from(b), BigInt::from(t), BigInt::from(m));
+                    assert_eq!(
+                        expected,
+                        discrete_log(&b, &t, &m),
+                        "{}^x = {} mod {}",
+                        b,
+                        t,
+                        m
+                    );
+                }
+            }
+        }
+
+        let mersenne_61 = (BigInt::one() << 61).sub(&BigInt::one());
+        let three = BigInt::from(3u32);
+        let target = three.modpow(&BigInt::from(123456u32), &mersenne_61);
+        let bound = BigInt::one() << 20;
+        assert_eq!(
+            Some(BigInt::from(123456u32)),
+            discrete_log_below(&three, &target, &mersenne_61, &bound)
+        );
+        assert_eq!(
+            None,
+            discrete_log_below(&three, &target, &mersenne_61, &BigInt::from(123456u32))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_discrete_log_not_coprime() {
+        discrete_log(&BigInt::two(), &BigInt::one(), &BigInt::from(10u32));
+    }
+
+    #[test]
+    fn test_multiplicative_order() {
+        // Everything is 1 modulo 1.
+        let order_naive = |a: u32, n: u32| {
+            (1..=n).find(|&k| BigInt::from(a).modpow(&BigInt::from(k), &BigInt::from(n)) == 1 % n)
+        };
+        for n in 1..60u32 {
+            for a in 0..n {
+                let expected = if BigInt::from(a).gcd(&BigInt::from(n)).is_one() {
+                    order_naive(a, n).map(BigInt::from)
+                } else {
+                    None
+                };
+                assert_eq!(
+                    expected,
+                    multiplicative_order(&BigInt::from(a), &BigInt::from(n)),
+                    "{} mod {}",
+                    a,
+                    n
+                );
+            }
+        }
+        // 2 has order 11 modulo 23, and 10 modulo 11.
+        assert_eq!(
+            Some(BigInt::from(11u32)),
+            multiplicative_order(&BigInt::two(), &BigInt::from(23u32))
+        );
+        let p = BigInt::from(998244353u32);
+        assert_eq!(
+            Some(p.sub(&BigInt::one())),
+            multiplicative_order(&BigInt::from(3u32), &p)
+        );
+    }
+
+    #[test]
+    fn test_primitive_root() {
+        let roots = [
+            (1u32, Some(0u32)),
+            (2, Some(1)),
+            (4, Some(3)),
+            (7, Some(3)),
+            (8, None),
+            (9, Some(2)),
+            (12, None),
+            (18, Some(5)),
+            (23, Some(5)),
+            (41, Some(6)),
+            (1000003, Some(2)),
+        ];
+        for &(n, root) in roots.iter() {
+            assert_eq!(
+                root.map(BigInt::from),
+                primitive_root(&BigInt::from(n)),
+                "{}",
+                n
+            );
+        }
+        // The usual generator of the NTT prime 119 * 2^23 + 1.
+        assert_eq!(
+            Some(BigInt::from(3u32)),
+            primitive_root(&BigInt::from(998244353u32))
+        );
+    }
+
+    #[test]
+    fn test_as_perfect_power() {
+        let mut expected = HashMap::new();
+        for base in 2..100u32 {
+            let mut power = base * base;
+            let mut k = 2;
+            while power < 10000 {
+                // The smallest base, with the largest exponent, wins.
+                expected.entry(power).or_insert((base, k));
+                power *= base;
+                k += 1;
+            }
+        }
+        for n in 0..10000u32 {
+            let found = BigInt::from(n).as_perfect_power();
+            let wanted = expected.get(&n).map(|&(b, k)| (BigInt::from(b), k));
+            assert_eq!(wanted, found, "{}", n);
+        }
+        let a = BigInt::from_string("1234567890123");
+        assert_eq!(Some((a.clone(), 5)), a.pow(5).as_perfect_power());
+        assert_eq!(None, a.pow(5).add(&BigInt::one()).as_perfect_power());
+        assert_eq!(
+            Some((BigInt::two(), 1000)),
+            (BigInt::one() << 1000).as_perfect_power()
+        );
+        assert_eq!(
+            Some((BigInt::from(10u32), 30)),
+            BigInt::ten().pow(30).as_perfect_power()
+        );
+    }
+}
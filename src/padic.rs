@@ -0,0 +1,147 @@
+//! p-adic tools: valuations, and Hensel lifting of polynomial roots from modulo `p` to
+//! modulo `p^k`.
+
+use crate::modular::{ModInt, ModRing};
+use crate::optimized_memory::BigInt;
+use crate::signed;
+
+impl BigInt {
+    /// The exponent of `p` in the value: the largest `e` such that `p^e` divides it.
+    ///
+    /// Panics if the value is zero or if `p` is below 2.
+    pub fn valuation(&self, p: &BigInt) -> u32 {
+        assert!(!self.is_zero(), "zero has no valuation");
+        assert!(*p >= 2u32, "valuations need a base of at least 2");
+        let mut value = self.clone();
+        let mut exponent = 0;
+        loop {
+            let (q, r) = value.div_rem(p);
+            if !r.is_zero() {
+                return exponent;
+            }
+            value = q;
+            exponent += 1;
+        }
+    }
+}
+
+/// The root modulo `p^k` of the polynomial with the given coefficients, constant term
+/// first, that reduces to `root` modulo `p`, or `None` if `root` is not a simple root
+/// modulo `p`: `f(root)` is nonzero modulo `p`, or `f'(root)` is zero modulo `p` (a
+/// singular root).
+///
+/// Newton's iteration `r - f(r) / f'(r)` doubles the precision at each step, and the
+/// lifted root is unique.
+///
+/// Panics if `p` is below 2 or `k` is zero.
+pub fn hensel_lift(
+    coefficients: &[signed::BigInt],
+    root: &BigInt,
+    p: &BigInt,
+    k: u32,
+) -> Option<BigInt> {
+    assert!(*p >= 2u32, "the modulus must be at least 2");
+    assert!(k > 0, "the exponent must be positive");
+    let derivative: Vec<signed::BigInt> = coefficients
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| c.mul(&signed::BigInt::from(i as i64)))
+        .collect();
+    let ring = ModRing::new(p.clone());
+    let r = ring.element(root);
+    if !evaluate(coefficients, &r).value().is_zero() {
+        return None;
+    }
+    evaluate(&derivative, &r).inv()?;
+
+    let mut root = r.into_value();
+    let mut precision = 1;
+    while precision < k {
+        precision = (2 * precision).min(k);
+        let ring = ModRing::new(p.pow(precision));
+        let r = ring.element(&root);
+        let slope = evaluate(&derivative, &r)
+            .inv()
+            .expect("f'(root) stays invertible");
+        root = (&r - &(&evaluate(coefficients, &r) * &slope)).into_value();
+    }
+    Some(root)
+}
+
+/// Helper function for `hensel_lift`: the polynomial at `x`, by Horner's rule.
+fn evaluate(coefficients: &[signed::BigInt], x: &ModInt) -> ModInt {
+    let ring = x.ring();
+    let modulus = signed::BigInt::from(ring.modulus().clone());
+    coefficients.iter().rev().fold(ring.zero(), |acc, c| {
+        &(&acc * x) + &ring.element(&c.rem_euclid(&modulus).into_magnitude())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn coefficients(values: &[i64]) -> Vec<signed::BigInt> {
+        values.iter().map(|&v| signed::BigInt::from(v)).collect()
+    }
+
+    #[test]
+    fn test_valuation() {
+        let two = BigInt::two();
+        assert_eq!(0, BigInt::one().valuation(&two));
+        assert_eq!(100, (BigInt::one() << 100).valuation(&two));
+        assert_eq!(3, BigInt::from(3u32 * 125).valuation(&BigInt::from(5u32)));
+        assert_eq!(1, BigInt::from(12u32).valuation(&BigInt::from(12u32)));
+        assert_eq!(0, BigInt::from(12u32).valuation(&BigInt::from(7u32)));
+        let p = BigInt::from(1000003u32);
+        assert_eq!(7, p.pow(7).mul(&BigInt::from(8u32)).valuation(&p));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_valuation_of_zero() {
+        BigInt::zero().valuation(&BigInt::two());
+    }
+
+    #[test]
+    fn test_hensel_lift() {
+        // x^2 + 1 has the roots 2 and 3 modulo 5.
+        let f = coefficients(&[1, 0, 1]);
+        let five = BigInt::from(5u32);
+        for (root, k) in [(2u32, 1u32), (2, 2), (3, 5), (2, 40)] {
+            let lifted = hensel_lift(&f, &BigInt::from(root), &five, k).unwrap();
+            let modulus = five.pow(k);
+            assert!(lifted < modulus);
+            assert_eq!(BigInt::from(root), lifted.rem(&five));
+            assert!(lifted
+                .mul(&lifted)
+                .add(&BigInt::one())
+                .rem(&modulus)
+                .is_zero());
+        }
+        assert_eq!(
+            Some(BigInt::from(7u32)),
+            hensel_lift(&f, &BigInt::from(2u32), &five, 2)
+        );
+        assert_eq!(None, hensel_lift(&f, &BigInt::from(1u32), &five, 3));
+
+        // x^3 - 6 has the simple root 3 modulo 7, as 27 = 6.
+        let g = coefficients(&[-6, 0, 0, 1]);
+        let seven = BigInt::from(7u32);
+        let lifted = hensel_lift(&g, &BigInt::from(3u32), &seven, 20).unwrap();
+        assert_eq!(BigInt::from(6u32), lifted.pow(3).rem(&seven.pow(20)));
+
+        // x^2 has the double root 0 modulo 3, which does not lift uniquely.
+        assert_eq!(
+            None,
+            hensel_lift(
+                &coefficients(&[0, 0, 1]),
+                &BigInt::zero(),
+                &BigInt::from(3u32),
+                4
+            )
+        );
+    }
+}
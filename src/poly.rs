@@ -0,0 +1,342 @@
+//! Dense polynomials with signed BigInt coefficients.
+
+use crate::optimized_memory::BigInt as Magnitude;
+use crate::signed::BigInt;
+use std::fmt;
+
+/// A polynomial, stored as its coefficients from the constant term up, without
+/// trailing zeros. The zero polynomial has no coefficients.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Poly {
+    coefficients: Vec<BigInt>,
+}
+
+impl Poly {
+    pub fn zero() -> Poly {
+        Poly {
+            coefficients: Vec::new(),
+        }
+    }
+
+    /// The polynomial `c[0] + c[1] x + c[2] x^2 + ...`.
+    pub fn new(coefficients: Vec<BigInt>) -> Poly {
+        let mut result = Poly { coefficients };
+        result.normalize();
+        result
+    }
+
+    pub fn constant(c: BigInt) -> Poly {
+        Poly::new(vec![c])
+    }
+
+    pub fn coefficients(&self) -> &[BigInt] {
+        &self.coefficients
+    }
+
+    /// The coefficient of `x^i`.
+    pub fn coefficient(&self, i: usize) -> BigInt {
+        self.coefficients.get(i).cloned().unwrap_or_default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    /// The degree, or `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    /// The coefficient of the highest power, zero for the zero polynomial.
+    pub fn leading_coefficient(&self) -> BigInt {
+        self.coefficients.last().cloned().unwrap_or_default()
+    }
+
+    pub fn add(&self, other: &Poly) -> Poly {
+        let len = std::cmp::max(self.coefficients.len(), other.coefficients.len());
+        Poly::new(
+            (0..len)
+                .map(|i| self.coefficient(i).add(&other.coefficient(i)))
+                .collect(),
+        )
+    }
+
+    pub fn sub(&self, other: &Poly) -> Poly {
+        let len = std::cmp::max(self.coefficients.len(), other.coefficients.len());
+        Poly::new(
+            (0..len)
+                .map(|i| self.coefficient(i).sub(&other.coefficient(i)))
+                .collect(),
+        )
+    }
+
+    pub fn scale(&self, c: &BigInt) -> Poly {
+        Poly::new(self.coefficients.iter().map(|a| a.mul(c)).collect())
+    }
+
+    /// Multiplication by Kronecker substitution: both polynomials are evaluated at a
+    /// power of two large enough to separate the coefficients of the product, which
+    /// turns the whole computation into one integer multiplication.
+    pub fn mul(&self, other: &Poly) -> Poly {
+        if self.is_zero() || other.is_zero() {
+            return Poly::zero();
+        }
+        // Every coefficient of the product is bounded by len * max|a| * max|b|, and one
+        // more bit leaves room for the sign of the balanced digits.
+        let terms = std::cmp::min(self.coefficients.len(), other.coefficients.len());
+        let width = self.max_bits() + other.max_bits() + bit_length(terms) + 1;
+        let product = self
+            .evaluate_at_power_of_two(width)
+            .mul(&other.evaluate_at_power_of_two(width));
+        let len = self.coefficients.len() + other.coefficients.len() - 1;
+        Poly::new(split_balanced(&product, width, len))
+    }
+
+    /// Evaluate the polynomial at `x` with Horner's rule.
+    pub fn eval(&self, x: &BigInt) -> BigInt {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(BigInt::zero(), |acc, c| acc.mul(x).add(c))
+    }
+
+    /// Pseudo-division: the quotient `q` and remainder `r`, of degree lower than the
+    /// divisor's, such that `lc^(m - n + 1) * self = q * divisor + r`, where `lc` is the
+    /// leading coefficient of the divisor and `m`, `n` are the degrees. If `self` has
+    /// lower degree than the divisor the result is `(0, self)`.
+    ///
+    /// Panics if `divisor` is zero.
+    pub fn pseudo_div_rem(&self, divisor: &Poly) -> (Poly, Poly) {
+        let n = divisor
+            .degree()
+            .expect("attempt to divide by zero polynomial");
+        let m = match self.degree() {
+            Some(m) if m >= n => m,
+            _ => return (Poly::zero(), self.clone()),
+        };
+        let lc = divisor.leading_coefficient();
+        let mut quotient = vec![BigInt::zero(); m - n + 1];
+        let mut remainder = self.coefficients.clone();
+        for k in (0..=m - n).rev() {
+            // The remainder has degree at most n + k: after scaling everything by lc,
+            // subtracting top * x^k * divisor cancels its top coefficient.
+            let top = remainder[n + k].clone();
+            for q in quotient.iter_mut().skip(k + 1) {
+                *q = q.mul(&lc);
+            }
+            quotient[k] = top.clone();
+            for r in remainder.iter_mut().take(n + k) {
+                *r = r.mul(&lc);
+            }
+            for j in 0..n {
+                let t = top.mul(&divisor.coefficients[j]);
+                remainder[j + k] = remainder[j + k].sub(&t);
+            }
+            remainder.truncate(n + k);
+        }
+        (Poly::new(quotient), Poly::new(remainder))
+    }
+
+    fn normalize(&mut self) {
+        while self.coefficients.last().is_some_and(BigInt::is_zero) {
+            self.coefficients.pop();
+        }
+    }
+
+    fn max_bits(&self) -> usize {
+        self.coefficients
+            .iter()
+            .map(|c| c.magnitude().bits())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Helper function for `mul`: the value at 2^width, each coefficient being smaller
+    /// than 2^(width - 1) in absolute value.
+    fn evaluate_at_power_of_two(&self, width: usize) -> BigInt {
+        let mut positive = Magnitude::zero();
+        let mut negative = Magnitude::zero();
+        for (i, c) in self.coefficients.iter().enumerate() {
+            let shifted = c.magnitude().clone() << (i * width);
+            if c.is_negative() {
+                negative = negative.add(&shifted);
+            } else {
+                positive = positive.add(&shifted);
+            }
+        }
+        BigInt::from(positive).sub(&BigInt::from(negative))
+    }
+}
+
+/// Number of bits needed to represent `n`.
+fn bit_length(n: usize) -> usize {
+    (usize::BITS - n.leading_zeros()) as usize
+}
+
+/// Helper function for `mul`: the inverse of `evaluate_at_power_of_two`, reading `len`
+/// digits in base 2^width, each in the balanced range [-2^(width - 1), 2^(width - 1)).
+fn split_balanced(value: &BigInt, width: usize, len: usize) -> Vec<BigInt> {
+    let half = Magnitude::one() << (width - 1);
+    let base = Magnitude::one() << width;
+    let mut rest = value.magnitude().clone();
+    let mut digits = Vec::with_capacity(len);
+    for _ in 0..len {
+        let low = rest.truncate_to(width);
+        rest = rest >> width;
+        let digit = if low >= half {
+            rest = rest.add(&Magnitude::one());
+            BigInt::new(true, base.sub(&low))
+        } else {
+            BigInt::from(low)
+        };
+        digits.push(if value.is_negative() { -digit } else { digit });
+    }
+    digits
+}
+
+/// Terms from the highest power down, such as `3x^2 - x + 1`.
+impl fmt::Display for Poly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut first = true;
+        for (i, c) in self.coefficients.iter().enumerate().rev() {
+            if c.is_zero() {
+                continue;
+            }
+            let sign = match (first, c.is_negative()) {
+                (true, true) => "-",
+                (true, false) => "",
+                (false, true) => " - ",
+                (false, false) => " + ",
+            };
+            write!(f, "{}", sign)?;
+            if !c.magnitude().is_one() || i == 0 {
+                write!(f, "{}", c.magnitude())?;
+            }
+            match i {
+                0 => {}
+                1 => write!(f, "x")?,
+                _ => write!(f, "x^{}", i)?,
+            }
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn p(coefficients: &[i64]) -> Poly {
+        Poly::new(coefficients.iter().map(|&c| BigInt::from(c)).collect())
+    }
+
+    /// Schoolbook product, to check the Kronecker substitution against.
+    fn naive_mul(a: &Poly, b: &Poly) -> Poly {
+        if a.is_zero() || b.is_zero() {
+            return Poly::zero();
+        }
+        let mut result = vec![BigInt::zero(); a.coefficients.len() + b.coefficients.len() - 1];
+        for (i, x) in a.coefficients.iter().enumerate() {
+            for (j, y) in b.coefficients.iter().enumerate() {
+                result[i + j] = result[i + j].add(&x.mul(y));
+            }
+        }
+        Poly::new(result)
+    }
+
+    #[test]
+    fn test_new_degree() {
+        assert_eq!(Poly::zero(), p(&[0, 0]));
+        assert_eq!(None, Poly::zero().degree());
+        assert_eq!(Some(2), p(&[1, 0, 3, 0]).degree());
+        assert_eq!(BigInt::from(3i32), p(&[1, 0, 3]).leading_coefficient());
+        assert_eq!(BigInt::zero(), p(&[1]).coefficient(5));
+    }
+
+    #[test]
+    fn test_add_sub() {
+        assert_eq!(p(&[4, 1, 3]), p(&[1, 2, 3]).add(&p(&[3, -1])));
+        assert_eq!(p(&[1]), p(&[1, 2, 3]).sub(&p(&[0, 2, 3])));
+        assert_eq!(Poly::zero(), p(&[1, 2]).sub(&p(&[1, 2])));
+    }
+
+    #[test]
+    fn test_mul() {
+        assert_eq!(p(&[-1, 0, 1]), p(&[1, 1]).mul(&p(&[-1, 1])));
+        assert_eq!(Poly::zero(), p(&[1, 1]).mul(&Poly::zero()));
+        assert_eq!(p(&[-6]), p(&[2]).mul(&p(&[-3])));
+        let cases = [
+            (p(&[3, -7, 0, 5, -1]), p(&[-2, 9, -4])),
+            (p(&[-1, -1, -1]), p(&[-1, -1, -1, -1])),
+            (p(&[i64::MAX, i64::MIN, 0, -1]), p(&[i64::MIN, i64::MAX])),
+            (p(&[0, 0, 1]), p(&[0, -5])),
+        ];
+        for (a, b) in cases.iter() {
+            assert_eq!(naive_mul(a, b), a.mul(b));
+            assert_eq!(naive_mul(a, b), b.mul(a));
+        }
+        let big = Poly::new(vec![
+            BigInt::from_string("-123456789012345678901234567890"),
+            BigInt::from_string("98765432109876543210"),
+            BigInt::one(),
+        ]);
+        assert_eq!(naive_mul(&big, &big), big.mul(&big));
+    }
+
+    #[test]
+    fn test_eval() {
+        let a = p(&[1, -3, 2]);
+        assert_eq!(BigInt::from(1i32), a.eval(&BigInt::zero()));
+        assert_eq!(BigInt::from(0i32), a.eval(&BigInt::one()));
+        assert_eq!(BigInt::from(15i32), a.eval(&BigInt::from(-2i32)));
+        assert_eq!(BigInt::zero(), Poly::zero().eval(&BigInt::from(7i32)));
+    }
+
+    #[test]
+    fn test_pseudo_div_rem() {
+        let cases = [
+            (p(&[1, 2, 3, 4, 5]), p(&[1, 0, 2])),
+            (p(&[-7, 0, 0, 3]), p(&[2, -3])),
+            (p(&[5, 1]), p(&[0, 0, 1])),
+            (p(&[6, 5, 1]), p(&[2, 1])),
+        ];
+        for (a, b) in cases.iter() {
+            let (q, r) = a.pseudo_div_rem(b);
+            let exponent = match a.degree() {
+                Some(m) if m >= b.degree().unwrap() => m - b.degree().unwrap() + 1,
+                _ => 0,
+            };
+            let lc = BigInt::from(b.leading_coefficient().magnitude().pow(exponent as u32));
+            let lc = if b.leading_coefficient().is_negative() && exponent % 2 == 1 {
+                -lc
+            } else {
+                lc
+            };
+            assert_eq!(a.scale(&lc), q.mul(b).add(&r));
+            assert!(r.degree() < b.degree());
+        }
+        assert_eq!(
+            (p(&[3, 1]), Poly::zero()),
+            p(&[6, 5, 1]).pseudo_div_rem(&p(&[2, 1]))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_pseudo_div_by_zero() {
+        p(&[1]).pseudo_div_rem(&Poly::zero());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("3x^2 - x + 1", p(&[1, -1, 3]).to_string());
+        assert_eq!("-x^3 + 2x", p(&[0, 2, 0, -1]).to_string());
+        assert_eq!("-1", p(&[-1]).to_string());
+        assert_eq!("0", Poly::zero().to_string());
+    }
+}
@@ -0,0 +1,148 @@
+//! A pool of BigInts whose storage is reused for temporaries, so that a loop computing
+//! many short-lived intermediate values allocates only while the pool warms up.
+//!
+//! Values taken from a `BigIntPool` are zero but keep the capacity they had when they
+//! were given back. The arithmetic entry points write their result into such a value.
+
+use crate::optimized_memory::BigInt;
+
+/// Number of idle values a pool created with `new` keeps; values given back beyond that
+/// are dropped.
+pub const DEFAULT_MAX_IDLE: usize = 64;
+
+/// Idle BigInts ready to be handed out again.
+#[derive(Debug, Clone)]
+pub struct BigIntPool {
+    idle: Vec<BigInt>,
+    max_idle: usize,
+}
+
+impl BigIntPool {
+    pub fn new() -> BigIntPool {
+        BigIntPool::with_max_idle(DEFAULT_MAX_IDLE)
+    }
+
+    /// A pool keeping at most `max_idle` values.
+    pub fn with_max_idle(max_idle: usize) -> BigIntPool {
+        BigIntPool {
+            idle: Vec::new(),
+            max_idle,
+        }
+    }
+
+    /// Number of idle values.
+    pub fn len(&self) -> usize {
+        self.idle.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.idle.is_empty()
+    }
+
+    /// A zero value, with the storage of the largest idle value if there is one.
+    pub fn take(&mut self) -> BigInt {
+        self.idle.pop().unwrap_or_else(BigInt::zero)
+    }
+
+    /// Return a value to the pool, which keeps its storage for a later `take` unless the
+    /// pool is full.
+    pub fn give(&mut self, mut value: BigInt) {
+        if self.idle.len() >= self.max_idle {
+            return;
+        }
+        value.clear();
+        // Keep the idle values sorted by capacity, so that `take` hands out the largest.
+        let position = self
+            .idle
+            .partition_point(|idle| idle.heap_size() <= value.heap_size());
+        self.idle.insert(position, value);
+    }
+
+    /// `a + b` in a value from the pool.
+    pub fn add(&mut self, a: &BigInt, b: &BigInt) -> BigInt {
+        let mut result = self.take();
+        result.assign(a);
+        result.add_in_place(b);
+        result
+    }
+
+    /// `a - b` in a value from the pool.
+    ///
+    /// Panics if `b` is larger than `a`.
+    pub fn sub(&mut self, a: &BigInt, b: &BigInt) -> BigInt {
+        let mut result = self.take();
+        result.assign(a);
+        result.sub_in_place(b);
+        result
+    }
+
+    /// `a * b` in a value from the pool.
+    pub fn mul(&mut self, a: &BigInt, b: &BigInt) -> BigInt {
+        let mut result = self.take();
+        result.assign_mul(a, b);
+        result
+    }
+}
+
+impl Default for BigIntPool {
+    fn default() -> BigIntPool {
+        BigIntPool::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_take_give() {
+        let mut pool = BigIntPool::with_max_idle(2);
+        assert!(pool.is_empty());
+        assert_eq!(BigInt::zero(), pool.take());
+
+        let large = BigInt::one() << 1000;
+        let capacity = large.heap_size();
+        pool.give(BigInt::from(7u32));
+        pool.give(large);
+        pool.give(BigInt::from(8u32));
+        assert_eq!(2, pool.len());
+
+        let value = pool.take();
+        assert_eq!(BigInt::zero(), value);
+        assert_eq!(capacity, value.heap_size());
+        assert_eq!(BigInt::zero(), pool.take());
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut pool = BigIntPool::new();
+        let a = BigInt::from_string("340282366920938463463374607431768211457");
+        let b = BigInt::from_string("18446744073709551629");
+        for _ in 0..3 {
+            let sum = pool.add(&a, &b);
+            let difference = pool.sub(&a, &b);
+            let product = pool.mul(&a, &b);
+            assert_eq!(a.add(&b), sum);
+            assert_eq!(a.sub(&b), difference);
+            assert_eq!(a.mul(&b), product);
+            assert_eq!(BigInt::zero(), pool.mul(&a, &BigInt::zero()));
+            pool.give(sum);
+            pool.give(difference);
+            pool.give(product);
+        }
+        assert_eq!(3, pool.len());
+
+        let mut square = pool.take();
+        square.add_in_place(&a);
+        let square = pool.mul(&square, &square);
+        assert_eq!(a.mul(&a), square);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sub_underflow() {
+        BigIntPool::new().sub(&BigInt::one(), &BigInt::two());
+    }
+}
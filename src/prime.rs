@@ -0,0 +1,763 @@
+//! Primality testing and prime generation.
+
+use crate::factor::primes_below;
+use crate::limb_ops;
+use crate::modular::ModRing;
+use crate::number_theory::jacobi;
+use crate::optimized_memory::BigInt;
+#[cfg(feature = "rand")]
+use rand::Rng;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// The primes below 256, used for trial division and as Miller-Rabin bases.
+pub(crate) const SMALL_PRIMES: [u32; 54] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+/// Number of odd candidates `next_prime` and `prev_prime` sieve at once.
+const SIEVE_WINDOW: usize = 512;
+
+/// Number of odd candidates `primes_in_range` sieves at once.
+const SEGMENT_SIZE: usize = 1 << 14;
+
+/// `primes_in_range` sieves with the primes below this bound, at most.
+const SEGMENT_SIEVE_BOUND: u32 = 1 << 16;
+
+impl BigInt {
+    /// Trial division by the primes below 256, then the Miller-Rabin test with the first
+    /// `rounds` of those primes as bases (at most all 54 of them).
+    ///
+    /// A prime always passes. A composite passes each base with probability at most 1/4,
+    /// and with 13 or more rounds the answer is exact below 3.3 * 10^24.
+    ///
+    /// Panics if `rounds` is zero, which would let every composite without a factor below
+    /// 256 pass.
+    pub fn is_probable_prime(&self, rounds: usize) -> bool {
+        assert!(rounds > 0, "at least one Miller-Rabin round is required");
+        self.trial_division()
+            .unwrap_or_else(|| self.passes_miller_rabin(rounds))
+    }
+
+    /// The Baillie-PSW test: a base-2 strong probable prime test followed by a strong
+    /// Lucas test with Selfridge's parameters.
+    ///
+    /// The answer is exact below 2^64, and no composite passing both tests is known at
+    /// any size.
+    pub fn is_prime_bpsw(&self) -> bool {
+        self.trial_division()
+            .unwrap_or_else(|| self.passes_miller_rabin(1) && self.passes_strong_lucas())
+    }
+
+    /// The smallest prime above the value.
+    ///
+    /// Windows of odd candidates are sieved by the small primes, and only the survivors
+    /// go through `is_prime_bpsw`. With the `rayon` feature consecutive windows are
+    /// searched in parallel, with the same result.
+    pub fn next_prime(&self) -> BigInt {
+        if *self < 256u32 * 256 {
+            let mut n = self.to_u32().unwrap() + 1;
+            while !BigInt::from(n).is_prime_bpsw() {
+                n += 1;
+            }
+            return BigInt::from(n);
+        }
+        let mut start = self.add(&BigInt::one());
+        if start.is_even() {
+            start = start.add(&BigInt::one());
+        }
+        search(&start, true, BigInt::is_prime_bpsw)
+    }
+
+    /// The largest prime below the value, or `None` below 3. Searches like `next_prime`.
+    pub fn prev_prime(&self) -> Option<BigInt> {
+        if *self <= 256u32 * 256 {
+            let n = self.to_u32().unwrap();
+            return (2..n).rev().map(BigInt::from).find(BigInt::is_prime_bpsw);
+        }
+        let mut start = self.sub(&BigInt::one());
+        if start.is_even() {
+            start = start.sub(&BigInt::one());
+        }
+        // 65537 is prime, so the search stops before the windows leave the large values.
+        Some(search(&start, false, BigInt::is_prime_bpsw))
+    }
+
+    /// Helper function for the primality tests: trial division by the primes below 256,
+    /// `None` if it was inconclusive.
+    fn trial_division(&self) -> Option<bool> {
+        for (p, r) in SmallResidues::new(self).iter() {
+            if *self == p {
+                return Some(true);
+            }
+            if r == 0 {
+                return Some(false);
+            }
+        }
+        if *self < 2u32 {
+            return Some(false);
+        }
+        // Below 256^2 trial division was conclusive.
+        if *self < 256u32 * 256 {
+            return Some(true);
+        }
+        None
+    }
+
+    /// The Miller-Rabin test alone, for odd values above 256 that survived trial division.
+    fn passes_miller_rabin(&self, rounds: usize) -> bool {
+        let one = BigInt::one();
+        let minus_one = self.sub(&one);
+        let s = minus_one.trailing_zeros();
+        let d = minus_one.clone() >> s;
+        let ring = ModRing::new(self.clone());
+        let minus_one = ring.element(&minus_one);
+        'bases: for &base in SMALL_PRIMES.iter().take(rounds) {
+            #[cfg(feature = "tracing")]
+            crate::stats::record_miller_rabin_round();
+            let mut x = ring.element(&BigInt::from(base)).pow(&d);
+            if x.value().is_one() || x == minus_one {
+                continue;
+            }
+            for _ in 1..s {
+                x = &x * &x;
+                if x == minus_one {
+                    continue 'bases;
+                }
+            }
+            return false;
+        }
+        true
+    }
+
+    /// The strong Lucas test with `P = 1` and `Q = (1 - D) / 4`, where `D` is the first of
+    /// 5, -7, 9, -11, ... with Jacobi symbol `(D/n) = -1`, for odd values above 256.
+    fn passes_strong_lucas(&self) -> bool {
+        #[cfg(feature = "tracing")]
+        crate::stats::record_lucas_test();
+        // No suitable D exists for a square.
+        if self.sqrt().pow(2) == *self {
+            return false;
+        }
+        let ring = ModRing::new(self.clone());
+        let signed = |k: i64| {
+            let e = ring.element(&BigInt::from(k.unsigned_abs()));
+            if k < 0 {
+                -e
+            } else {
+                e
+            }
+        };
+        let mut d = 5i64;
+        let discriminant = loop {
+            let candidate = signed(d);
+            match jacobi(candidate.value(), self) {
+                -1 => break candidate,
+                // D is a proper factor.
+                0 => return false,
+                _ => d = if d > 0 { -d - 2 } else { -d + 2 },
+            }
+        };
+        let q = signed((1 - d) / 4);
+        let plus_one = self.add(&BigInt::one());
+        let s = plus_one.trailing_zeros();
+        let k = plus_one.clone() >> s;
+        let half = ring.element(&(plus_one >> 1));
+
+        // U_k, V_k and Q^k, following the bits of k from the top.
+        let (mut u, mut v, mut qk) = (ring.one(), ring.one(), q.clone());
+        for i in (0..k.bits() - 1).rev() {
+            u = &u * &v;
+            v = &(&v * &v) - &(&qk + &qk);
+            qk = &qk * &qk;
+            if k.bit(i) {
+                let next_u = &(&u + &v) * &half;
+                v = &(&(&discriminant * &u) + &v) * &half;
+                u = next_u;
+                qk = &qk * &q;
+            }
+        }
+        if u.value().is_zero() {
+            return true;
+        }
+        // V_{k 2^r} for r < s.
+        for _ in 0..s {
+            if v.value().is_zero() {
+                return true;
+            }
+            v = &(&v * &v) - &(&qk + &qk);
+            qk = &qk * &qk;
+        }
+        false
+    }
+
+    /// Number of trailing zero bits, zero for zero.
+    pub(crate) fn trailing_zeros(&self) -> usize {
+        (0..self.bits()).find(|&i| self.bit(i)).unwrap_or(0)
+    }
+}
+
+/// The Lucas-Lehmer test: whether the Mersenne number `2^p - 1` is prime.
+///
+/// `s = 4` is squared minus 2 for `p - 2` steps; the number is prime exactly when `s` ends
+/// at zero. Reducing modulo `2^p - 1` only takes a shift and an addition, since `2^p` is 1.
+pub fn lucas_lehmer(p: u32) -> bool {
+    if p == 2 {
+        return true;
+    }
+    // For a composite p = ab, 2^a - 1 divides 2^p - 1.
+    if !BigInt::from(p).is_prime_bpsw() {
+        return false;
+    }
+    let bits = p as usize;
+    let modulus = (BigInt::one() << bits).sub(&BigInt::one());
+    let mut s = BigInt::from(4u32);
+    for _ in 0..p - 2 {
+        s = reduce_mersenne(s.mul(&s).add(&modulus).sub(&BigInt::two()), bits);
+    }
+    s.is_zero() || s == modulus
+}
+
+/// Helper function for `lucas_lehmer`: a value congruent to `x` modulo `2^bits - 1`, at
+/// most `2^bits - 1`.
+fn reduce_mersenne(mut x: BigInt, bits: usize) -> BigInt {
+    while x.bits() > bits {
+        x = x.truncate_to(bits).add(&(x >> bits));
+    }
+    x
+}
+
+/// The primes `p` with `low <= p < high`, in increasing order, by a segmented sieve.
+///
+/// Each segment of odd candidates is sieved by the primes up to `sqrt(high)`, or up to
+/// `SEGMENT_SIEVE_BOUND` for larger ranges. The survivors are then prime below the
+/// square of that bound, and go through `is_prime_bpsw` above it.
+pub fn primes_in_range(low: &BigInt, high: &BigInt) -> PrimesInRange {
+    let bound = high
+        .sqrt()
+        .add(&BigInt::one())
+        .min(BigInt::from(SEGMENT_SIEVE_BOUND))
+        .to_u32()
+        .unwrap();
+    let next = if low.is_even() {
+        low.add(&BigInt::one())
+    } else {
+        low.clone()
+    };
+    PrimesInRange {
+        two: *low <= 2u32 && *high > 2u32,
+        next,
+        high: high.clone(),
+        sieve_primes: primes_below(bound),
+        exact_below: BigInt::from(u64::from(bound) * u64::from(bound)),
+        pending: VecDeque::new(),
+    }
+}
+
+/// The iterator returned by `primes_in_range`.
+#[derive(Debug, Clone)]
+pub struct PrimesInRange {
+    /// Whether 2 is still to come.
+    two: bool,
+    /// The first odd candidate of the next segment.
+    next: BigInt,
+    high: BigInt,
+    sieve_primes: Arc<Vec<u32>>,
+    /// Survivors of the sieve below this value are prime.
+    exact_below: BigInt,
+    /// The primes of the current segment not returned yet.
+    pending: VecDeque<BigInt>,
+}
+
+impl PrimesInRange {
+    /// Sieve the next segment into `pending`.
+    fn sieve_segment(&mut self) {
+        let remaining = self.high.sub(&self.next).to_usize();
+        let len = remaining.map_or(SEGMENT_SIZE, |r| r.div_ceil(2).min(SEGMENT_SIZE));
+        let mut composite = vec![false; len];
+        for &p in self.sieve_primes.iter().skip(1) {
+            let r = limb_ops::mod_1(self.next.as_limbs_le(), p) as u64;
+            // Solve next + 2i = 0 modulo p; (p + 1) / 2 inverts 2.
+            let p = p as u64;
+            let mut first = (p - r) * p.div_ceil(2) % p;
+            if self.next <= p {
+                // That first multiple is p itself.
+                first += p;
+            }
+            for i in (first as usize..len).step_by(p as usize) {
+                composite[i] = true;
+            }
+        }
+        for (i, _) in composite.iter().enumerate().filter(|(_, &c)| !c) {
+            let candidate = self.next.add(&BigInt::from(2 * i));
+            if !candidate.is_one() && (candidate < self.exact_below || candidate.is_prime_bpsw()) {
+                self.pending.push_back(candidate);
+            }
+        }
+        self.next = self.next.add(&BigInt::from(2 * len));
+    }
+}
+
+impl Iterator for PrimesInRange {
+    type Item = BigInt;
+
+    fn next(&mut self) -> Option<BigInt> {
+        if self.two {
+            self.two = false;
+            return Some(BigInt::two());
+        }
+        loop {
+            if let Some(p) = self.pending.pop_front() {
+                return Some(p);
+            }
+            if self.next >= self.high {
+                return None;
+            }
+            self.sieve_segment();
+        }
+    }
+}
+
+/// Helper function for the prime searches: the first odd candidate from `start`, upwards
+/// or downwards, that survives the sieve and is accepted.
+#[cfg(not(feature = "rayon"))]
+fn search<F>(start: &BigInt, forward: bool, accept: F) -> BigInt
+where
+    F: Fn(&BigInt) -> bool + Sync,
+{
+    (0..)
+        .find_map(|window| search_window(start, forward, window, &accept))
+        .unwrap()
+}
+
+/// Helper function for the prime searches: as many windows as there are threads are
+/// searched at once, and the hit in the earliest window wins, so the result is the same
+/// as searching them in order.
+#[cfg(feature = "rayon")]
+fn search<F>(start: &BigInt, forward: bool, accept: F) -> BigInt
+where
+    F: Fn(&BigInt) -> bool + Sync,
+{
+    use rayon::prelude::*;
+
+    let threads = rayon::current_num_threads();
+    for block in 0.. {
+        let hits: Vec<Option<BigInt>> = (block * threads..(block + 1) * threads)
+            .into_par_iter()
+            .map(|window| search_window(start, forward, window, &accept))
+            .collect();
+        if let Some(hit) = hits.into_iter().flatten().next() {
+            return hit;
+        }
+    }
+    unreachable!("the search ran out of windows")
+}
+
+/// The first accepted candidate in window number `window` from `start`, or `None` if there
+/// is none or if, downwards, the window would reach the small primes.
+pub(crate) fn search_window<F>(
+    start: &BigInt,
+    forward: bool,
+    window: usize,
+    accept: &F,
+) -> Option<BigInt>
+where
+    F: Fn(&BigInt) -> bool,
+{
+    let shift = BigInt::from(2 * SIEVE_WINDOW * window);
+    let base = if forward {
+        start.add(&shift)
+    } else {
+        start
+            .checked_sub(&shift)
+            .filter(|base| *base >= 2 * SIEVE_WINDOW + 256)?
+    };
+    sieve_window(&base, forward).into_iter().find_map(|i| {
+        let offset = BigInt::from(2 * i);
+        let candidate = if forward {
+            base.add(&offset)
+        } else {
+            base.sub(&offset)
+        };
+        Some(candidate).filter(accept)
+    })
+}
+
+/// Helper function for `search_window`: the indices `i` for which
+/// `start + 2i` (or `start - 2i` backwards) has no odd factor below 256, for an odd
+/// `start` large enough that none of the candidates is itself a small prime.
+fn sieve_window(start: &BigInt, forward: bool) -> Vec<usize> {
+    let mut composite = [false; SIEVE_WINDOW];
+    for (p, r) in SmallResidues::new(start).iter().skip(1) {
+        // Solve start + 2i = 0 (or start - 2i = 0) modulo p; (p + 1) / 2 inverts 2.
+        let half = p.div_ceil(2);
+        let first = if forward {
+            (p - r) * half % p
+        } else {
+            r * half % p
+        };
+        for i in (first as usize..SIEVE_WINDOW).step_by(p as usize) {
+            composite[i] = true;
+        }
+    }
+    (0..SIEVE_WINDOW).filter(|&i| !composite[i]).collect()
+}
+
+/// The remainders of a candidate modulo each of `SMALL_PRIMES`, kept up to date as the
+/// candidate moves so that sieving a run of candidates needs no BigInt division.
+pub(crate) struct SmallResidues {
+    residues: [u32; SMALL_PRIMES.len()],
+}
+
+impl SmallResidues {
+    pub(crate) fn new(n: &BigInt) -> SmallResidues {
+        let mut residues = [0; SMALL_PRIMES.len()];
+        for (r, &p) in residues.iter_mut().zip(SMALL_PRIMES.iter()) {
+            *r = limb_ops::mod_1(n.as_limbs_le(), p);
+        }
+        SmallResidues { residues }
+    }
+
+    /// Move the candidate forward by `delta`.
+    #[cfg(any(test, feature = "rand"))]
+    pub(crate) fn advance(&mut self, delta: u32) {
+        for (r, &p) in self.residues.iter_mut().zip(SMALL_PRIMES.iter()) {
+            *r = ((*r as u64 + delta as u64) % p as u64) as u32;
+        }
+    }
+
+    /// Pairs of a small prime and the remainder of the candidate modulo it.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        SMALL_PRIMES
+            .iter()
+            .cloned()
+            .zip(self.residues.iter().cloned())
+    }
+}
+
+/// Miller-Rabin rounds for the output of `gen_prime`.
+#[cfg(feature = "rand")]
+const GEN_PRIME_ROUNDS: usize = 40;
+
+/// A random prime of exactly `bits` bits.
+///
+/// Odd candidates are walked upwards from a random start with the top bit set, sieved by
+/// windows like in `next_prime`; those that avoid every small prime get 40 rounds of
+/// Miller-Rabin. With the `rayon` feature the windows are searched in parallel, and the
+/// result for a given `rng` state is the same.
+///
+/// Panics if `bits` is smaller than 2.
+#[cfg(feature = "rand")]
+pub fn gen_prime<R: Rng + ?Sized>(rng: &mut R, bits: usize) -> BigInt {
+    assert!(bits >= 2, "bits must be at least 2");
+    // The sieve would reject the small primes themselves, and trial division is exact.
+    if bits <= 16 {
+        loop {
+            let candidate = random_odd(rng, bits);
+            if candidate.is_prime_bpsw() {
+                return candidate;
+            }
+        }
+    }
+    loop {
+        let start = random_odd(rng, bits);
+        // Stop at the first candidate with too many bits as well, and start over.
+        let candidate = search(&start, true, |c| {
+            c.bits() != bits || c.passes_miller_rabin(GEN_PRIME_ROUNDS)
+        });
+        if candidate.bits() == bits {
+            return candidate;
+        }
+    }
+}
+
+/// A safe prime of exactly `bits` bits, that is a prime `p` such that `(p - 1) / 2` is
+/// also prime, both passing `rounds` rounds of Miller-Rabin.
+///
+/// Candidates `q` for `(p - 1) / 2` are sieved incrementally: `q` and `2q + 1` must both
+/// avoid the small primes, and only the survivors reach the Miller-Rabin test.
+///
+/// Panics if `bits` is smaller than 10, where the sieve would also reject small primes.
+#[cfg(feature = "rand")]
+pub fn gen_safe_prime<R: Rng + ?Sized>(rng: &mut R, bits: usize, rounds: usize) -> BigInt {
+    assert!(bits >= 10, "bits must be at least 10");
+    loop {
+        let start = random_odd(rng, bits - 1);
+        let mut residues = SmallResidues::new(&start);
+        let mut offset = 0u32;
+        loop {
+            // 2q + 1 is divisible by s exactly when q = (s - 1) / 2 mod s.
+            let survives = residues.iter().all(|(s, r)| r != 0 && r != (s - 1) / 2);
+            if survives {
+                let q = start.add(&BigInt::from(offset));
+                if q.bits() != bits - 1 {
+                    break;
+                }
+                let p = q.clone() << 1;
+                let p = p.add(&BigInt::one());
+                if q.passes_miller_rabin(1)
+                    && p.passes_miller_rabin(rounds)
+                    && q.passes_miller_rabin(rounds)
+                {
+                    return p;
+                }
+            }
+            residues.advance(2);
+            offset += 2;
+        }
+    }
+}
+
+/// Helper function for the generators: a random odd value of exactly `bits` bits.
+#[cfg(feature = "rand")]
+fn random_odd<R: Rng + ?Sized>(rng: &mut R, bits: usize) -> BigInt {
+    let mut candidate = BigInt::random_bits(rng, bits);
+    candidate.set_bit(bits - 1, true);
+    candidate.set_bit(0, true);
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Primality by trial division up to the square root.
+    fn is_prime_naive(n: u32) -> bool {
+        n >= 2
+            && (2..)
+                .take_while(|d| d * d <= n)
+                .all(|d| !n.is_multiple_of(d))
+    }
+
+    #[test]
+    fn test_small() {
+        for n in 0..70000u32 {
+            assert_eq!(
+                is_prime_naive(n),
+                BigInt::from(n).is_probable_prime(1),
+                "{}",
+                n
+            );
+        }
+    }
+
+    #[test]
+    fn test_large() {
+        let mersenne_127 = (BigInt::one() << 127).sub(&BigInt::one());
+        assert!(mersenne_127.is_probable_prime(20));
+        let mersenne_128 = (BigInt::one() << 128).sub(&BigInt::one());
+        assert!(!mersenne_128.is_probable_prime(20));
+        // 2^89 - 1 and 2^107 - 1 are prime, their product is not.
+        let a = (BigInt::one() << 89).sub(&BigInt::one());
+        let b = (BigInt::one() << 107).sub(&BigInt::one());
+        assert!(a.is_probable_prime(10) && b.is_probable_prime(10));
+        assert!(!a.mul(&b).is_probable_prime(10));
+        // Strong pseudoprime to the twelve prime bases up to 37, but not to 41.
+        let pseudoprime = BigInt::from_string("318665857834031151167461");
+        assert!(pseudoprime.is_probable_prime(12));
+        assert!(!pseudoprime.is_probable_prime(13));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_rounds() {
+        BigInt::from(65537u64 * 65539).is_probable_prime(0);
+    }
+
+    #[test]
+    fn test_bpsw() {
+        for n in 0..70000u32 {
+            assert_eq!(is_prime_naive(n), BigInt::from(n).is_prime_bpsw(), "{}", n);
+        }
+        // Strong pseudoprimes to several bases, and a semiprime just below 2^64.
+        for s in [
+            "3215031751",
+            "3825123056546413051",
+            "318665857834031151167461",
+        ]
+        .iter()
+        {
+            assert!(!BigInt::from_string(s).is_prime_bpsw(), "{}", s);
+        }
+        let semiprime = BigInt::from(4294967291u64 * 4294967279);
+        assert!(!semiprime.is_prime_bpsw());
+        let below_2_64 = BigInt::from(u64::MAX - 58);
+        assert!(below_2_64.is_prime_bpsw());
+
+        let mersenne_127 = (BigInt::one() << 127).sub(&BigInt::one());
+        assert!(mersenne_127.is_prime_bpsw());
+        let a = (BigInt::one() << 89).sub(&BigInt::one());
+        assert!(!a.mul(&mersenne_127).is_prime_bpsw());
+        assert!(!a.mul(&a).is_prime_bpsw());
+    }
+
+    #[test]
+    fn test_next_prev_prime() {
+        let mut previous = None;
+        for n in 0..70000u32 {
+            if is_prime_naive(n) {
+                if let Some(p) = previous {
+                    assert_eq!(BigInt::from(n), BigInt::from(p).next_prime());
+                    assert_eq!(Some(BigInt::from(p)), BigInt::from(n).prev_prime());
+                }
+                previous = Some(n);
+            }
+        }
+        assert_eq!(BigInt::two(), BigInt::zero().next_prime());
+        assert_eq!(None, BigInt::two().prev_prime());
+        assert_eq!(Some(BigInt::two()), BigInt::from(3u32).prev_prime());
+
+        let two_64 = BigInt::one() << 64;
+        assert_eq!(two_64.add(&BigInt::from(13u32)), two_64.next_prime());
+        assert_eq!(Some(BigInt::from(u64::MAX - 58)), two_64.prev_prime());
+        let big = BigInt::ten().pow(30);
+        assert_eq!(big.add(&BigInt::from(57u32)), big.next_prime());
+        assert_eq!(Some(big.sub(&BigInt::from(11u32))), big.prev_prime());
+
+        // A gap of 1132 spans more than one sieve window, in both directions.
+        let p = BigInt::from(1693182318746371u64);
+        let q = p.add(&BigInt::from(1132u32));
+        assert_eq!(q, p.next_prime());
+        assert_eq!(Some(p), q.prev_prime());
+    }
+
+    #[test]
+    fn test_primes_in_range() {
+        let collect = |low: u32, high: u32| -> Vec<u32> {
+            primes_in_range(&BigInt::from(low), &BigInt::from(high))
+                .map(|p| p.to_u32().unwrap())
+                .collect()
+        };
+        let naive = |low: u32, high: u32| -> Vec<u32> {
+            (low..high).filter(|&n| is_prime_naive(n)).collect()
+        };
+        for (low, high) in [
+            (0, 1000),
+            (1, 2),
+            (2, 3),
+            (3, 3),
+            (100, 90),
+            (3, 4),
+            (0, 100000),
+        ] {
+            assert_eq!(naive(low, high), collect(low, high), "{} {}", low, high);
+        }
+        // Across several segments, and past the square of the sieve bound.
+        let low = (1u64 << 32) - 20000;
+        let primes: Vec<BigInt> =
+            primes_in_range(&BigInt::from(low), &BigInt::from(low + 40000)).collect();
+        let mut p = BigInt::from(low).sub(&BigInt::one()).next_prime();
+        for q in primes.iter() {
+            assert_eq!(p, *q);
+            p = p.next_prime();
+        }
+        assert!(p >= low + 40000);
+
+        let big = BigInt::ten().pow(30);
+        let window: Vec<BigInt> = primes_in_range(&big, &big.add(&BigInt::from(1000u32))).collect();
+        assert_eq!(big.add(&BigInt::from(57u32)), window[0]);
+        assert!(window.iter().all(BigInt::is_prime_bpsw));
+        assert!(window.windows(2).all(|w| w[0].next_prime() == w[1]));
+    }
+
+    #[test]
+    fn test_lucas_lehmer() {
+        let exponents = [2, 3, 5, 7, 13, 17, 19, 31, 61, 89, 107, 127];
+        for p in 0..200 {
+            assert_eq!(exponents.contains(&p), lucas_lehmer(p), "{}", p);
+        }
+        assert!(lucas_lehmer(521) && lucas_lehmer(607));
+        assert!(!lucas_lehmer(523));
+    }
+
+    #[test]
+    fn test_strong_lucas() {
+        // The strong Lucas pseudoprimes below 60000 for Selfridge's parameters.
+        let pseudoprimes = [
+            5459, 5777, 10877, 16109, 18971, 22499, 24569, 25199, 40309, 58519,
+        ];
+        for n in (257..60000u32).step_by(2) {
+            let expected = is_prime_naive(n) || pseudoprimes.contains(&n);
+            assert_eq!(expected, BigInt::from(n).passes_strong_lucas(), "{}", n);
+        }
+    }
+
+    #[test]
+    fn test_small_residues() {
+        let n = BigInt::from_string("1000000000000000000000000000057");
+        let mut residues = SmallResidues::new(&n);
+        residues.advance(1000);
+        let moved = n.add(&BigInt::from(1000u32));
+        for (p, r) in residues.iter() {
+            assert_eq!(moved.rem(&BigInt::from(p)), BigInt::from(r));
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_gen_prime() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(8);
+        for &bits in [2, 3, 8, 16, 17, 64, 256].iter() {
+            let p = gen_prime(&mut rng, bits);
+            assert_eq!(bits, p.bits());
+            assert!(p.is_probable_prime(54));
+        }
+        let mut seen: Vec<u32> = (0..100)
+            .map(|_| gen_prime(&mut rng, 5).to_u32().unwrap())
+            .collect();
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(vec![17, 19, 23, 29, 31], seen);
+
+        let first = gen_prime(&mut StdRng::seed_from_u64(9), 128);
+        assert_eq!(first, gen_prime(&mut StdRng::seed_from_u64(9), 128));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel_search() {
+        let start = BigInt::from(1693182318746373u64);
+        let sequential = |forward, accept: &dyn Fn(&BigInt) -> bool| {
+            (0..)
+                .find_map(|window| search_window(&start, forward, window, &accept))
+                .unwrap()
+        };
+        for &forward in [true, false].iter() {
+            assert_eq!(
+                sequential(forward, &BigInt::is_prime_bpsw),
+                search(&start, forward, BigInt::is_prime_bpsw)
+            );
+        }
+        // Only candidates past the first window are accepted, the nearest one wins.
+        let far = |c: &BigInt| c.sub(&start) >= 2 * SIEVE_WINDOW;
+        assert_eq!(sequential(true, &far), search(&start, true, far));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_gen_safe_prime() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for &bits in [10, 64, 128].iter() {
+            let p = gen_safe_prime(&mut rng, bits, 20);
+            assert_eq!(bits, p.bits());
+            assert!(p.is_probable_prime(54));
+            assert!((p.sub(&BigInt::one()) >> 1).is_probable_prime(54));
+        }
+    }
+
+    #[test]
+    fn test_trailing_zeros() {
+        assert_eq!(0, BigInt::zero().trailing_zeros());
+        assert_eq!(0, BigInt::one().trailing_zeros());
+        assert_eq!(100, (BigInt::from(3u32) << 100).trailing_zeros());
+    }
+}
@@ -0,0 +1,231 @@
+//! Random BigInts drawn from any `rand::Rng`, including `rng.gen_range(low..high)` with
+//! BigInt bounds through `UniformBigInt`.
+//!
+//! Key material should come from the `_secure` functions, which only accept generators
+//! marked `CryptoRng`. `ReproducibleRng` is the other side: seeded, repeatable, and
+//! unable to reach them.
+
+use crate::optimized_memory::BigInt;
+use rand::distributions::uniform::{SampleBorrow, SampleUniform, UniformSampler};
+use rand::rngs::StdRng;
+use rand::{CryptoRng, Rng, RngCore, SeedableRng};
+
+impl BigInt {
+    /// A uniform value in `[0, 2^bits)`.
+    pub fn random_bits<R: Rng + ?Sized>(rng: &mut R, bits: usize) -> BigInt {
+        let limbs: Vec<u32> = (0..bits.div_ceil(32)).map(|_| rng.gen()).collect();
+        BigInt::from_limbs_le(&limbs).truncate_to(bits)
+    }
+
+    /// A uniform value in `[0, bound)`, by rejection sampling among values of the same
+    /// size as `bound`, so fewer than two draws are needed on average.
+    ///
+    /// Panics if `bound` is zero.
+    pub fn random_below<R: Rng + ?Sized>(rng: &mut R, bound: &BigInt) -> BigInt {
+        assert!(!bound.is_zero(), "bound must be nonzero");
+        let bits = bound.bits();
+        loop {
+            let candidate = BigInt::random_bits(rng, bits);
+            if candidate < *bound {
+                return candidate;
+            }
+        }
+    }
+
+    /// `random_bits` for key material: only cryptographically secure generators qualify.
+    pub fn random_bits_secure<R: CryptoRng + RngCore + ?Sized>(rng: &mut R, bits: usize) -> BigInt {
+        BigInt::random_bits(rng, bits)
+    }
+
+    /// `random_below` for key material: only cryptographically secure generators qualify.
+    ///
+    /// Panics if `bound` is zero.
+    pub fn random_below_secure<R: CryptoRng + RngCore + ?Sized>(
+        rng: &mut R,
+        bound: &BigInt,
+    ) -> BigInt {
+        BigInt::random_below(rng, bound)
+    }
+}
+
+/// A seeded generator for tests and simulations: the same seed gives the same values on
+/// every platform, for a given version of `rand`.
+///
+/// It deliberately does not implement `CryptoRng`, even though the generator behind it
+/// would qualify, so that a test seed cannot end up in key material.
+#[derive(Debug, Clone)]
+pub struct ReproducibleRng(StdRng);
+
+impl RngCore for ReproducibleRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+impl SeedableRng for ReproducibleRng {
+    type Seed = <StdRng as SeedableRng>::Seed;
+
+    fn from_seed(seed: Self::Seed) -> ReproducibleRng {
+        ReproducibleRng(StdRng::from_seed(seed))
+    }
+}
+
+/// Shorthand for `ReproducibleRng::seed_from_u64(seed)`.
+pub fn reproducible_rng(seed: u64) -> ReproducibleRng {
+    ReproducibleRng::seed_from_u64(seed)
+}
+
+/// The backend of `rand::distributions::Uniform<BigInt>`: values in `[low, low + range)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformBigInt {
+    low: BigInt,
+    range: BigInt,
+}
+
+impl UniformSampler for UniformBigInt {
+    type X = BigInt;
+
+    /// Panics if `low >= high`, like the samplers of the primitive integers.
+    fn new<B1, B2>(low: B1, high: B2) -> UniformBigInt
+    where
+        B1: SampleBorrow<BigInt> + Sized,
+        B2: SampleBorrow<BigInt> + Sized,
+    {
+        let (low, high) = (low.borrow(), high.borrow());
+        assert!(low < high, "Uniform::new called with `low >= high`");
+        UniformBigInt {
+            low: low.clone(),
+            range: high.sub(low),
+        }
+    }
+
+    /// Panics if `low > high`.
+    fn new_inclusive<B1, B2>(low: B1, high: B2) -> UniformBigInt
+    where
+        B1: SampleBorrow<BigInt> + Sized,
+        B2: SampleBorrow<BigInt> + Sized,
+    {
+        let (low, high) = (low.borrow(), high.borrow());
+        assert!(
+            low <= high,
+            "Uniform::new_inclusive called with `low > high`"
+        );
+        UniformBigInt {
+            low: low.clone(),
+            range: high.sub(low).add(&BigInt::one()),
+        }
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> BigInt {
+        self.low.add(&BigInt::random_below(rng, &self.range))
+    }
+}
+
+impl SampleUniform for BigInt {
+    type Sampler = UniformBigInt;
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_random_bits() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(BigInt::zero(), BigInt::random_bits(&mut rng, 0));
+        let mut top_bit_set = false;
+        for _ in 0..100 {
+            let value = BigInt::random_bits(&mut rng, 70);
+            assert!(value.bits() <= 70);
+            top_bit_set |= value.bit(69);
+        }
+        assert!(top_bit_set);
+    }
+
+    #[test]
+    fn test_random_below() {
+        let mut rng = StdRng::seed_from_u64(2);
+        assert_eq!(
+            BigInt::zero(),
+            BigInt::random_below(&mut rng, &BigInt::one())
+        );
+
+        // Each of the 5 values shows up about 1000 times.
+        let bound = BigInt::from(5u32);
+        let mut counts = [0; 5];
+        for _ in 0..5000 {
+            counts[BigInt::random_below(&mut rng, &bound).to_usize().unwrap()] += 1;
+        }
+        assert!(counts.iter().all(|&c| c > 850 && c < 1150), "{:?}", counts);
+
+        let bound = (BigInt::one() << 200).add(&BigInt::one());
+        for _ in 0..100 {
+            assert!(BigInt::random_below(&mut rng, &bound) < bound);
+        }
+    }
+
+    #[test]
+    fn test_gen_range() {
+        use rand::distributions::{Distribution, Uniform};
+
+        let mut rng = StdRng::seed_from_u64(4);
+        let low = BigInt::one() << 100;
+        let high = low.add(&BigInt::from(3u32));
+        let mut seen = [false; 3];
+        for _ in 0..100 {
+            let value = rng.gen_range(low.clone()..high.clone());
+            assert!(value >= low && value < high);
+            seen[value.sub(&low).to_usize().unwrap()] = true;
+        }
+        assert_eq!([true; 3], seen);
+
+        assert_eq!(low, rng.gen_range(low.clone()..=low.clone()));
+        let inclusive = Uniform::new_inclusive(&low, &high);
+        assert!((0..100).any(|_| inclusive.sample(&mut rng) == high));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_gen_range_empty() {
+        let mut rng = StdRng::seed_from_u64(5);
+        rng.gen_range(BigInt::one()..BigInt::one());
+    }
+
+    #[test]
+    fn test_secure_and_reproducible() {
+        let bound = BigInt::from_string("1000000000000000000000000000000");
+        let mut secure = rand::thread_rng();
+        assert!(BigInt::random_below_secure(&mut secure, &bound) < bound);
+        assert!(BigInt::random_bits_secure(&mut StdRng::seed_from_u64(6), 80).bits() <= 80);
+
+        let first: Vec<BigInt> = (0..5)
+            .map(|_| BigInt::random_below(&mut reproducible_rng(7), &bound))
+            .collect();
+        assert!(first.windows(2).all(|w| w[0] == w[1]));
+        let mut rng = reproducible_rng(7);
+        let a = BigInt::random_below(&mut rng, &bound);
+        let b = BigInt::random_below(&mut rng, &bound);
+        assert_eq!(first[0], a);
+        assert_ne!(a, b);
+        assert_ne!(a, BigInt::random_below(&mut reproducible_rng(8), &bound));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_random_below_zero() {
+        BigInt::random_below(&mut StdRng::seed_from_u64(3), &BigInt::zero());
+    }
+}
@@ -0,0 +1,207 @@
+//! Educational RSA key generation and decryption with the Chinese Remainder Theorem.
+//!
+//! Nothing here runs in constant time or pads messages: use it to learn or to test, never
+//! to protect real data.
+
+use crate::optimized_memory::BigInt;
+use crate::prime::gen_prime;
+use rand::{CryptoRng, RngCore};
+
+/// The smallest size `gen_prime_pair` accepts.
+const MIN_PRIME_BITS: usize = 5;
+
+/// The values that let decryption work modulo `p` and `q` separately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrtParams {
+    /// `d mod (p - 1)`.
+    pub dp: BigInt,
+    /// `d mod (q - 1)`.
+    pub dq: BigInt,
+    /// `q^-1 mod p`.
+    pub qinv: BigInt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RsaKey {
+    pub n: BigInt,
+    pub e: BigInt,
+    pub d: BigInt,
+    pub p: BigInt,
+    pub q: BigInt,
+    pub crt: CrtParams,
+}
+
+impl RsaKey {
+    /// Generate a key whose modulus has exactly `bits` bits, retrying until `e` is
+    /// invertible. The primes are only as unpredictable as `rng`, hence the `CryptoRng`
+    /// bound.
+    ///
+    /// Panics if `bits` is smaller than 10 or odd, or if `e` is not an odd number greater
+    /// than 1, since no such `e` is ever invertible.
+    pub fn generate<R: CryptoRng + RngCore + ?Sized>(
+        rng: &mut R,
+        bits: usize,
+        e: &BigInt,
+    ) -> RsaKey {
+        assert!(
+            bits >= 2 * MIN_PRIME_BITS && bits.is_multiple_of(2),
+            "bits must be even and at least 10"
+        );
+        assert!(
+            e.bit(0) && *e > 1u32,
+            "the public exponent must be odd and greater than 1"
+        );
+        loop {
+            let (p, q) = gen_prime_pair(rng, bits / 2);
+            if let Some(d) = private_exponent(e, &p, &q) {
+                let crt = crt_params(&d, &p, &q);
+                return RsaKey {
+                    n: p.mul(&q),
+                    e: e.clone(),
+                    d,
+                    p,
+                    q,
+                    crt,
+                };
+            }
+        }
+    }
+
+    /// `m^e mod n`.
+    pub fn encrypt(&self, m: &BigInt) -> BigInt {
+        m.modpow(&self.e, &self.n)
+    }
+
+    /// `c^d mod n`, computed modulo `p` and `q`.
+    pub fn decrypt(&self, c: &BigInt) -> BigInt {
+        decrypt_crt(c, &self.p, &self.q, &self.crt)
+    }
+}
+
+/// Two distinct primes of exactly `bits` bits each. The top two bits are set, so their
+/// product has exactly `2 * bits` bits.
+///
+/// Panics if `bits` is smaller than 5: with the top two bits and the low bit fixed there
+/// is only one candidate of 3 bits (7) and one prime of 4 bits (13).
+pub fn gen_prime_pair<R: CryptoRng + RngCore + ?Sized>(
+    rng: &mut R,
+    bits: usize,
+) -> (BigInt, BigInt) {
+    assert!(bits >= MIN_PRIME_BITS, "bits must be at least 5");
+    let p = random_prime(rng, bits);
+    loop {
+        let q = random_prime(rng, bits);
+        if q != p {
+            return (p, q);
+        }
+    }
+}
+
+/// `e^-1 mod lcm(p - 1, q - 1)`, or `None` if `e` is not invertible.
+pub fn private_exponent(e: &BigInt, p: &BigInt, q: &BigInt) -> Option<BigInt> {
+    let one = BigInt::one();
+    let lambda = p.sub(&one).lcm(&q.sub(&one));
+    e.mod_inverse(&lambda)
+}
+
+/// Panics if `q` is not invertible modulo `p`, which means `p` and `q` are not distinct
+/// primes.
+pub fn crt_params(d: &BigInt, p: &BigInt, q: &BigInt) -> CrtParams {
+    let one = BigInt::one();
+    CrtParams {
+        dp: d.rem(&p.sub(&one)),
+        dq: d.rem(&q.sub(&one)),
+        qinv: q.mod_inverse(p).expect("p and q must be distinct primes"),
+    }
+}
+
+/// Garner's recombination: `m = m2 + q * (qinv * (m1 - m2) mod p)`, where `m1` and
+/// `m2` are the decryptions modulo `p` and `q`.
+pub fn decrypt_crt(c: &BigInt, p: &BigInt, q: &BigInt, params: &CrtParams) -> BigInt {
+    let m1 = c.modpow(&params.dp, p);
+    let m2 = c.modpow(&params.dq, q);
+    // m1 - m2 modulo p, without going negative.
+    let difference = m1.add(p).sub(&m2.rem(p)).rem(p);
+    let h = params.qinv.mul(&difference).rem(p);
+    m2.add(&q.mul(&h))
+}
+
+/// Helper function for `gen_prime_pair`: primes from `gen_prime` until one has its second
+/// highest bit set too.
+fn random_prime<R: CryptoRng + RngCore + ?Sized>(rng: &mut R, bits: usize) -> BigInt {
+    loop {
+        let prime = gen_prime(rng, bits);
+        if prime.bit(bits - 2) {
+            return prime;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_gen_prime_pair() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for &bits in [5, 16, 64, 100].iter() {
+            let (p, q) = gen_prime_pair(&mut rng, bits);
+            assert_ne!(p, q);
+            assert_eq!(bits, p.bits());
+            assert_eq!(bits, q.bits());
+            assert_eq!(2 * bits, p.mul(&q).bits());
+            assert!(p.is_probable_prime(54) && q.is_probable_prime(54));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_gen_prime_pair_too_small() {
+        gen_prime_pair(&mut StdRng::seed_from_u64(1), 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_generate_even_exponent() {
+        RsaKey::generate(&mut StdRng::seed_from_u64(1), 64, &BigInt::from(4u32));
+    }
+
+    #[test]
+    fn test_textbook_key() {
+        // The classic example: p = 61, q = 53, e = 17.
+        let p = BigInt::from(61u32);
+        let q = BigInt::from(53u32);
+        let e = BigInt::from(17u32);
+        let d = private_exponent(&e, &p, &q).unwrap();
+        assert_eq!(BigInt::from(413u32), d);
+        let params = crt_params(&d, &p, &q);
+        assert_eq!(BigInt::from(53u32), params.dp);
+        assert_eq!(BigInt::from(49u32), params.dq);
+        assert_eq!(BigInt::from(38u32), params.qinv);
+        let n = p.mul(&q);
+        let c = BigInt::from(65u32).modpow(&e, &n);
+        assert_eq!(BigInt::from(2790u32), c);
+        assert_eq!(BigInt::from(65u32), decrypt_crt(&c, &p, &q, &params));
+        assert_eq!(
+            None,
+            private_exponent(&BigInt::from(3u32), &BigInt::from(7u32), &q)
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let key = RsaKey::generate(&mut rng, 256, &BigInt::from(65537u32));
+        assert_eq!(256, key.n.bits());
+        let m = BigInt::from_string("123456789012345678901234567890");
+        let c = key.encrypt(&m);
+        assert_ne!(m, c);
+        assert_eq!(m, key.decrypt(&c));
+        assert_eq!(m, c.modpow(&key.d, &key.n));
+        let small = RsaKey::generate(&mut rng, 10, &BigInt::from(11u32));
+        assert_eq!(10, small.n.bits());
+    }
+}
@@ -0,0 +1,174 @@
+//! Binary splitting for series whose terms have rational ratios, the way constants such
+//! as `e` and `pi` are computed to millions of digits.
+//!
+//! The sum of `a(k) p(0) ... p(k) / (q(0) ... q(k))` over `k < n` is split in halves down
+//! to single terms, and the halves are combined with a few multiplications of integers
+//! of similar size, so there is a single division at the very end.
+
+use crate::optimized_memory::BigInt as Magnitude;
+use crate::signed::BigInt;
+
+/// Digits computed beyond those requested, so that truncating the partial sum of the
+/// series cannot change them, except after a run of this many nines.
+const GUARD_DIGITS: u64 = 10;
+
+/// Each term of Chudnovsky's series adds this many digits of `pi`, a little less than
+/// `log10(640320^3 / 1728)`.
+const CHUDNOVSKY_DIGITS_PER_TERM: u64 = 14;
+
+/// The sum of `a(k) p(0) ... p(k) / (q(0) ... q(k))` for `k < terms`, as a numerator
+/// and a denominator `(t, q)` which are not reduced. No terms give `(0, 1)`.
+pub fn binary_split<A, P, Q>(a: A, p: P, q: Q, terms: u64) -> (BigInt, BigInt)
+where
+    A: Fn(u64) -> BigInt,
+    P: Fn(u64) -> BigInt,
+    Q: Fn(u64) -> BigInt,
+{
+    if terms == 0 {
+        return (BigInt::zero(), BigInt::one());
+    }
+    let (_, q, t) = split(&a, &p, &q, 0, terms);
+    (t, q)
+}
+
+/// Helper function for `binary_split`: `(P, Q, T)` for the terms in `low..high`, with `P`
+/// and `Q` the products of the `p(k)` and `q(k)`, and `T / Q` their sum with the products
+/// starting at `low`.
+fn split<A, P, Q>(a: &A, p: &P, q: &Q, low: u64, high: u64) -> (BigInt, BigInt, BigInt)
+where
+    A: Fn(u64) -> BigInt,
+    P: Fn(u64) -> BigInt,
+    Q: Fn(u64) -> BigInt,
+{
+    if high - low == 1 {
+        let pk = p(low);
+        let t = a(low).mul(&pk);
+        return (pk, q(low), t);
+    }
+    let middle = low + (high - low) / 2;
+    let (p_left, q_left, t_left) = split(a, p, q, low, middle);
+    let (p_right, q_right, t_right) = split(a, p, q, middle, high);
+    let t = t_left.mul(&q_right).add(&p_left.mul(&t_right));
+    (p_left.mul(&p_right), q_left.mul(&q_right), t)
+}
+
+/// `e` with `n` digits after the decimal point, truncated, from the series of `1 / k!`.
+pub fn e_digits(n: u64) -> String {
+    // The first omitted term, 1 / terms!, must be below 10^-(n + GUARD_DIGITS).
+    let mut terms = 1;
+    let mut log10_factorial = 0.0;
+    while log10_factorial <= (n + GUARD_DIGITS) as f64 {
+        log10_factorial += (terms as f64).log10();
+        terms += 1;
+    }
+    let (t, q) = binary_split(
+        |_| BigInt::one(),
+        |_| BigInt::one(),
+        |k| BigInt::from(k.max(1) as i128),
+        terms,
+    );
+    let scale = Magnitude::ten().pow((n + GUARD_DIGITS) as u32);
+    let scaled = t.magnitude().mul(&scale).div(q.magnitude());
+    fixed_point(&scaled, n)
+}
+
+/// `pi` with `n` digits after the decimal point, truncated, by Chudnovsky's series
+/// `1 / pi = 12 sum (-1)^k (6k)! (13591409 + 545140134 k) / ((3k)! k!^3 640320^(3k + 3/2))`.
+pub fn pi_digits(n: u64) -> String {
+    let terms = (n + GUARD_DIGITS) / CHUDNOVSKY_DIGITS_PER_TERM + 2;
+    // 640320^3 / 24, so that the ratio of consecutive terms is p(k) / q(k).
+    let c = Magnitude::from(10939058860032000u64);
+    let (t, q) = binary_split(
+        |k| BigInt::from(13591409 + 545140134 * k as i128),
+        |k| match k {
+            0 => BigInt::one(),
+            k => {
+                let k = k as i128;
+                BigInt::from(-(6 * k - 5) * (2 * k - 1) * (6 * k - 1))
+            }
+        },
+        |k| match k {
+            0 => BigInt::one(),
+            k => BigInt::from(Magnitude::from(k).pow(3).mul(&c)),
+        },
+        terms,
+    );
+    // pi = 426880 sqrt(10005) q / t.
+    let scale = Magnitude::ten().pow((n + GUARD_DIGITS) as u32);
+    let root = scale.mul(&scale).mul(&Magnitude::from(10005u32)).sqrt();
+    let scaled = root
+        .mul(&Magnitude::from(426880u32))
+        .mul(q.magnitude())
+        .div(t.magnitude());
+    fixed_point(&scaled, n)
+}
+
+/// Helper function for `e_digits` and `pi_digits`: `value / 10^GUARD_DIGITS` written with
+/// `n` digits after the decimal point.
+fn fixed_point(value: &Magnitude, n: u64) -> String {
+    let digits = value
+        .div(&Magnitude::ten().pow(GUARD_DIGITS as u32))
+        .to_string();
+    let point = digits.len() - n as usize;
+    if n == 0 {
+        digits
+    } else {
+        format!("{}.{}", &digits[..point], &digits[point..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const E_100: &str = "2.7182818284590452353602874713526624977572470936999595749669676277240766303535475945713821785251664274";
+    const PI_100: &str = "3.1415926535897932384626433832795028841971693993751058209749445923078164062862089986280348253421170679";
+
+    #[test]
+    fn test_binary_split() {
+        assert_eq!(
+            (BigInt::zero(), BigInt::one()),
+            binary_split(|_| BigInt::one(), |_| BigInt::one(), |_| BigInt::one(), 0)
+        );
+        // The sum of 2^-k for k < 10 is 1023/512.
+        let (t, q) = binary_split(
+            |_| BigInt::one(),
+            |_| BigInt::one(),
+            |k| BigInt::from(if k == 0 { 1 } else { 2 }),
+            10,
+        );
+        assert_eq!(
+            Magnitude::from(1023u32).mul(q.magnitude()),
+            t.magnitude().mul(&Magnitude::from(512u32))
+        );
+        // An alternating series: 1 - 1/3 + 1/9 - 1/27 = 20/27.
+        let (t, q) = binary_split(
+            |_| BigInt::one(),
+            |k| BigInt::from(if k == 0 { 1 } else { -1 }),
+            |k| BigInt::from(if k == 0 { 1 } else { 3 }),
+            4,
+        );
+        assert_eq!(t.mul(&BigInt::from(27)), q.mul(&BigInt::from(20)));
+    }
+
+    #[test]
+    fn test_e_digits() {
+        assert_eq!("2", e_digits(0));
+        assert_eq!("2.7", e_digits(1));
+        assert_eq!(E_100, e_digits(100));
+        assert!(e_digits(2000).starts_with(E_100));
+    }
+
+    #[test]
+    fn test_pi_digits() {
+        assert_eq!("3", pi_digits(0));
+        assert_eq!("3.14", pi_digits(2));
+        assert_eq!(PI_100, pi_digits(100));
+        let many = pi_digits(2000);
+        assert!(many.starts_with(PI_100));
+        assert_eq!(2002, many.len());
+        // Digits 762 to 767 after the point are the Feynman point, six nines.
+        assert_eq!("999999", &many[763..769]);
+    }
+}
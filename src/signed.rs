@@ -0,0 +1,500 @@
+//! Signed arbitrary precision integer, stored as a sign and an `optimized_memory::BigInt`
+//! magnitude. All operations are derived from the unsigned ones.
+
+use crate::optimized_memory::{BigInt as Magnitude, TryFromBigIntError};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Neg;
+
+/// A signed integer. Zero is never negative, so derived equality and hashing are exact.
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Magnitude,
+}
+
+impl BigInt {
+    pub fn zero() -> BigInt {
+        BigInt {
+            negative: false,
+            magnitude: Magnitude::zero(),
+        }
+    }
+
+    pub fn one() -> BigInt {
+        BigInt::from(Magnitude::one())
+    }
+
+    /// Build a value from its sign and magnitude. The sign of zero is ignored.
+    pub fn new(negative: bool, magnitude: Magnitude) -> BigInt {
+        BigInt {
+            negative: negative && !magnitude.is_zero(),
+            magnitude,
+        }
+    }
+
+    /// Convert a decimal string, with an optional leading `-` or `+`, to BigInt.
+    pub fn from_string(s: &str) -> BigInt {
+        if let Some(digits) = s.strip_prefix('-') {
+            BigInt::new(true, Magnitude::from_string(digits))
+        } else {
+            BigInt::from(Magnitude::from_string(s.strip_prefix('+').unwrap_or(s)))
+        }
+    }
+
+    pub fn magnitude(&self) -> &Magnitude {
+        &self.magnitude
+    }
+
+    pub fn into_magnitude(self) -> Magnitude {
+        self.magnitude
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_positive(&self) -> bool {
+        !self.negative && !self.is_zero()
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::from(self.magnitude.clone())
+    }
+
+    /// -1, 0 or 1 according to the sign of the value.
+    pub fn signum(&self) -> i32 {
+        if self.negative {
+            -1
+        } else if self.is_zero() {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Bytes allocated on the heap for the magnitude, spare capacity included.
+    pub fn heap_size(&self) -> usize {
+        self.magnitude.heap_size()
+    }
+
+    /// Compare the absolute values, ignoring the signs.
+    pub fn cmp_abs(&self, other: &BigInt) -> Ordering {
+        self.magnitude.cmp(&other.magnitude)
+    }
+
+    pub fn add(&self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, self.magnitude.add(&other.magnitude));
+        }
+        match self.cmp_abs(other) {
+            Ordering::Less => BigInt::new(other.negative, other.magnitude.sub(&self.magnitude)),
+            _ => BigInt::new(self.negative, self.magnitude.sub(&other.magnitude)),
+        }
+    }
+
+    pub fn sub(&self, other: &BigInt) -> BigInt {
+        self.add(&-other)
+    }
+
+    pub fn mul(&self, other: &BigInt) -> BigInt {
+        BigInt::new(
+            self.negative != other.negative,
+            self.magnitude.mul(&other.magnitude),
+        )
+    }
+
+    /// Quotient rounded towards zero and remainder with the sign of `self`, like the `/`
+    /// and `%` operators on primitive integers.
+    ///
+    /// Panics if `other` is zero.
+    pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+        let (quotient, remainder) = self.magnitude.div_rem(&other.magnitude);
+        (
+            BigInt::new(self.negative != other.negative, quotient),
+            BigInt::new(self.negative, remainder),
+        )
+    }
+
+    /// Panics if `other` is zero.
+    pub fn div(&self, other: &BigInt) -> BigInt {
+        self.div_rem(other).0
+    }
+
+    /// Panics if `other` is zero.
+    pub fn rem(&self, other: &BigInt) -> BigInt {
+        self.div_rem(other).1
+    }
+
+    /// Euclidean division: the remainder is always non-negative, like `div_euclid` and
+    /// `rem_euclid` on primitive integers.
+    ///
+    /// Panics if `other` is zero.
+    pub fn div_rem_euclid(&self, other: &BigInt) -> (BigInt, BigInt) {
+        let (quotient, remainder) = self.div_rem(other);
+        if !remainder.negative {
+            return (quotient, remainder);
+        }
+        if other.negative {
+            (quotient.add(&BigInt::one()), remainder.sub(other))
+        } else {
+            (quotient.sub(&BigInt::one()), remainder.add(other))
+        }
+    }
+
+    /// Panics if `other` is zero.
+    pub fn div_euclid(&self, other: &BigInt) -> BigInt {
+        self.div_rem_euclid(other).0
+    }
+
+    /// Panics if `other` is zero.
+    pub fn rem_euclid(&self, other: &BigInt) -> BigInt {
+        self.div_rem_euclid(other).1
+    }
+}
+
+impl From<Magnitude> for BigInt {
+    fn from(magnitude: Magnitude) -> BigInt {
+        BigInt::new(false, magnitude)
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(n: i128) -> BigInt {
+        BigInt::new(n < 0, Magnitude::from(n.unsigned_abs()))
+    }
+}
+
+/// Implement `From` for signed primitives narrower than i128.
+macro_rules! impl_from_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for BigInt {
+                fn from(n: $t) -> BigInt {
+                    BigInt::from(n as i128)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_primitive!(i8, i16, i32, i64, isize);
+
+impl TryFrom<&BigInt> for i128 {
+    type Error = TryFromBigIntError;
+
+    fn try_from(b: &BigInt) -> Result<i128, TryFromBigIntError> {
+        let error = TryFromBigIntError { target: "i128" };
+        let magnitude = b.magnitude.to_u128().ok_or(error.clone())?;
+        if b.negative {
+            if magnitude > i128::MIN.unsigned_abs() {
+                return Err(error);
+            }
+            Ok((magnitude as i128).wrapping_neg())
+        } else {
+            i128::try_from(magnitude).map_err(|_| error)
+        }
+    }
+}
+
+/// Implement `TryFrom<&BigInt>` for signed primitives narrower than i128.
+macro_rules! impl_try_from_bigint {
+    ($($t:ident),*) => {
+        $(
+            impl TryFrom<&BigInt> for $t {
+                type Error = TryFromBigIntError;
+
+                fn try_from(b: &BigInt) -> Result<$t, TryFromBigIntError> {
+                    let error = TryFromBigIntError {
+                        target: stringify!($t),
+                    };
+                    let n = i128::try_from(b).map_err(|_| error.clone())?;
+                    $t::try_from(n).map_err(|_| error)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_bigint!(i8, i16, i32, i64, isize);
+
+impl fmt::Display for BigInt {
+    /// A precision truncates the digits like for the magnitude.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        crate::optimized_memory::write_signed_decimal(
+            self.magnitude.as_limbs_le(),
+            !self.negative,
+            f,
+        )
+    }
+}
+
+/// Values are shown in decimal, like primitive integers.
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::LowerHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(!self.negative, "0x", &format!("{:x}", self.magnitude))
+    }
+}
+
+impl fmt::UpperHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(!self.negative, "0x", &format!("{:X}", self.magnitude))
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude)
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn int(n: i64) -> BigInt {
+        BigInt::from(n)
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(BigInt::zero(), BigInt::new(true, Magnitude::zero()));
+        assert!(!BigInt::new(true, Magnitude::zero()).negative);
+        assert!(BigInt::new(true, Magnitude::one()).negative);
+        assert_eq!(&Magnitude::from(5u32), int(-5).magnitude());
+        assert_eq!(BigInt::one(), int(1));
+        assert_eq!(Magnitude::from(5u32), int(-5).into_magnitude());
+    }
+
+    #[test]
+    fn test_neg() {
+        assert_eq!(int(-5), -int(5));
+        assert_eq!(int(5), -int(-5));
+        assert_eq!(int(5), -&int(-5));
+        assert_eq!(int(-5), -&int(5));
+        assert_eq!(BigInt::zero(), -BigInt::zero());
+        assert!(!(-&BigInt::zero()).negative);
+        assert!(!(-BigInt::new(true, Magnitude::zero())).negative);
+    }
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(int(5), int(-5).abs());
+        assert_eq!(int(5), int(5).abs());
+        assert_eq!(-1, int(-5).signum());
+        assert_eq!(0, int(0).signum());
+        assert_eq!(1, int(5).signum());
+        assert!(int(-5).is_negative());
+        assert!(!int(-5).is_positive());
+        assert!(!int(0).is_negative());
+        assert!(!int(0).is_positive());
+        assert!(int(5).is_positive());
+        assert_eq!(Ordering::Greater, int(-7).cmp_abs(&int(5)));
+        assert_eq!(Ordering::Equal, int(-5).cmp_abs(&int(5)));
+        assert_eq!(Ordering::Less, int(3).cmp_abs(&int(-5)));
+    }
+
+    #[test]
+    fn test_add_sub() {
+        for a in -6..7 {
+            for b in -6..7 {
+                assert_eq!(int(a + b), int(a).add(&int(b)));
+                assert_eq!(int(a - b), int(a).sub(&int(b)));
+            }
+        }
+        let big = BigInt::from(Magnitude::from_string("18446744073709551616"));
+        assert_eq!(int(-2), int(i64::MAX).sub(&big).add(&int(i64::MAX)));
+    }
+
+    #[test]
+    fn test_mul() {
+        for a in -6..7 {
+            for b in -6..7 {
+                assert_eq!(int(a * b), int(a).mul(&int(b)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_div_rem() {
+        for a in -20..21 {
+            for b in -6..7 {
+                if b == 0 {
+                    continue;
+                }
+                assert_eq!((int(a / b), int(a % b)), int(a).div_rem(&int(b)));
+                assert_eq!(int(a / b), int(a).div(&int(b)));
+                assert_eq!(int(a % b), int(a).rem(&int(b)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_div_rem_euclid() {
+        for a in -20..21i64 {
+            for b in -6..7i64 {
+                if b == 0 {
+                    continue;
+                }
+                assert_eq!(
+                    (int(a.div_euclid(b)), int(a.rem_euclid(b))),
+                    int(a).div_rem_euclid(&int(b))
+                );
+                assert_eq!(int(a.div_euclid(b)), int(a).div_euclid(&int(b)));
+                assert_eq!(int(a.rem_euclid(b)), int(a).rem_euclid(&int(b)));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_div_by_zero() {
+        int(5).div_rem_euclid(&int(0));
+    }
+
+    #[test]
+    fn test_from_string() {
+        assert_eq!(int(0), BigInt::from_string("0"));
+        assert_eq!(int(0), BigInt::from_string("-0"));
+        assert_eq!(int(42), BigInt::from_string("42"));
+        assert_eq!(int(42), BigInt::from_string("+42"));
+        assert_eq!(int(-42), BigInt::from_string("-42"));
+        assert_eq!(
+            BigInt::new(
+                true,
+                Magnitude::from_string("42949672963434342343243324343232890890")
+            ),
+            BigInt::from_string("-42949672963434342343243324343232890890")
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("0", int(0).to_string());
+        assert_eq!("42", int(42).to_string());
+        assert_eq!("-42", int(-42).to_string());
+        assert_eq!("-42", format!("{:?}", int(-42)));
+        assert_eq!("+42", format!("{:+}", int(42)));
+        assert_eq!("-42", format!("{:+}", int(-42)));
+        assert_eq!("+0", format!("{:+}", int(0)));
+        assert_eq!("  -42", format!("{:>5}", int(-42)));
+        assert_eq!("-0042", format!("{:05}", int(-42)));
+        assert_eq!("ff", format!("{:x}", int(255)));
+        assert_eq!("-ff", format!("{:x}", int(-255)));
+        assert_eq!("-0xFF", format!("{:#X}", int(-255)));
+        assert_eq!("+0xff", format!("{:+#x}", int(255)));
+
+        let s = "-42949672963434342343243324343232890890";
+        assert_eq!(s, BigInt::from_string(s).to_string());
+        assert_eq!("-4294…e+34", format!("{:.4}", BigInt::from_string(s)));
+        assert_eq!("-42", format!("{:.4}", int(-42)));
+        assert_eq!(" -4…e+1", format!("{:>7.1}", int(-42)));
+    }
+
+    #[test]
+    fn test_from_primitive() {
+        assert_eq!(BigInt::from_string("-128"), BigInt::from(i8::MIN));
+        assert_eq!(BigInt::from_string("32767"), BigInt::from(i16::MAX));
+        assert_eq!(BigInt::from_string("-2147483648"), BigInt::from(i32::MIN));
+        assert_eq!(
+            BigInt::from_string("-9223372036854775808"),
+            BigInt::from(i64::MIN)
+        );
+        assert_eq!(
+            BigInt::from_string("-170141183460469231731687303715884105728"),
+            BigInt::from(i128::MIN)
+        );
+        assert_eq!(
+            BigInt::from_string("170141183460469231731687303715884105727"),
+            BigInt::from(i128::MAX)
+        );
+        assert_eq!(BigInt::from_string("-12"), BigInt::from(-12isize));
+        assert_eq!(BigInt::zero(), BigInt::from(0i32));
+    }
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(Ok(-42), i32::try_from(&int(-42)));
+        assert_eq!(Ok(i64::MIN), i64::try_from(&int(i64::MIN)));
+        assert_eq!(Ok(i64::MAX), i64::try_from(&int(i64::MAX)));
+        assert_eq!(Ok(-12), isize::try_from(&int(-12)));
+        assert_eq!(Ok(i128::MIN), i128::try_from(&BigInt::from(i128::MIN)));
+        assert_eq!(Ok(i128::MAX), i128::try_from(&BigInt::from(i128::MAX)));
+
+        let err = i32::try_from(&int(2147483648)).unwrap_err();
+        assert_eq!("BigInt value is too large to fit in i32", err.to_string());
+        assert!(i32::try_from(&int(-2147483649)).is_err());
+        assert!(i64::try_from(&int(i64::MIN).sub(&int(1))).is_err());
+        assert!(i64::try_from(&int(i64::MAX).add(&int(1))).is_err());
+        assert!(i128::try_from(&BigInt::from(i128::MIN).sub(&int(1))).is_err());
+        assert!(i128::try_from(&BigInt::from(i128::MAX).add(&int(1))).is_err());
+    }
+
+    #[test]
+    fn test_try_from_bounds() {
+        macro_rules! check_bounds {
+            ($($t:ident),*) => {
+                $(
+                    let min = BigInt::from($t::MIN);
+                    let max = BigInt::from($t::MAX);
+                    assert_eq!(Ok($t::MIN), $t::try_from(&min));
+                    assert_eq!(Ok($t::MAX), $t::try_from(&max));
+                    assert_eq!(Ok(0), $t::try_from(&BigInt::zero()));
+                    assert!($t::try_from(&max.add(&int(1))).is_err());
+                    assert!($t::try_from(&min.sub(&int(1))).is_err());
+                )*
+            };
+        }
+        check_bounds!(i8, i16, i32, i64, i128, isize);
+        assert_eq!(
+            "BigInt value is too large to fit in i8",
+            i8::try_from(&int(128)).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_cmp() {
+        let mut v = vec![int(3), int(-7), int(0), int(-2), int(10)];
+        v.sort();
+        assert_eq!(vec![int(-7), int(-2), int(0), int(3), int(10)], v);
+    }
+}
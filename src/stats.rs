@@ -0,0 +1,172 @@
+//! Process-wide counters of the expensive operations, behind the `tracing` feature, for
+//! finding out which algorithms a workload actually runs and on what sizes.
+//!
+//! Every thread updates the same relaxed atomic counters, so a `stats()` snapshot taken
+//! while other threads compute may mix counts from before and after an operation. Sizes
+//! are in limbs of 32 bits.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A snapshot of the counters, since the start of the process or the last `reset_stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    /// Multiplications by the built-in algorithms, schoolbook and Karatsuba.
+    pub schoolbook_muls: u64,
+    /// Multiplications computed by a registered `mul_backend::MulBackend`.
+    pub backend_muls: u64,
+    /// The sum over all multiplications of the product of the operand sizes, a measure
+    /// of the work done.
+    pub mul_limb_products: u64,
+    /// The size of the largest operand of a multiplication.
+    pub largest_mul_operand: u64,
+    /// Long divisions, `div`, `rem` and their variants included.
+    pub divisions: u64,
+    /// The size of the largest dividend.
+    pub largest_dividend: u64,
+    /// Conversions of a value to decimal, for formatting or digit utilities.
+    pub to_decimal: u64,
+    /// Conversions of a decimal string to a value.
+    pub from_decimal: u64,
+    /// Miller-Rabin rounds, one per base.
+    pub miller_rabin_rounds: u64,
+    /// Strong Lucas tests, as in `is_prime_bpsw`.
+    pub lucas_tests: u64,
+}
+
+/// The counters behind `Stats`, field by field.
+struct Counters {
+    schoolbook_muls: AtomicU64,
+    backend_muls: AtomicU64,
+    mul_limb_products: AtomicU64,
+    largest_mul_operand: AtomicU64,
+    divisions: AtomicU64,
+    largest_dividend: AtomicU64,
+    to_decimal: AtomicU64,
+    from_decimal: AtomicU64,
+    miller_rabin_rounds: AtomicU64,
+    lucas_tests: AtomicU64,
+}
+
+static COUNTERS: Counters = Counters {
+    schoolbook_muls: AtomicU64::new(0),
+    backend_muls: AtomicU64::new(0),
+    mul_limb_products: AtomicU64::new(0),
+    largest_mul_operand: AtomicU64::new(0),
+    divisions: AtomicU64::new(0),
+    largest_dividend: AtomicU64::new(0),
+    to_decimal: AtomicU64::new(0),
+    from_decimal: AtomicU64::new(0),
+    miller_rabin_rounds: AtomicU64::new(0),
+    lucas_tests: AtomicU64::new(0),
+};
+
+/// The current value of every counter.
+pub fn stats() -> Stats {
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    Stats {
+        schoolbook_muls: load(&COUNTERS.schoolbook_muls),
+        backend_muls: load(&COUNTERS.backend_muls),
+        mul_limb_products: load(&COUNTERS.mul_limb_products),
+        largest_mul_operand: load(&COUNTERS.largest_mul_operand),
+        divisions: load(&COUNTERS.divisions),
+        largest_dividend: load(&COUNTERS.largest_dividend),
+        to_decimal: load(&COUNTERS.to_decimal),
+        from_decimal: load(&COUNTERS.from_decimal),
+        miller_rabin_rounds: load(&COUNTERS.miller_rabin_rounds),
+        lucas_tests: load(&COUNTERS.lucas_tests),
+    }
+}
+
+/// Set every counter back to zero.
+pub fn reset_stats() {
+    for counter in [
+        &COUNTERS.schoolbook_muls,
+        &COUNTERS.backend_muls,
+        &COUNTERS.mul_limb_products,
+        &COUNTERS.largest_mul_operand,
+        &COUNTERS.divisions,
+        &COUNTERS.largest_dividend,
+        &COUNTERS.to_decimal,
+        &COUNTERS.from_decimal,
+        &COUNTERS.miller_rabin_rounds,
+        &COUNTERS.lucas_tests,
+    ] {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Count a multiplication of operands of `a` and `b` limbs, by the backend or not.
+pub(crate) fn record_mul(backend: bool, a: usize, b: usize) {
+    if backend {
+        COUNTERS.backend_muls.fetch_add(1, Ordering::Relaxed);
+    } else {
+        COUNTERS.schoolbook_muls.fetch_add(1, Ordering::Relaxed);
+    }
+    let work = (a as u64).saturating_mul(b as u64);
+    COUNTERS
+        .mul_limb_products
+        .fetch_add(work, Ordering::Relaxed);
+    COUNTERS
+        .largest_mul_operand
+        .fetch_max(a.max(b) as u64, Ordering::Relaxed);
+}
+
+/// Count a division of a dividend of `limbs` limbs.
+pub(crate) fn record_division(limbs: usize) {
+    COUNTERS.divisions.fetch_add(1, Ordering::Relaxed);
+    COUNTERS
+        .largest_dividend
+        .fetch_max(limbs as u64, Ordering::Relaxed);
+}
+
+/// Count a conversion to decimal.
+pub(crate) fn record_to_decimal() {
+    COUNTERS.to_decimal.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a conversion from decimal.
+pub(crate) fn record_from_decimal() {
+    COUNTERS.from_decimal.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count one Miller-Rabin round.
+pub(crate) fn record_miller_rabin_round() {
+    COUNTERS.miller_rabin_rounds.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count one strong Lucas test.
+pub(crate) fn record_lucas_test() {
+    COUNTERS.lucas_tests.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::optimized_memory::BigInt;
+
+    #[test]
+    fn test_stats() {
+        // Other tests update the counters meanwhile, so only increases are checked.
+        let before = stats();
+        let large = BigInt::one() << 4000;
+        let product = large.mul(&large);
+        let _ = product.div_rem(&BigInt::from(7u32));
+        let _ = BigInt::from_string("123456789").to_string();
+        assert!(BigInt::from_string("1000000007").is_prime_bpsw());
+        let after = stats();
+
+        assert!(
+            after.schoolbook_muls + after.backend_muls
+                > before.schoolbook_muls + before.backend_muls
+        );
+        assert!(after.mul_limb_products >= before.mul_limb_products + 126 * 126);
+        assert!(after.largest_mul_operand >= 126);
+        assert!(after.divisions > before.divisions);
+        assert!(after.largest_dividend >= 251);
+        assert!(after.to_decimal > before.to_decimal);
+        assert!(after.from_decimal > before.from_decimal);
+        assert!(after.miller_rabin_rounds > before.miller_rabin_rounds);
+        assert!(after.lucas_tests > before.lucas_tests);
+    }
+}
@@ -0,0 +1,271 @@
+//! Fixed-width unsigned integers stored on the stack, with the wrapping, checked and
+//! overflowing arithmetic of the primitive integer types.
+
+use crate::limb_ops;
+use crate::optimized_memory::{BigInt, TryFromBigIntError};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// An unsigned integer of `LIMBS` limbs in base 2^32, least significant first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Uint<const LIMBS: usize> {
+    limbs: [u32; LIMBS],
+}
+
+pub type U128 = Uint<4>;
+pub type U256 = Uint<8>;
+pub type U512 = Uint<16>;
+pub type U1024 = Uint<32>;
+
+impl<const LIMBS: usize> Uint<LIMBS> {
+    pub const ZERO: Uint<LIMBS> = Uint { limbs: [0; LIMBS] };
+    pub const ONE: Uint<LIMBS> = Uint::from_u32(1);
+    pub const MAX: Uint<LIMBS> = Uint {
+        limbs: [u32::MAX; LIMBS],
+    };
+    pub const BITS: usize = LIMBS * 32;
+
+    pub const fn from_limbs_le(limbs: [u32; LIMBS]) -> Uint<LIMBS> {
+        Uint { limbs }
+    }
+
+    /// Panics if `LIMBS` is zero and `n` is not.
+    pub const fn from_u32(n: u32) -> Uint<LIMBS> {
+        let mut limbs = [0; LIMBS];
+        if LIMBS > 0 {
+            limbs[0] = n;
+        } else if n != 0 {
+            panic!("value does not fit in Uint");
+        }
+        Uint { limbs }
+    }
+
+    /// All the limbs, including the leading zeros.
+    pub fn as_limbs_le(&self) -> &[u32; LIMBS] {
+        &self.limbs
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.iter().all(|&l| l == 0)
+    }
+
+    pub fn overflowing_add(self, other: Uint<LIMBS>) -> (Uint<LIMBS>, bool) {
+        let mut result = self;
+        let carry = limb_ops::add_n(&mut result.limbs, &other.limbs);
+        (result, carry > 0)
+    }
+
+    pub fn overflowing_sub(self, other: Uint<LIMBS>) -> (Uint<LIMBS>, bool) {
+        let mut result = self;
+        let borrow = limb_ops::sub_n(&mut result.limbs, &other.limbs);
+        (result, borrow > 0)
+    }
+
+    /// Schoolbook multiplication keeping only the low `LIMBS` limbs of the product.
+    pub fn overflowing_mul(self, other: Uint<LIMBS>) -> (Uint<LIMBS>, bool) {
+        let mut result = [0; LIMBS];
+        let mut overflow = false;
+        for i in 0..LIMBS {
+            if self.limbs[i] == 0 {
+                continue;
+            }
+            let mut carry = 0;
+            for j in 0..LIMBS {
+                let digit_product = self.limbs[i] as u64 * other.limbs[j] as u64;
+                if i + j >= LIMBS {
+                    overflow |= digit_product != 0;
+                    continue;
+                }
+                let t = result[i + j] as u64 + digit_product + carry;
+                result[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            overflow |= carry > 0;
+        }
+        (Uint { limbs: result }, overflow)
+    }
+
+    pub fn wrapping_add(self, other: Uint<LIMBS>) -> Uint<LIMBS> {
+        self.overflowing_add(other).0
+    }
+
+    pub fn wrapping_sub(self, other: Uint<LIMBS>) -> Uint<LIMBS> {
+        self.overflowing_sub(other).0
+    }
+
+    pub fn wrapping_mul(self, other: Uint<LIMBS>) -> Uint<LIMBS> {
+        self.overflowing_mul(other).0
+    }
+
+    pub fn checked_add(self, other: Uint<LIMBS>) -> Option<Uint<LIMBS>> {
+        match self.overflowing_add(other) {
+            (result, false) => Some(result),
+            (_, true) => None,
+        }
+    }
+
+    pub fn checked_sub(self, other: Uint<LIMBS>) -> Option<Uint<LIMBS>> {
+        match self.overflowing_sub(other) {
+            (result, false) => Some(result),
+            (_, true) => None,
+        }
+    }
+
+    pub fn checked_mul(self, other: Uint<LIMBS>) -> Option<Uint<LIMBS>> {
+        match self.overflowing_mul(other) {
+            (result, false) => Some(result),
+            (_, true) => None,
+        }
+    }
+
+    /// Quotient and remainder, or `None` if `other` is zero. The division itself goes
+    /// through the heap BigInt.
+    pub fn checked_div_rem(self, other: Uint<LIMBS>) -> Option<(Uint<LIMBS>, Uint<LIMBS>)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = self.to_bigint().div_rem(&other.to_bigint());
+        Some((Uint::truncate(&quotient), Uint::truncate(&remainder)))
+    }
+
+    pub fn to_bigint(&self) -> BigInt {
+        BigInt::from_limbs_le(&self.limbs)
+    }
+
+    /// The low `LIMBS` limbs of `b`, that is `b` modulo 2^(32 * LIMBS).
+    pub fn truncate(b: &BigInt) -> Uint<LIMBS> {
+        let mut limbs = [0; LIMBS];
+        for (limb, &l) in limbs.iter_mut().zip(b.as_limbs_le()) {
+            *limb = l;
+        }
+        Uint { limbs }
+    }
+}
+
+impl<const LIMBS: usize> Default for Uint<LIMBS> {
+    fn default() -> Uint<LIMBS> {
+        Uint::ZERO
+    }
+}
+
+impl<const LIMBS: usize> Ord for Uint<LIMBS> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs.iter().rev().cmp(other.limbs.iter().rev())
+    }
+}
+
+impl<const LIMBS: usize> PartialOrd for Uint<LIMBS> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const LIMBS: usize> From<Uint<LIMBS>> for BigInt {
+    fn from(u: Uint<LIMBS>) -> BigInt {
+        u.to_bigint()
+    }
+}
+
+impl<const LIMBS: usize> TryFrom<&BigInt> for Uint<LIMBS> {
+    type Error = TryFromBigIntError;
+
+    fn try_from(b: &BigInt) -> Result<Uint<LIMBS>, TryFromBigIntError> {
+        if b.as_limbs_le().len() > LIMBS {
+            return Err(TryFromBigIntError { target: "Uint" });
+        }
+        Ok(Uint::truncate(b))
+    }
+}
+
+impl<const LIMBS: usize> fmt::Display for Uint<LIMBS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_bigint(), f)
+    }
+}
+
+impl<const LIMBS: usize> fmt::LowerHex for Uint<LIMBS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.to_bigint(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn u256(s: &str) -> U256 {
+        U256::try_from(&BigInt::from_string(s)).unwrap()
+    }
+
+    #[test]
+    fn test_constants() {
+        assert!(U256::ZERO.is_zero());
+        assert_eq!(U256::ZERO, U256::default());
+        assert_eq!("1", U256::ONE.to_string());
+        assert_eq!(256, U256::BITS);
+        assert_eq!(
+            (BigInt::one() << 256).sub(&BigInt::one()),
+            U256::MAX.to_bigint()
+        );
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let a = u256("340282366920938463463374607431768211455");
+        let b = u256("1");
+        assert_eq!(
+            "340282366920938463463374607431768211456",
+            a.wrapping_add(b).to_string()
+        );
+        assert_eq!((U256::ZERO, true), U256::MAX.overflowing_add(U256::ONE));
+        assert_eq!(None, U256::MAX.checked_add(U256::ONE));
+        assert_eq!((U256::MAX, true), U256::ZERO.overflowing_sub(U256::ONE));
+        assert_eq!(None, U256::ZERO.checked_sub(U256::ONE));
+        assert_eq!(Some(a), a.wrapping_add(b).checked_sub(b));
+    }
+
+    #[test]
+    fn test_mul() {
+        let a = u256("340282366920938463463374607431768211455");
+        assert_eq!(
+            Some(a.to_bigint().mul(&a.to_bigint())),
+            a.checked_mul(a).map(BigInt::from)
+        );
+        let b = a.wrapping_add(U256::from_u32(2));
+        assert_eq!(None, b.checked_mul(b));
+        let (product, overflow) = b.overflowing_mul(b);
+        assert!(overflow);
+        assert_eq!(U256::truncate(&b.to_bigint().mul(&b.to_bigint())), product);
+        assert_eq!(U256::ONE, U256::MAX.wrapping_mul(U256::MAX));
+        assert_eq!(Some(U256::ZERO), U256::MAX.checked_mul(U256::ZERO));
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let a = u256("1000000000000000000000000000000000000000007");
+        let b = u256("1000000000000");
+        let (q, r) = a.checked_div_rem(b).unwrap();
+        assert_eq!("1000000000000000000000000000000", q.to_string());
+        assert_eq!("7", r.to_string());
+        assert_eq!(None, a.checked_div_rem(U256::ZERO));
+    }
+
+    #[test]
+    fn test_cmp() {
+        assert!(U256::ONE < U256::MAX);
+        assert!(u256("4294967296") > u256("4294967295"));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let big = BigInt::one() << 256;
+        assert!(U256::try_from(&big).is_err());
+        assert!(U512::try_from(&big).is_ok());
+        assert_eq!(U256::ZERO, U256::truncate(&big));
+        let b = BigInt::from_string("123456789012345678901234567890");
+        assert_eq!(b, BigInt::from(U256::try_from(&b).unwrap()));
+        assert_eq!("ff", format!("{:x}", U128::from_u32(255)));
+    }
+}
@@ -0,0 +1,84 @@
+//! The operations shared by the unsigned backends, `easy::BigInt` and
+//! `optimized_memory::BigInt`, so that generic code can run against either.
+//!
+//! `cmp` and `to_string` share their names with `Ord` and `ToString`: when the trait is
+//! in scope and the concrete type is known, call them as `BigUnsigned::cmp(&a, &b)`.
+
+use std::cmp::Ordering;
+
+pub trait BigUnsigned: Sized {
+    fn zero() -> Self;
+
+    /// Parse a string of decimal digits. Panics on any other character.
+    fn from_decimal(s: &str) -> Self;
+
+    fn add(&self, other: &Self) -> Self;
+
+    fn mul(&self, other: &Self) -> Self;
+
+    /// Compare the values, ignoring any difference in representation.
+    fn cmp(&self, other: &Self) -> Ordering;
+
+    /// The value in decimal.
+    fn to_string(&self) -> String;
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{easy, optimized_memory};
+    use test::Bencher;
+
+    fn factorial<B: BigUnsigned>(n: u32) -> B {
+        (1..=n).fold(B::from_decimal("1"), |acc, i| {
+            acc.mul(&B::from_decimal(&i.to_string()))
+        })
+    }
+
+    fn check_backend<B: BigUnsigned>() {
+        assert_eq!("0", B::zero().to_string());
+        assert_eq!("0", B::from_decimal("0").to_string());
+        assert_eq!("1234567890", B::from_decimal("1234567890").to_string());
+        assert_eq!("1234567890", B::from_decimal("0001234567890").to_string());
+        let a = B::from_decimal("98765432109876543210");
+        let b = B::from_decimal("12345678901234567890");
+        assert_eq!("111111111011111111100", a.add(&b).to_string());
+        assert_eq!(
+            "1219326311370217952237463801111263526900",
+            a.mul(&b).to_string()
+        );
+        assert_eq!(Ordering::Greater, a.cmp(&b));
+        assert_eq!(Ordering::Less, b.cmp(&a));
+        assert_eq!(Ordering::Equal, B::zero().cmp(&B::from_decimal("000")));
+        assert_eq!(
+            "30414093201713378043612608166064768844377641568960512000000000000",
+            factorial::<B>(50).to_string()
+        );
+    }
+
+    #[test]
+    fn test_easy() {
+        check_backend::<easy::BigInt>();
+    }
+
+    #[test]
+    fn test_optimized_memory() {
+        check_backend::<optimized_memory::BigInt>();
+    }
+
+    #[test]
+    fn test_selected_backend() {
+        check_backend::<crate::BigInt>();
+    }
+
+    #[bench]
+    fn bench_factorial_easy(b: &mut Bencher) {
+        b.iter(|| factorial::<easy::BigInt>(30))
+    }
+
+    #[bench]
+    fn bench_factorial_optimized_memory(b: &mut Bencher) {
+        b.iter(|| factorial::<optimized_memory::BigInt>(30))
+    }
+}